@@ -2,112 +2,426 @@
 //! 
 //! This module handles the CLI argument parsing and main application logic.
 
-use anyhow::Result;
-use std::io::{Read, Write};
-
-use crate::generator::xlsx_type0::XlsxType0Generator;
-use crate::generator::xlsx_type1::XlsxType1Generator;
-use crate::generator::xlsx_type2::XlsxType2Generator;
-use crate::generator::xlsx_type3::XlsxType3Generator;
-use crate::generator::xlsx_type4::XlsxType4Generator;
-use crate::generator::xlsx_type5::XlsxType5Generator;
-use crate::generator::GeneratorOptions;
-use crate::parser::dir_tree::DirTreeParser;
-use crate::parser::html_list::HtmlListParser;
-use crate::parser::mspdi::MspdiParser;
-use crate::parser::opml::OpmlParser;
-use crate::parser::simple_text::SimpleTextParser;
-use crate::parser::ParserOptions;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+
+use crate::diff::diff_outlines;
+use crate::generator::xlsx_diff::XlsxDiffGenerator;
+use crate::generator::xlsx_workbook::{split_top_level, SheetSplit, XlsxWorkbookGenerator};
+use crate::generator::{Generator, XlsxGenerator, XlsxStreamGenerator};
+use crate::merge::{merge_outlines, MergeMode};
+use crate::outline::{Outline, OutlineSummary};
+use crate::parser::error::ParseWarning;
+use crate::parser::{Parser, StreamParser};
+use crate::transform::{ItemTransform, Transform};
 use rust_xlsxwriter::Workbook;
 
+/// An input file and the parser that reads it.
+pub struct ConversionInput<'a> {
+    /// The input file; `None` or `-` reads stdin.
+    pub path: Option<String>,
+    pub parser: &'a dyn Parser,
+}
+
+impl ConversionInput<'_> {
+    fn path(&self) -> Option<&Path> {
+        match &self.path {
+            Some(path) if path != "-" => Some(Path::new(path)),
+            _ => None,
+        }
+    }
+
+    /// Returns the file name of the input, or `<stdin>`.
+    pub fn name(&self) -> String {
+        self.path()
+            .map(|path| {
+                path.file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .unwrap_or_else(|| "<stdin>".to_string())
+    }
+}
+
+/// Parses each of `inputs` with its parser, passing the warnings about ambiguous lines of
+/// the inputs to `on_warning`.
+///
+/// # Returns
+///
+/// The name of each input (see [`ConversionInput::name`]) and its outline, in order.
+pub fn parse_inputs(
+    inputs: &[ConversionInput],
+    on_warning: &mut dyn FnMut(ParseWarning),
+) -> Result<Vec<(String, Outline)>> {
+    inputs
+        .iter()
+        .map(|input| {
+            let (outline, warnings) = input.parser.parse_input_with_warnings(input.path())?;
+            warnings.into_iter().for_each(&mut *on_warning);
+            Ok((input.name(), outline))
+        })
+        .collect()
+}
+
+/// Parses `inputs`, merges them with `merge_mode` if there are several, applies `transforms`
+/// in order and writes the document built by `generator_factory`.
+///
+/// Warnings about ambiguous lines of the inputs are passed to `on_warning`.
 pub fn run_conversion(
-    input_path_option: &Option<String>,
+    inputs: &[ConversionInput],
+    merge_mode: MergeMode,
     output_writer: &mut dyn Write,
-    from_options: ParserOptions,
-    to_options: GeneratorOptions,
+    transforms: &[Box<dyn Transform>],
+    generator_factory: &dyn Fn(Outline) -> Result<Box<dyn Generator>>,
+    on_warning: &mut dyn FnMut(ParseWarning),
 ) -> Result<()> {
-    let outline = match from_options {
-        ParserOptions::DirTree(options) => {
-            let path = match input_path_option {
-                Some(p) => std::path::PathBuf::from(p),
-                None => anyhow::bail!("Input path is required for dir_tree parser."),
-            };
-            if !path.is_dir() {
-                anyhow::bail!(
-                    "Input path '{}' is not a valid directory for dir_tree parser.",
-                    path.display()
-                );
-            }
-            let parser = DirTreeParser::new(options);
-            parser.parse(&path)?
-        }
-        ParserOptions::SimpleText(_)
-        | ParserOptions::HtmlList(_)
-        | ParserOptions::Mspdi(_)
-        | ParserOptions::Opml(_) => {
-            let input_content = match input_path_option {
-                Some(path) if path != "-" => std::fs::read_to_string(path)?,
-                _ => {
-                    let mut buf = String::new();
-                    std::io::stdin().read_to_string(&mut buf)?;
-                    buf
-                }
-            };
-
-            match from_options {
-                ParserOptions::SimpleText(options) => {
-                    let parser = SimpleTextParser::new(options);
-                    parser.parse(&input_content)?
-                }
-                ParserOptions::HtmlList(options) => {
-                    let parser = HtmlListParser::new(options);
-                    parser.parse(&input_content)?
-                }
-                ParserOptions::Mspdi(options) => {
-                    let parser = MspdiParser::new(options);
-                    parser.parse(&input_content)?
-                }
-                ParserOptions::Opml(options) => {
-                    let parser = OpmlParser::new(options);
-                    parser.parse(&input_content)?
-                }
-                _ => unreachable!(),
-            }
-        }
+    let mut outlines = parse_inputs(inputs, on_warning)?;
+    let mut outline = if outlines.len() == 1 {
+        outlines.remove(0).1
+    } else {
+        merge_outlines(outlines, merge_mode)
     };
+    for transform in transforms {
+        outline = transform.apply(outline)?;
+    }
 
-    let mut workbook = Workbook::new();
-    let worksheet = workbook.add_worksheet();
+    let generator = generator_factory(outline)?;
+    generator.output(output_writer)?;
 
-    match to_options {
-        GeneratorOptions::XlsxType0(options) => {
-            let generator = XlsxType0Generator::new(outline, options);
-            generator.output_to_worksheet(worksheet)?;
-        }
-        GeneratorOptions::XlsxType1(options) => {
-            let generator = XlsxType1Generator::new(outline, options);
-            generator.output_to_worksheet(worksheet)?;
-        }
-        GeneratorOptions::XlsxType2(options) => {
-            let generator = XlsxType2Generator::new(outline, options);
-            generator.output_to_worksheet(worksheet)?;
+    Ok(())
+}
+
+/// Builds the generator of a worksheet for a layout name and an outline.
+pub type LayoutFactory<'a> = dyn Fn(&str, Outline) -> Result<Box<dyn XlsxGenerator>> + 'a;
+
+/// Parses `inputs` and writes a workbook with a worksheet for each part of the outline and
+/// each of `layouts`.
+///
+/// The outline is split according to `split`: one part per input file (each with
+/// `transforms` applied), or one part per level-1 item of the merged and transformed
+/// outline. Without `split`, the merged outline is a single part. `xlsx_generator_factory`
+/// builds the generator of a part for a layout name.
+///
+/// Worksheets are named after the part when there is a single layout, after the layout when
+/// there is a single part, and `part (layout)` otherwise; see
+/// [`crate::generator::xlsx_workbook::sheet_names`] for how names are made valid.
+/// Warnings about ambiguous lines of the inputs are passed to `on_warning`.
+#[allow(clippy::too_many_arguments)]
+pub fn run_workbook_conversion(
+    inputs: &[ConversionInput],
+    merge_mode: MergeMode,
+    split: Option<SheetSplit>,
+    layouts: &[String],
+    output_writer: &mut dyn Write,
+    transforms: &[Box<dyn Transform>],
+    xlsx_generator_factory: &LayoutFactory,
+    on_warning: &mut dyn FnMut(ParseWarning),
+) -> Result<()> {
+    anyhow::ensure!(!layouts.is_empty(), "No worksheet layout given");
+    let apply_transforms = |mut outline: Outline| -> Result<Outline> {
+        for transform in transforms {
+            outline = transform.apply(outline)?;
         }
-        GeneratorOptions::XlsxType3(options) => {
-            let generator = XlsxType3Generator::new(outline, options);
-            generator.output_to_worksheet(worksheet)?;
+        Ok(outline)
+    };
+
+    let mut outlines = parse_inputs(inputs, on_warning)?;
+    let parts = if split == Some(SheetSplit::Input) {
+        outlines
+            .into_iter()
+            .map(|(name, outline)| Ok((name, apply_transforms(outline)?)))
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        let outline = apply_transforms(if outlines.len() == 1 {
+            outlines.remove(0).1
+        } else {
+            merge_outlines(outlines, merge_mode)
+        })?;
+        match split {
+            Some(SheetSplit::TopLevel) => split_top_level(outline),
+            _ => vec![("Sheet1".to_string(), outline)],
         }
-        GeneratorOptions::XlsxType4(options) => {
-            let generator = XlsxType4Generator::new(outline, options);
-            generator.output_to_worksheet(worksheet)?;
+    };
+
+    let part_count = parts.len();
+    let mut sheets = Vec::new();
+    for (part_name, outline) in parts {
+        for layout in layouts {
+            let name = match (part_count, layouts.len()) {
+                (_, 1) => part_name.clone(),
+                (1, _) => layout.clone(),
+                _ => format!("{} ({})", part_name, layout),
+            };
+            sheets.push((name, xlsx_generator_factory(layout, outline.clone())?));
         }
-        GeneratorOptions::XlsxType5(options) => {
-            let generator = XlsxType5Generator::new(outline, options);
-            generator.output_to_worksheet(worksheet)?;
+    }
+    XlsxWorkbookGenerator::new(sheets).output(output_writer)?;
+
+    Ok(())
+}
+
+/// Parses `old` and `new`, two revisions of an outline, applies `transforms` to each and
+/// writes their diff (see [`diff_outlines`]) with each of `layouts`, filling added, removed,
+/// moved and changed rows.
+///
+/// A single layout is written to a single worksheet; several layouts to one worksheet each,
+/// named after the layout. The plain-text summary of the diff is written to `summary_writer`,
+/// and warnings about ambiguous lines of the inputs are passed to `on_warning`.
+#[allow(clippy::too_many_arguments)]
+pub fn run_diff_conversion(
+    old: &ConversionInput,
+    new: &ConversionInput,
+    layouts: &[String],
+    output_writer: &mut dyn Write,
+    summary_writer: &mut dyn Write,
+    transforms: &[Box<dyn Transform>],
+    xlsx_generator_factory: &LayoutFactory,
+    on_warning: &mut dyn FnMut(ParseWarning),
+) -> Result<()> {
+    anyhow::ensure!(!layouts.is_empty(), "No worksheet layout given");
+    let mut parse = |input: &ConversionInput| -> Result<Outline> {
+        let (mut outline, warnings) = input.parser.parse_input_with_warnings(input.path())?;
+        warnings.into_iter().for_each(&mut *on_warning);
+        for transform in transforms {
+            outline = transform.apply(outline)?;
         }
+        Ok(outline)
     };
+    let diff = diff_outlines(&parse(old)?, &parse(new)?);
+
+    let mut sheets: Vec<(String, Box<dyn XlsxGenerator>)> = Vec::new();
+    for layout in layouts {
+        let generator = xlsx_generator_factory(layout, diff.outline.clone())?;
+        sheets.push((
+            layout.clone(),
+            Box::new(
+                XlsxDiffGenerator::new(&diff.outline, generator)
+                    .with_context(|| format!("Cannot write the diff with layout {}", layout))?,
+            ),
+        ));
+    }
+    if sheets.len() == 1 {
+        sheets.remove(0).1.output(output_writer)?;
+    } else {
+        XlsxWorkbookGenerator::new(sheets).output(output_writer)?;
+    }
+    write!(summary_writer, "{}", diff)?;
 
-    let buffer = workbook.save_to_buffer()?;
-    output_writer.write_all(&buffer)?;
+    Ok(())
+}
+
+/// Converts the input file at `input_path` to the XLSX file at `output_path` without
+/// holding the outline in memory.
+///
+/// The input is parsed twice: once to gather the `OutlineSummary` the layout is sized
+/// from, and once to write each item, after applying `item_transforms` in order, to a
+/// constant-memory worksheet built by `generator_factory`. Warnings about ambiguous lines
+/// of the input are passed to `on_warning` once, from the first pass.
+pub fn run_streaming_conversion(
+    input_path: &Path,
+    output_path: &Path,
+    parser: &dyn StreamParser,
+    item_transforms: &[Box<dyn ItemTransform>],
+    generator_factory: &dyn Fn(Outline, OutlineSummary) -> Result<Box<dyn XlsxStreamGenerator>>,
+    on_warning: &mut dyn FnMut(ParseWarning),
+) -> Result<()> {
+    let mut summary = OutlineSummary::default();
+    let (header, warnings) = parser.parse_stream_with_warnings(input_path, &mut |item| {
+        summary.add_item(&item);
+        Ok(())
+    })?;
+    warnings.into_iter().for_each(on_warning);
+    summary.add_header(&header);
+
+    let mut generator = generator_factory(header.clone(), summary)?;
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet_with_constant_memory();
+    generator.start(worksheet)?;
+    parser.parse_stream(input_path, &mut |mut item| {
+        for transform in item_transforms {
+            item = transform.apply_item(&header, item)?;
+        }
+        generator.write_item(worksheet, &item)
+    })?;
+    workbook.save(output_path)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::xlsx_type0::{XlsxType0Generator, XlsxType0GeneratorOptions};
+    use crate::generator::xlsx_type2::{
+        XlsxType2Generator, XlsxType2GeneratorOptions, XlsxType2StreamGenerator,
+    };
+    use crate::generator::IntegrateCellsOption;
+    use crate::parser::simple_text::{SimpleTextParser, SimpleTextParserOptions};
+    use crate::transform::value_type::{ValueTypeTransform, ValueTypeTransformOptions};
+    use crate::value::ValueType;
+    use std::fs::File;
+    use tempfile::NamedTempFile;
+    use umya_spreadsheet::reader::xlsx::read as read_xlsx;
+
+    /// Returns the values of the used range of the first worksheet, row by row.
+    fn read_cells(path: &Path) -> Vec<Vec<String>> {
+        let spreadsheet = read_xlsx(path).unwrap();
+        let worksheet = spreadsheet.get_sheet(&0).unwrap();
+        let (max_col, max_row) = worksheet.get_highest_column_and_row();
+        (1..=max_row)
+            .map(|row| {
+                (1..=max_col)
+                    .map(|col| worksheet.get_value((col, row)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_run_streaming_conversion_matches_run_conversion() -> Result<()> {
+        let mut input = NamedTempFile::new()?;
+        input.write_all(b"1,10\n\t1.1,11\n\t\t1.1.1,12\n\t1.2,x\n2,20\n")?;
+        let parser = SimpleTextParser::new(SimpleTextParserOptions {
+            delimiter: Some(",".to_string()),
+            key_header: vec!["H1".to_string()],
+            value_header: vec!["V".to_string()],
+            ..Default::default()
+        });
+        let options = XlsxType2GeneratorOptions {
+            outline_rows: true,
+            integrate_cells: Some(IntegrateCellsOption::Colspan),
+            shironuri: false,
+        };
+        let value_type = || {
+            ValueTypeTransform::new(ValueTypeTransformOptions {
+                value_type: vec![ValueType::Auto],
+            })
+        };
+
+        let output = NamedTempFile::with_suffix(".xlsx")?;
+        run_conversion(
+            &[ConversionInput {
+                path: Some(input.path().to_string_lossy().into_owned()),
+                parser: &parser,
+            }],
+            MergeMode::default(),
+            &mut File::create(output.path())?,
+            &[Box::new(value_type())],
+            &|outline| Ok(Box::new(XlsxType2Generator::new(outline, options.clone()))),
+            &mut |_| {},
+        )?;
+
+        let streaming_output = NamedTempFile::with_suffix(".xlsx")?;
+        run_streaming_conversion(
+            input.path(),
+            streaming_output.path(),
+            &parser,
+            &[Box::new(value_type())],
+            &|header, summary| {
+                Ok(Box::new(XlsxType2StreamGenerator::new(
+                    header,
+                    summary,
+                    options.clone(),
+                )))
+            },
+            &mut |_| {},
+        )?;
+
+        let cells = read_cells(streaming_output.path());
+        assert_eq!(cells, read_cells(output.path()));
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[3], vec!["", "", "1.1.1", "12"]);
+
+        let spreadsheet = read_xlsx(streaming_output.path()).unwrap();
+        let worksheet = spreadsheet.get_sheet(&0).unwrap();
+        let cell = worksheet.get_cell((4, 4)).unwrap();
+        assert_eq!(cell.get_value_number(), Some(12.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_streaming_conversion_reports_warnings_once() -> Result<()> {
+        let mut input = NamedTempFile::new()?;
+        input.write_all(b"a\n  a1\n      a2\n")?;
+        let parser = SimpleTextParser::new(SimpleTextParserOptions {
+            auto_indent: true,
+            ..Default::default()
+        });
+
+        let output = NamedTempFile::with_suffix(".xlsx")?;
+        let mut warnings = Vec::new();
+        run_streaming_conversion(
+            input.path(),
+            output.path(),
+            &parser,
+            &[],
+            &|header, summary| {
+                Ok(Box::new(XlsxType2StreamGenerator::new(
+                    header,
+                    summary,
+                    XlsxType2GeneratorOptions {
+                        outline_rows: false,
+                        integrate_cells: None,
+                        shironuri: false,
+                    },
+                )))
+            },
+            &mut |warning| warnings.push(warning),
+        )?;
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].location.line, 3);
+        assert_eq!(warnings[0].location.file.as_deref(), Some(input.path()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_diff_conversion() -> Result<()> {
+        let mut old = NamedTempFile::new()?;
+        old.write_all(b"a,1\n\ta1\nb,2\n")?;
+        let mut new = NamedTempFile::new()?;
+        new.write_all(b"a,1\nb,3\n\ta1\n")?;
+        let parser = SimpleTextParser::new(SimpleTextParserOptions {
+            delimiter: Some(",".to_string()),
+            ..Default::default()
+        });
+        let input = |file: &NamedTempFile| ConversionInput {
+            path: Some(file.path().to_string_lossy().into_owned()),
+            parser: &parser,
+        };
+
+        let output = NamedTempFile::with_suffix(".xlsx")?;
+        let mut summary = Vec::new();
+        run_diff_conversion(
+            &input(&old),
+            &input(&new),
+            &["xlsx_type0".to_string()],
+            &mut File::create(output.path())?,
+            &mut summary,
+            &[],
+            &|_, outline| {
+                Ok(Box::new(XlsxType0Generator::new(
+                    outline,
+                    XlsxType0GeneratorOptions::default(),
+                )))
+            },
+            &mut |_| {},
+        )?;
+
+        assert_eq!(
+            String::from_utf8(summary)?,
+            "0 added, 0 removed, 1 moved, 1 changed\n~ b: #1 2 -> 3\n> b/a1 (from a/a1)\n"
+        );
+        assert_eq!(
+            read_cells(output.path()),
+            vec![
+                vec!["", "Outline Level", "", "Change"],
+                vec!["a", "1", "1", ""],
+                vec!["b", "1", "3", "changed"],
+                vec!["a1", "2", "", "moved"],
+            ]
+        );
+        Ok(())
+    }
+}
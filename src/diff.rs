@@ -0,0 +1,507 @@
+//! Comparison of two revisions of an outline.
+//! 
+//! Items are matched by key path. Items of the new outline whose path is not
+//! found in the old one are matched by key with the remaining old items, so
+//! that a subtree moved to another parent shows as moved rather than as
+//! removed and added again.
+
+use crate::merge::reconcile_value_headers;
+use crate::outline::{NodeId, NodeRef, Outline, OutlineItem, OutlineTree};
+use crate::value::Value;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// The value header of the column holding the change type of each item.
+pub const CHANGE_HEADER: &str = "Change";
+
+/// How an item changed between two outlines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Unchanged,
+    /// The item is only in the new outline.
+    Added,
+    /// The item is only in the old outline.
+    Removed,
+    /// The item is in both outlines, under another parent.
+    Moved,
+    /// The item is at the same path in both outlines, with different values.
+    ValueChanged,
+}
+
+impl ChangeKind {
+    /// Returns the text of the change column: `added`, `removed`, `moved`, `changed`, or an
+    /// empty string for unchanged items.
+    pub fn marker(&self) -> &'static str {
+        match self {
+            ChangeKind::Unchanged => "",
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Moved => "moved",
+            ChangeKind::ValueChanged => "changed",
+        }
+    }
+}
+
+/// A value that differs between the old and the new revision of an item.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueChange {
+    /// The 0-based value column.
+    pub column: usize,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// The change of an item of the merged outline of an [`OutlineDiff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemChange {
+    pub kind: ChangeKind,
+    /// The keys of the item and its ancestors, from level 1 down: the path in the new
+    /// outline, or in the old outline for removed items.
+    pub path: Vec<String>,
+    /// The path in the old outline of a moved item.
+    pub from: Option<Vec<String>>,
+    /// The values that differ, for moved and changed items.
+    pub value_changes: Vec<ValueChange>,
+}
+
+/// The result of [`diff_outlines`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineDiff {
+    /// The items of both outlines, in the order of the new outline with removed items at
+    /// their old position. The last value column, headed [`CHANGE_HEADER`], holds the
+    /// [`ChangeKind::marker`] of each item.
+    pub outline: Outline,
+    /// The change of each item of `outline`, in the same order.
+    pub changes: Vec<ItemChange>,
+}
+
+impl OutlineDiff {
+    /// Returns the number of items with a change of `kind`.
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+
+    /// Returns the name of value column `column`: its value header, or `#n`.
+    fn column_name(&self, column: usize) -> String {
+        self.outline
+            .value_header
+            .get(column)
+            .filter(|header| !header.is_empty())
+            .cloned()
+            .unwrap_or_else(|| format!("#{}", column + 1))
+    }
+}
+
+impl fmt::Display for OutlineDiff {
+    /// Writes a plain-text summary: the number of changes of each kind, then one line per
+    /// changed item, e.g. `~ President/VP Sales: Budget 10 -> 12`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} added, {} removed, {} moved, {} changed",
+            self.count(ChangeKind::Added),
+            self.count(ChangeKind::Removed),
+            self.count(ChangeKind::Moved),
+            self.count(ChangeKind::ValueChanged)
+        )?;
+        for change in &self.changes {
+            let sign = match change.kind {
+                ChangeKind::Unchanged => continue,
+                ChangeKind::Added => '+',
+                ChangeKind::Removed => '-',
+                ChangeKind::Moved => '>',
+                ChangeKind::ValueChanged => '~',
+            };
+            write!(f, "{} {}", sign, change.path.join("/"))?;
+            if let Some(from) = &change.from {
+                write!(f, " (from {})", from.join("/"))?;
+            }
+            for (i, value_change) in change.value_changes.iter().enumerate() {
+                write!(
+                    f,
+                    "{} {} {} -> {}",
+                    if i == 0 { ":" } else { "," },
+                    self.column_name(value_change.column),
+                    value_change.old,
+                    value_change.new
+                )?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Matches the items of two trees.
+struct Matcher<'a> {
+    old: &'a OutlineTree,
+    /// The old node of each matched new node, and whether it was moved.
+    matches: HashMap<NodeId, (NodeId, bool)>,
+    /// The matched old nodes.
+    used: HashSet<NodeId>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(old: &'a OutlineTree, new: &'a OutlineTree) -> Self {
+        let mut matcher = Matcher {
+            old,
+            matches: HashMap::new(),
+            used: HashSet::new(),
+        };
+        matcher.match_children(new.root(), old.root());
+
+        // Items left over are matched by key, as moved
+        let mut candidates: HashMap<&str, VecDeque<NodeId>> = HashMap::new();
+        for node in old.root().descendants() {
+            if !matcher.used.contains(&node.id()) {
+                candidates
+                    .entry(key(node))
+                    .or_default()
+                    .push_back(node.id());
+            }
+        }
+        for node in new.root().descendants() {
+            if matcher.matches.contains_key(&node.id()) {
+                continue;
+            }
+            let Some(queue) = candidates.get_mut(key(node)) else {
+                continue;
+            };
+            while let Some(old_id) = queue.pop_front() {
+                if matcher.used.insert(old_id) {
+                    matcher.matches.insert(node.id(), (old_id, true));
+                    matcher.match_children(node, old.node(old_id));
+                    break;
+                }
+            }
+        }
+        matcher
+    }
+
+    /// Matches the children of `new` with the children of `old` with the same key, in order,
+    /// and their descendants in turn.
+    fn match_children(&mut self, new: NodeRef, old: NodeRef) {
+        for new_child in new.children() {
+            let old_child = old
+                .children()
+                .find(|c| !self.used.contains(&c.id()) && key(*c) == key(new_child));
+            if let Some(old_child) = old_child {
+                self.used.insert(old_child.id());
+                self.matches.insert(new_child.id(), (old_child.id(), false));
+                self.match_children(new_child, old_child);
+            }
+        }
+    }
+}
+
+fn key(node: NodeRef<'_>) -> &str {
+    node.item().map_or("", |item| item.key.as_str())
+}
+
+fn path(node: NodeRef) -> Vec<String> {
+    let mut path: Vec<String> = std::iter::once(node)
+        .chain(node.ancestors())
+        .filter_map(|n| n.item().map(|item| item.key.clone()))
+        .collect();
+    path.reverse();
+    path
+}
+
+/// Returns the values that differ between `old` and `new`, missing values being empty.
+fn value_changes(old: &[Value], new: &[Value]) -> Vec<ValueChange> {
+    let empty = Value::default();
+    (0..old.len().max(new.len()))
+        .filter_map(|column| {
+            let old = old.get(column).unwrap_or(&empty);
+            let new = new.get(column).unwrap_or(&empty);
+            (old != new).then(|| ValueChange {
+                column,
+                old: old.clone(),
+                new: new.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Builds the merged outline of a diff.
+struct DiffBuilder<'a> {
+    matcher: &'a Matcher<'a>,
+    value_length: usize,
+    diff: OutlineDiff,
+}
+
+impl DiffBuilder<'_> {
+    fn push(&mut self, item: &OutlineItem, level: u32, change: ItemChange) {
+        let mut value = item.value.clone();
+        value.resize(self.value_length, Value::default());
+        value.push(Value::from(change.kind.marker()));
+        self.diff.outline.item.push(OutlineItem {
+            key: item.key.clone(),
+            level,
+            value,
+        });
+        self.diff.changes.push(change);
+    }
+
+    /// Adds the children of `new` and the removed children of `old`, its old revision, at
+    /// `level`.
+    fn add_children(&mut self, new: NodeRef, old: Option<NodeRef>, level: u32) {
+        let old_children: Vec<NodeRef> = old.map_or(Vec::new(), |o| o.children().collect());
+        let mut next_old = 0;
+        for new_child in new.children() {
+            let old_match = self.matcher.matches.get(&new_child.id()).copied();
+            if let Some((old_id, false)) = old_match {
+                // Removed items before the old revision of this child come first; a child
+                // that comes before a previous sibling in `old` has none left
+                let index = old_children.iter().position(|c| c.id() == old_id);
+                if let Some(index) = index.filter(|&index| index >= next_old) {
+                    for old_child in &old_children[next_old..index] {
+                        self.add_removed(*old_child, level);
+                    }
+                    next_old = next_old.max(index + 1);
+                }
+            }
+
+            let item = new_child.item().expect("children are items");
+            let old_child = old_match.map(|(old_id, _)| self.matcher.old.node(old_id));
+            let change = match (old_match, old_child.and_then(|c| c.item())) {
+                (Some((_, moved)), Some(old_item)) => {
+                    let value_changes = value_changes(&old_item.value, &item.value);
+                    ItemChange {
+                        kind: if moved {
+                            ChangeKind::Moved
+                        } else if value_changes.is_empty() {
+                            ChangeKind::Unchanged
+                        } else {
+                            ChangeKind::ValueChanged
+                        },
+                        path: path(new_child),
+                        from: old_child.filter(|_| moved).map(path),
+                        value_changes,
+                    }
+                }
+                _ => ItemChange {
+                    kind: ChangeKind::Added,
+                    path: path(new_child),
+                    from: None,
+                    value_changes: Vec::new(),
+                },
+            };
+            self.push(item, level, change);
+            self.add_children(new_child, old_child, level + 1);
+        }
+        for old_child in &old_children[next_old.min(old_children.len())..] {
+            self.add_removed(*old_child, level);
+        }
+    }
+
+    /// Adds `old` and its descendants at `level` if it is removed, skipping moved items.
+    fn add_removed(&mut self, old: NodeRef, level: u32) {
+        if self.matcher.used.contains(&old.id()) {
+            return;
+        }
+        let item = old.item().expect("children are items");
+        let change = ItemChange {
+            kind: ChangeKind::Removed,
+            path: path(old),
+            from: None,
+            value_changes: Vec::new(),
+        };
+        self.push(item, level, change);
+        for old_child in old.children() {
+            self.add_removed(old_child, level + 1);
+        }
+    }
+}
+
+/// Compares `old` and `new`, two revisions of an outline.
+///
+/// Items are matched by key path; siblings with the same key are matched in order. The
+/// items of `new` left over are then matched by key with the items of `old` left over, in
+/// order, and reported as moved together with their subtree. Items of the merged outline are
+/// leveled by their depth in it.
+///
+/// When both outlines have a value header, value columns are matched by header name (see
+/// [`crate::merge::merge_outlines`]), the columns of `new` first; otherwise by position.
+/// The headers of the merged outline are those of `new`, or of `old` where `new` has none,
+/// and the [`CHANGE_HEADER`] value column is added after the widest value list.
+pub fn diff_outlines(old: &Outline, new: &Outline) -> OutlineDiff {
+    let has_value_header = |o: &Outline| o.value_header.iter().any(|h| !h.is_empty());
+    let (old, new) = if has_value_header(old) && has_value_header(new) {
+        let (mut old, mut new) = (old.clone(), new.clone());
+        reconcile_value_headers(&mut [&mut new, &mut old]);
+        (Cow::Owned(old), Cow::Owned(new))
+    } else {
+        (Cow::Borrowed(old), Cow::Borrowed(new))
+    };
+    let (old, new) = (old.as_ref(), new.as_ref());
+
+    let old_tree = old.to_tree();
+    let new_tree = new.to_tree();
+    let matcher = Matcher::new(&old_tree, &new_tree);
+
+    let value_length = old
+        .max_value_length()
+        .max(new.max_value_length())
+        .max(new.value_header.len());
+    let mut outline = Outline::new();
+    outline.key_header = if new.key_header.is_empty() {
+        old.key_header.clone()
+    } else {
+        new.key_header.clone()
+    };
+    outline.value_header = if new.value_header.is_empty() {
+        old.value_header.clone()
+    } else {
+        new.value_header.clone()
+    };
+    outline.value_header.resize(value_length, String::new());
+    outline.value_header.push(CHANGE_HEADER.to_string());
+
+    let mut builder = DiffBuilder {
+        matcher: &matcher,
+        value_length,
+        diff: OutlineDiff {
+            outline,
+            changes: Vec::new(),
+        },
+    };
+    builder.add_children(new_tree.root(), Some(old_tree.root()), 1);
+    builder.diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(items: &[(&str, u32, &[&str])]) -> Outline {
+        let mut outline = Outline::new();
+        outline.value_header = vec!["Budget".to_string()];
+        for (key, level, value) in items {
+            outline.add_item(key, *level, value.iter().map(|s| s.to_string()).collect());
+        }
+        outline
+    }
+
+    fn rows(diff: &OutlineDiff) -> Vec<(String, u32, String)> {
+        diff.outline
+            .item
+            .iter()
+            .map(|item| {
+                (
+                    item.key.clone(),
+                    item.level,
+                    item.value.last().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_outlines() {
+        let old = outline(&[
+            ("President", 1, &["100"]),
+            ("VP Marketing", 2, &["10"]),
+            ("Manager A", 3, &[]),
+            ("Manager C", 3, &[]),
+            ("VP Sales", 2, &["10"]),
+        ]);
+        let new = outline(&[
+            ("President", 1, &["100"]),
+            ("VP Sales", 2, &["12"]),
+            ("Manager C", 3, &[]),
+            ("VP Legal", 2, &["5"]),
+        ]);
+        let diff = diff_outlines(&old, &new);
+        let row = |key: &str, level, change: &str| (key.to_string(), level, change.to_string());
+        assert_eq!(
+            rows(&diff),
+            vec![
+                row("President", 1, ""),
+                row("VP Marketing", 2, "removed"),
+                row("Manager A", 3, "removed"),
+                row("VP Sales", 2, "changed"),
+                row("Manager C", 3, "moved"),
+                row("VP Legal", 2, "added"),
+            ]
+        );
+        assert_eq!(diff.outline.value_header, vec!["Budget", "Change"]);
+        assert_eq!(diff.outline.item[3].value[0], "12");
+        assert_eq!(
+            diff.to_string(),
+            "1 added, 2 removed, 1 moved, 1 changed\n\
+             - President/VP Marketing\n\
+             - President/VP Marketing/Manager A\n\
+             ~ President/VP Sales: Budget 10 -> 12\n\
+             > President/VP Sales/Manager C (from President/VP Marketing/Manager C)\n\
+             + President/VP Legal\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_outlines_matches_duplicate_keys_in_order() {
+        let old = outline(&[("a", 1, &["1"]), ("a", 1, &["2"])]);
+        let new = outline(&[("a", 1, &["1"]), ("a", 1, &["3"]), ("a", 1, &[])]);
+        let diff = diff_outlines(&old, &new);
+        let kinds: Vec<ChangeKind> = diff.changes.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ChangeKind::Unchanged,
+                ChangeKind::ValueChanged,
+                ChangeKind::Added
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_outlines_matches_values_by_header() {
+        let mut old = Outline::new();
+        old.value_header = vec!["Owner".to_string(), "Budget".to_string()];
+        old.add_item("a", 1, vec!["Ann".to_string(), "10".to_string()]);
+        old.add_item("b", 1, vec!["Bob".to_string(), "20".to_string()]);
+        let mut new = Outline::new();
+        new.value_header = vec!["Budget".to_string(), "Owner".to_string()];
+        new.add_item("a", 1, vec!["10".to_string(), "Ann".to_string()]);
+        new.add_item("b", 1, vec!["25".to_string(), "Bob".to_string()]);
+        let diff = diff_outlines(&old, &new);
+        assert_eq!(
+            diff.outline.value_header,
+            vec!["Budget", "Owner", CHANGE_HEADER]
+        );
+        let kinds: Vec<ChangeKind> = diff.changes.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Unchanged, ChangeKind::ValueChanged]);
+        assert_eq!(
+            diff.to_string().lines().nth(1),
+            Some("~ b: Budget 20 -> 25")
+        );
+    }
+
+    #[test]
+    fn test_diff_outlines_reordered_siblings() {
+        let old = outline(&[("x", 1, &["1"]), ("y", 1, &["2"])]);
+        let new = outline(&[("y", 1, &["2"]), ("x", 1, &["1"])]);
+        let diff = diff_outlines(&old, &new);
+        let row = |key: &str, level, change: &str| (key.to_string(), level, change.to_string());
+        assert_eq!(rows(&diff), vec![row("y", 1, ""), row("x", 1, "")]);
+
+        let old = outline(&[
+            ("x", 1, &["1"]),
+            ("y", 1, &["2"]),
+            ("y1", 2, &[]),
+            ("z", 1, &[]),
+        ]);
+        let new = outline(&[("y", 1, &["3"]), ("y1", 2, &[]), ("x", 1, &["4"])]);
+        let diff = diff_outlines(&old, &new);
+        assert_eq!(
+            rows(&diff),
+            vec![
+                row("y", 1, "changed"),
+                row("y1", 2, ""),
+                row("x", 1, "changed"),
+                row("z", 1, "removed"),
+            ]
+        );
+    }
+}
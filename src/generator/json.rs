@@ -0,0 +1,84 @@
+use crate::generator::Generator;
+use crate::outline::Outline;
+use anyhow::Result;
+use std::io::Write;
+
+#[derive(Debug, Clone, Default)]
+pub struct JsonGeneratorOptions {
+    /// If true, indent the JSON output for readability.
+    pub pretty: bool,
+}
+
+/// A generator that serializes an `Outline` losslessly as JSON.
+///
+/// The output can be read back with the `json` input type.
+pub struct JsonGenerator {
+    outline: Outline,
+    options: JsonGeneratorOptions,
+}
+
+impl JsonGenerator {
+    pub fn new(outline: Outline, options: JsonGeneratorOptions) -> Self {
+        JsonGenerator { outline, options }
+    }
+}
+
+impl Generator for JsonGenerator {
+    fn output(&self, writer: &mut dyn Write) -> Result<()> {
+        if self.options.pretty {
+            serde_json::to_writer_pretty(&mut *writer, &self.outline)?;
+        } else {
+            serde_json::to_writer(&mut *writer, &self.outline)?;
+        }
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::json::JsonParser;
+
+    fn reference_outline() -> Outline {
+        let mut outline = Outline::new();
+        outline.key_header = vec!["H1".to_string(), "H2".to_string(), "H3".to_string()];
+        outline.value_header = vec!["H(1)".to_string(), "H(2)".to_string()];
+        outline.add_item("1", 1, vec!["1(1)".to_string(), "1(2)".to_string()]);
+        outline.add_item("1.1", 2, vec!["1.1(1)".to_string()]);
+        outline.add_item("1.2", 2, vec![]);
+        outline.add_item("1.2.1", 3, vec!["".to_string(), "1.2.1(2)".to_string()]);
+        outline
+    }
+
+    #[test]
+    fn test_json_generator_output() -> Result<()> {
+        let mut outline = Outline::new();
+        outline.key_header = vec!["H1".to_string()];
+        outline.add_item("1", 1, vec!["a".to_string()]);
+
+        let generator = JsonGenerator::new(outline, JsonGeneratorOptions::default());
+        let mut buf = Vec::new();
+        generator.output(&mut buf)?;
+
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "{\"key_header\":[\"H1\"],\"value_header\":[],\"item\":[{\"key\":\"1\",\"level\":1,\"value\":[\"a\"]}]}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_generator_round_trip() -> Result<()> {
+        for pretty in [false, true] {
+            let generator =
+                JsonGenerator::new(reference_outline(), JsonGeneratorOptions { pretty });
+            let mut buf = Vec::new();
+            generator.output(&mut buf)?;
+
+            let outline = JsonParser::new().parse(&String::from_utf8(buf)?)?;
+            assert_eq!(outline, reference_outline());
+        }
+        Ok(())
+    }
+}
@@ -3,14 +3,22 @@
 //! This module contains generators that can convert the internal outline
 //! structure into various XLSX output formats.
 
+pub mod json;
+pub mod xlsx_diff;
 pub mod xlsx_type0;
 pub mod xlsx_type1;
 pub mod xlsx_type2;
 pub mod xlsx_type3;
 pub mod xlsx_type4;
 pub mod xlsx_type5;
+pub mod xlsx_workbook;
 
+use crate::outline::OutlineItem;
+use crate::value::Value;
+use anyhow::Result;
 use clap::ValueEnum;
+use rust_xlsxwriter::{ColNum, Format, RowNum, Workbook, Worksheet};
+use std::io::Write;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum IntegrateCellsOption {
@@ -19,12 +27,129 @@ pub enum IntegrateCellsOption {
     Both,
 }
 
-#[derive(Debug, Clone)]
-pub enum GeneratorOptions {
-    XlsxType0(xlsx_type0::XlsxType0GeneratorOptions),
-    XlsxType1(xlsx_type1::XlsxType1GeneratorOptions),
-    XlsxType2(xlsx_type2::XlsxType2GeneratorOptions),
-    XlsxType3(xlsx_type3::XlsxType3GeneratorOptions),
-    XlsxType4(xlsx_type4::XlsxType4GeneratorOptions),
-    XlsxType5(xlsx_type5::XlsxType5GeneratorOptions),
+/// A generator that renders an `Outline` into an output document.
+///
+/// Implementors are registered by name in a [`crate::registry::Registry`] so that
+/// the CLI and library callers can select them with `--to-type`.
+pub trait Generator {
+    /// Writes the generated document to `writer`.
+    fn output(&self, writer: &mut dyn Write) -> Result<()>;
+}
+
+/// A generator that lays out an `Outline` on a single XLSX worksheet.
+///
+/// Every `XlsxGenerator` is also a [`Generator`] writing a one-sheet workbook.
+pub trait XlsxGenerator {
+    /// Writes the outline to the given worksheet.
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()>;
+
+    /// Returns the 0-based column of the 0-based value column `index` on the row of `item`.
+    ///
+    /// Layouts returning `Some` write a header row, then each item on a row of its own, in
+    /// order. The default implementation returns `None`, meaning that the layout does not
+    /// write the values of every item.
+    fn value_col(&self, _item: &OutlineItem, _index: usize) -> Option<ColNum> {
+        None
+    }
+}
+
+impl<T: XlsxGenerator + ?Sized> XlsxGenerator for Box<T> {
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
+        (**self).output_to_worksheet(worksheet)
+    }
+
+    fn value_col(&self, item: &OutlineItem, index: usize) -> Option<ColNum> {
+        (**self).value_col(item, index)
+    }
+}
+
+impl<T: XlsxGenerator> Generator for T {
+    fn output(&self, writer: &mut dyn Write) -> Result<()> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        self.output_to_worksheet(worksheet)?;
+
+        let buffer = workbook.save_to_buffer()?;
+        writer.write_all(&buffer)?;
+        Ok(())
+    }
+}
+
+/// A generator that lays out an outline on an XLSX worksheet one item at a time, in row
+/// order.
+///
+/// Rows are never revisited, so the worksheet can be a constant-memory worksheet, which
+/// flushes each row to disk once the next row is started. The layout is sized from the
+/// outline header and [`crate::outline::OutlineSummary`] given up front.
+pub trait XlsxStreamGenerator {
+    /// Writes the header row and the worksheet-wide settings.
+    fn start(&mut self, worksheet: &mut Worksheet) -> Result<()>;
+    /// Writes the row of the next item.
+    fn write_item(&mut self, worksheet: &mut Worksheet, item: &OutlineItem) -> Result<()>;
+}
+
+/// Writes the header row and `items` to `worksheet` with `generator`.
+pub(crate) fn write_items(
+    generator: &mut dyn XlsxStreamGenerator,
+    worksheet: &mut Worksheet,
+    items: &[OutlineItem],
+) -> Result<()> {
+    generator.start(worksheet)?;
+    for item in items {
+        generator.write_item(worksheet, item)?;
+    }
+    Ok(())
+}
+
+/// Adds the row of an item at `level` to the worksheet outline, one group level per level
+/// below the first.
+///
+/// Called for each item row in turn, this builds the same nested groups as grouping each
+/// run of consecutive rows at or below a level.
+pub(crate) fn group_item_row(worksheet: &mut Worksheet, row: RowNum, level: u32) -> Result<()> {
+    for _ in 1..level {
+        worksheet.group_rows(row, row)?;
+    }
+    Ok(())
+}
+
+/// Writes an outline value to a cell as a native Excel type.
+///
+/// Numbers and booleans are written as such; dates, date-times and durations are
+/// written as Excel serial numbers with a matching number format added to `format`.
+pub(crate) fn write_value(
+    worksheet: &mut Worksheet,
+    row: RowNum,
+    col: ColNum,
+    value: &Value,
+    format: &Format,
+) -> Result<()> {
+    match value {
+        Value::Text(s) => {
+            worksheet.write_string_with_format(row, col, s, format)?;
+        }
+        Value::Integer(i) => {
+            worksheet.write_number_with_format(row, col, *i as f64, format)?;
+        }
+        Value::Number(n) => {
+            worksheet.write_number_with_format(row, col, *n, format)?;
+        }
+        Value::Boolean(b) => {
+            worksheet.write_boolean_with_format(row, col, *b, format)?;
+        }
+        Value::Date(d) => {
+            let format = format.clone().set_num_format("yyyy-mm-dd");
+            worksheet.write_datetime_with_format(row, col, d, &format)?;
+        }
+        Value::DateTime(dt) => {
+            let format = format.clone().set_num_format("yyyy-mm-dd hh:mm:ss");
+            worksheet.write_datetime_with_format(row, col, dt, &format)?;
+        }
+        Value::Duration(d) => {
+            let format = format.clone().set_num_format("[h]:mm:ss");
+            let days = d.num_milliseconds() as f64 / 86_400_000.0;
+            worksheet.write_number_with_format(row, col, days, &format)?;
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,223 @@
+//! Highlighting of the changes of an outline diff in any worksheet layout.
+//! 
+//! The merged outline of a [`crate::diff::OutlineDiff`] carries the change type
+//! of each item in its last value column. `XlsxDiffGenerator` writes it with
+//! another `XlsxGenerator` and adds conditional formats that fill each row
+//! according to the change type in its change column.
+
+use crate::diff::ChangeKind;
+use crate::generator::XlsxGenerator;
+use crate::outline::{Outline, OutlineItem};
+use anyhow::{anyhow, Result};
+use rust_xlsxwriter::{
+    utility::column_number_to_name, ColNum, Color, ConditionalFormatFormula, Format, RowNum,
+    Worksheet,
+};
+use std::collections::BTreeMap;
+
+/// The fill color of each change type.
+const CHANGE_COLORS: [(ChangeKind, u32); 4] = [
+    (ChangeKind::Added, 0xC6EFCE),
+    (ChangeKind::Removed, 0xFFC7CE),
+    (ChangeKind::Moved, 0xDDEBF7),
+    (ChangeKind::ValueChanged, 0xFFEB9C),
+];
+
+/// A generator writing a diff outline with another layout and filling changed rows.
+pub struct XlsxDiffGenerator {
+    layout: Box<dyn XlsxGenerator>,
+    /// The runs of consecutive item rows, first and last, by the column of their change
+    /// column.
+    runs: BTreeMap<ColNum, Vec<(RowNum, RowNum)>>,
+    last_col: ColNum,
+}
+
+impl XlsxDiffGenerator {
+    /// Creates a generator filling the rows written by `layout` for `outline`, the merged
+    /// outline of a diff.
+    ///
+    /// Fails if `layout` does not write the change column of every item (see
+    /// [`XlsxGenerator::value_col`]), e.g. `xlsx_type4` and `xlsx_type5`, which write no
+    /// values on the rows of parent items.
+    pub fn new(outline: &Outline, layout: Box<dyn XlsxGenerator>) -> Result<Self> {
+        let change_index = outline.value_header.len().saturating_sub(1);
+        let mut runs: BTreeMap<ColNum, Vec<(RowNum, RowNum)>> = BTreeMap::new();
+        let mut previous: Option<ColNum> = None;
+        for (index, item) in outline.item.iter().enumerate() {
+            let row = index as RowNum + 1;
+            let col = layout.value_col(item, change_index).ok_or_else(|| {
+                anyhow!("The layout does not write the values of every item, so changes cannot be shown")
+            })?;
+            let col_runs = runs.entry(col).or_default();
+            match col_runs.last_mut() {
+                Some((_, last)) if previous == Some(col) => *last = row,
+                _ => col_runs.push((row, row)),
+            }
+            previous = Some(col);
+        }
+
+        // Large enough for the columns of any layout; cells beyond the data are empty and
+        // never filled.
+        let last_col = (2 * outline.max_level() as usize + outline.max_value_length() + 1)
+            .min(ColNum::MAX as usize) as ColNum;
+        Ok(XlsxDiffGenerator {
+            layout,
+            runs,
+            last_col,
+        })
+    }
+}
+
+impl XlsxGenerator for XlsxDiffGenerator {
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
+        self.layout.output_to_worksheet(worksheet)?;
+
+        let last_col_name = column_number_to_name(self.last_col);
+        for (&col, col_runs) in &self.runs {
+            let (first_row, first_last_row) = col_runs[0];
+            let ranges: Vec<String> = col_runs
+                .iter()
+                .map(|(first, last)| format!("A{}:{}{}", first + 1, last_col_name, last + 1))
+                .collect();
+            for (kind, color) in CHANGE_COLORS {
+                let mut format = Format::new().set_background_color(Color::RGB(color));
+                if kind == ChangeKind::Removed {
+                    format = format.set_font_strikethrough();
+                }
+                // Relative to the top-left cell of the first range
+                let formula = format!(
+                    "=${}{}=\"{}\"",
+                    column_number_to_name(col),
+                    first_row + 1,
+                    kind.marker()
+                );
+                let mut rule = ConditionalFormatFormula::new()
+                    .set_rule(formula.as_str())
+                    .set_format(format);
+                if ranges.len() > 1 {
+                    rule = rule.set_multi_range(ranges.join(" "));
+                }
+                worksheet.add_conditional_format(
+                    first_row,
+                    0,
+                    first_last_row,
+                    self.last_col,
+                    &rule,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn value_col(&self, item: &OutlineItem, index: usize) -> Option<ColNum> {
+        self.layout.value_col(item, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::diff_outlines;
+    use crate::generator::xlsx_type0::{XlsxType0Generator, XlsxType0GeneratorOptions};
+    use crate::generator::xlsx_type3::{XlsxType3Generator, XlsxType3GeneratorOptions};
+    use crate::generator::xlsx_type4::{XlsxType4Generator, XlsxType4GeneratorOptions};
+    use crate::generator::Generator;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_xlsx_diff_generator() -> Result<()> {
+        let mut old = Outline::new();
+        old.add_item("a", 1, vec![]);
+        old.add_item("b", 1, vec!["1".to_string()]);
+        let mut new = Outline::new();
+        new.add_item("b", 1, vec!["2".to_string()]);
+        new.add_item("c", 1, vec![]);
+        let diff = diff_outlines(&old, &new);
+
+        let layout =
+            XlsxType0Generator::new(diff.outline.clone(), XlsxType0GeneratorOptions::default());
+        let generator = XlsxDiffGenerator::new(&diff.outline, Box::new(layout))?;
+        let temp_file = NamedTempFile::with_suffix(".xlsx")?;
+        generator.output(&mut std::fs::File::create(temp_file.path())?)?;
+
+        let spreadsheet = umya_spreadsheet::reader::xlsx::read(temp_file.path()).unwrap();
+        let worksheet = spreadsheet.get_sheet(&0).unwrap();
+        assert_eq!(worksheet.get_value((4, 1)), "Change");
+        assert_eq!(worksheet.get_value((4, 2)), "removed");
+        assert_eq!(worksheet.get_value((3, 3)), "2");
+        assert_eq!(worksheet.get_value((4, 3)), "changed");
+        assert_eq!(worksheet.get_value((4, 4)), "added");
+
+        let formats = worksheet.get_conditional_formatting_collection();
+        assert_eq!(formats.len(), 1);
+        assert_eq!(formats[0].get_sequence_of_references().get_sqref(), "A2:F4");
+        let rules = formats[0].get_conditional_collection();
+        assert_eq!(rules.len(), 4);
+        assert_eq!(
+            rules[0].get_formula().unwrap().get_address_str(),
+            "$D2=\"added\""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_xlsx_diff_generator_change_column_by_level() -> Result<()> {
+        let mut old = Outline::new();
+        old.add_item("added", 1, vec![]);
+        old.add_item("a1", 2, vec![]);
+        let mut new = old.clone();
+        new.add_item("b", 1, vec![]);
+        new.add_item("b1", 2, vec![]);
+        let diff = diff_outlines(&old, &new);
+
+        // The change column is the first value, right after the key
+        let layout = XlsxType3Generator::new(
+            diff.outline.clone(),
+            XlsxType3GeneratorOptions {
+                outline_rows: false,
+                integrate_cells: None,
+                shironuri: false,
+            },
+        );
+        let generator = XlsxDiffGenerator::new(&diff.outline, Box::new(layout))?;
+        let temp_file = NamedTempFile::with_suffix(".xlsx")?;
+        generator.output(&mut std::fs::File::create(temp_file.path())?)?;
+
+        let spreadsheet = umya_spreadsheet::reader::xlsx::read(temp_file.path()).unwrap();
+        let worksheet = spreadsheet.get_sheet(&0).unwrap();
+        assert_eq!(worksheet.get_value((2, 4)), "added");
+        let formats = worksheet.get_conditional_formatting_collection();
+        assert_eq!(formats.len(), 2);
+        assert_eq!(
+            formats[0].get_sequence_of_references().get_sqref(),
+            "A2:G2 A4:G4"
+        );
+        assert_eq!(
+            formats[0].get_conditional_collection()[0]
+                .get_formula()
+                .unwrap()
+                .get_address_str(),
+            "$B2=\"added\""
+        );
+        assert_eq!(
+            formats[1].get_sequence_of_references().get_sqref(),
+            "A3:G3 A5:G5"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_xlsx_diff_generator_rejects_leaf_row_layouts() {
+        let mut outline = Outline::new();
+        outline.add_item("a", 1, vec![]);
+        let diff = diff_outlines(&outline, &outline);
+        let layout = XlsxType4Generator::new(
+            diff.outline.clone(),
+            XlsxType4GeneratorOptions {
+                integrate_cells: None,
+                shironuri: false,
+            },
+        );
+        assert!(XlsxDiffGenerator::new(&diff.outline, Box::new(layout)).is_err());
+    }
+}
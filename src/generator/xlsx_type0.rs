@@ -1,4 +1,6 @@
-use crate::outline::Outline;
+use crate::generator::{write_items, write_value, XlsxGenerator, XlsxStreamGenerator};
+use crate::outline::{Outline, OutlineItem, OutlineSummary};
+use crate::value::Value;
 use anyhow::Result;
 use rust_xlsxwriter::{ColNum, Format, RowNum, Worksheet};
 
@@ -17,17 +19,60 @@ impl XlsxType0Generator {
     pub fn new(outline: Outline, options: XlsxType0GeneratorOptions) -> Self {
         XlsxType0Generator { outline, options }
     }
+}
 
-    pub fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
-        let mut row_index = 0;
-        let max_value_length = self.outline.max_value_length();
+impl XlsxGenerator for XlsxType0Generator {
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
+        let mut generator = XlsxType0StreamGenerator::new(
+            self.outline.header(),
+            self.outline.summary(),
+            self.options.clone(),
+        );
+        write_items(&mut generator, worksheet, &self.outline.item)
+    }
 
+    fn value_col(&self, _item: &OutlineItem, index: usize) -> Option<ColNum> {
+        // After the key and outline level columns
+        ColNum::try_from(2 + index).ok()
+    }
+}
+
+/// Writes the `xlsx_type0` layout one item at a time.
+pub struct XlsxType0StreamGenerator {
+    header: Outline,
+    summary: OutlineSummary,
+    options: XlsxType0GeneratorOptions,
+    border_format: Format,
+    row_index: RowNum,
+}
+
+impl XlsxType0StreamGenerator {
+    /// Creates a generator for an outline with the headers of `header` and the size
+    /// given by `summary`.
+    pub fn new(
+        header: Outline,
+        summary: OutlineSummary,
+        options: XlsxType0GeneratorOptions,
+    ) -> Self {
         // Define a format for cells with thin borders
         let mut border_format = Format::new().set_border(rust_xlsxwriter::FormatBorder::Thin);
         // If shironuri is true, set the background color of border_format to white.
-        if self.options.shironuri {
+        if options.shironuri {
             border_format = border_format.set_background_color(rust_xlsxwriter::Color::White);
         }
+        XlsxType0StreamGenerator {
+            header,
+            summary,
+            options,
+            border_format,
+            row_index: 0,
+        }
+    }
+}
+
+impl XlsxStreamGenerator for XlsxType0StreamGenerator {
+    fn start(&mut self, worksheet: &mut Worksheet) -> Result<()> {
+        let max_value_length = self.summary.max_value_length;
 
         // If shironuri is true, set the background color of all cells to white.
         if self.options.shironuri {
@@ -37,9 +82,9 @@ impl XlsxType0Generator {
 
         // Header row
         let mut header_values = Vec::new();
-        header_values.push(self.outline.key_header.first().cloned().unwrap_or_default());
+        header_values.push(self.header.key_header.first().cloned().unwrap_or_default());
         header_values.push("Outline Level".to_string());
-        for s in self.outline.value_header.iter() {
+        for s in self.header.value_header.iter() {
             header_values.push(s.clone());
         }
 
@@ -50,37 +95,37 @@ impl XlsxType0Generator {
 
         for (col_index, v) in header_values.iter().enumerate() {
             worksheet.write_with_format(
-                row_index as RowNum,
+                self.row_index,
                 col_index as ColNum,
                 v.clone(),
-                &border_format,
+                &self.border_format,
             )?;
         }
-        row_index += 1;
-
-        // Data rows
-        for item in &self.outline.item {
-            let mut row_values: Vec<String> = Vec::new();
-            row_values.push(item.key.clone());
-            row_values.push(item.level.to_string());
-            row_values.extend(item.value.iter().map(|s| s.to_string()));
-
-            // Pad header_values with empty strings if necessary
-            while row_values.len() < 2 + max_value_length {
-                row_values.push("".to_string());
-            }
-
-            for (col_index, v) in row_values.iter().enumerate() {
-                worksheet.write_with_format(
-                    row_index as RowNum,
-                    col_index as ColNum,
-                    v.clone(),
-                    &border_format,
-                )?;
-            }
-            row_index += 1;
+        self.row_index += 1;
+        Ok(())
+    }
+
+    fn write_item(&mut self, worksheet: &mut Worksheet, item: &OutlineItem) -> Result<()> {
+        let mut row_values: Vec<Value> = Vec::new();
+        row_values.push(Value::Text(item.key.clone()));
+        row_values.push(Value::Text(item.level.to_string()));
+        row_values.extend(item.value.iter().cloned());
+
+        // Pad header_values with empty strings if necessary
+        while row_values.len() < 2 + self.summary.max_value_length {
+            row_values.push(Value::default());
         }
 
+        for (col_index, v) in row_values.iter().enumerate() {
+            write_value(
+                worksheet,
+                self.row_index,
+                col_index as ColNum,
+                v,
+                &self.border_format,
+            )?;
+        }
+        self.row_index += 1;
         Ok(())
     }
 }
@@ -307,4 +352,65 @@ mod tests {
 
         drop(temp_file);
     }
+
+    #[test]
+    fn test_xlsx_type0_generator_typed_values() {
+        let mut outline = Outline {
+            key_header: vec!["Key".to_string()],
+            value_header: vec![],
+            item: vec![OutlineItem::new("Item 1", 1, vec![])],
+        };
+        outline.item[0].value = vec![
+            Value::infer("1.5"),
+            Value::infer("true"),
+            Value::infer("2025-01-31"),
+            Value::infer("PT12H0M0S"),
+            Value::infer("text"),
+        ];
+
+        let generator = XlsxType0Generator::new(outline, XlsxType0GeneratorOptions::default());
+
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        generator.output_to_worksheet(worksheet).unwrap();
+
+        let temp_file = NamedTempFile::with_suffix(".xlsx").unwrap();
+        let temp_path = temp_file.path().to_path_buf();
+        workbook.save(&temp_path).unwrap();
+
+        let read_spreadsheet = umya_spreadsheet::reader::xlsx::read(&temp_path).unwrap();
+        let read_worksheet = read_spreadsheet.get_sheet(&0).unwrap();
+        let format_code = |coordinate: (u32, u32)| {
+            read_worksheet
+                .get_style(coordinate)
+                .get_number_format()
+                .map(|f| f.get_format_code().to_string())
+                .unwrap_or_default()
+        };
+
+        let cell = read_worksheet.get_cell((3, 2)).unwrap();
+        assert_eq!(cell.get_value_number(), Some(1.5));
+        assert_eq!(
+            read_worksheet.get_cell((4, 2)).unwrap().get_data_type(),
+            "b"
+        );
+        assert_eq!(read_worksheet.get_value((4, 2)).as_str(), "TRUE");
+        let cell = read_worksheet.get_cell((5, 2)).unwrap();
+        assert_eq!(cell.get_value_number(), Some(45688.0));
+        assert_eq!(format_code((5, 2)), "yyyy-mm-dd");
+        let cell = read_worksheet.get_cell((6, 2)).unwrap();
+        assert_eq!(cell.get_value_number(), Some(0.5));
+        assert_eq!(format_code((6, 2)), "[h]:mm:ss");
+        assert_eq!(
+            read_worksheet.get_cell((7, 2)).unwrap().get_data_type(),
+            "s"
+        );
+        // The level column is still written as text
+        assert_eq!(
+            read_worksheet.get_cell((2, 2)).unwrap().get_data_type(),
+            "s"
+        );
+
+        drop(temp_file);
+    }
 }
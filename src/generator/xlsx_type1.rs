@@ -1,6 +1,10 @@
-use crate::outline::Outline;
+use crate::generator::{
+    group_item_row, write_items, write_value, XlsxGenerator, XlsxStreamGenerator,
+};
+use crate::outline::{Outline, OutlineItem, OutlineSummary};
+use crate::value::Value;
 use anyhow::Result;
-use rust_xlsxwriter::{Format, FormatBorder, Worksheet};
+use rust_xlsxwriter::{ColNum, Format, FormatBorder, RowNum, Worksheet};
 
 #[derive(Debug, Clone)]
 pub struct XlsxType1GeneratorOptions {
@@ -17,19 +21,61 @@ impl XlsxType1Generator {
     pub fn new(outline: Outline, options: XlsxType1GeneratorOptions) -> Self {
         XlsxType1Generator { outline, options }
     }
+}
+
+impl XlsxGenerator for XlsxType1Generator {
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
+        let mut generator = XlsxType1StreamGenerator::new(
+            self.outline.header(),
+            self.outline.summary(),
+            self.options.clone(),
+        );
+        write_items(&mut generator, worksheet, &self.outline.item)
+    }
 
-    pub fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
-        let mut row_index = 0;
-        let max_value_length = self.outline.max_value_length();
+    fn value_col(&self, _item: &OutlineItem, index: usize) -> Option<ColNum> {
+        ColNum::try_from(1 + index).ok()
+    }
+}
+
+/// Writes the `xlsx_type1` layout one item at a time.
+pub struct XlsxType1StreamGenerator {
+    header: Outline,
+    summary: OutlineSummary,
+    options: XlsxType1GeneratorOptions,
+    header_format: Format,
+    item_format: Format,
+    row_index: RowNum,
+}
 
+impl XlsxType1StreamGenerator {
+    /// Creates a generator for an outline with the headers of `header` and the size
+    /// given by `summary`.
+    pub fn new(
+        header: Outline,
+        summary: OutlineSummary,
+        options: XlsxType1GeneratorOptions,
+    ) -> Self {
         let mut header_format = Format::new().set_border(FormatBorder::Thin);
         let mut item_format = Format::new().set_border(FormatBorder::Thin);
 
-        if self.options.shironuri {
+        if options.shironuri {
             header_format = header_format.set_background_color(rust_xlsxwriter::Color::White);
             item_format = item_format.set_background_color(rust_xlsxwriter::Color::White);
         }
+        XlsxType1StreamGenerator {
+            header,
+            summary,
+            options,
+            header_format,
+            item_format,
+            row_index: 0,
+        }
+    }
+}
 
+impl XlsxStreamGenerator for XlsxType1StreamGenerator {
+    fn start(&mut self, worksheet: &mut Worksheet) -> Result<()> {
         // If shironuri is true, set the background color of all cells to white.
         if self.options.shironuri {
             let cell_format = Format::new().set_background_color(rust_xlsxwriter::Color::White);
@@ -38,97 +84,52 @@ impl XlsxType1Generator {
 
         // Write key header and value headers
         let mut headers: Vec<String> = Vec::new();
-        if let Some(key_h) = self.outline.key_header.first() {
+        if let Some(key_h) = self.header.key_header.first() {
             headers.push(key_h.clone());
         } else {
             headers.push("".to_string()); // Placeholder for key header if not present
         }
         // Pad value_header to max_value_length
-        let mut padded_value_headers = self.outline.value_header.clone();
-        padded_value_headers.resize(max_value_length, "".to_string());
+        let mut padded_value_headers = self.header.value_header.clone();
+        padded_value_headers.resize(self.summary.max_value_length, "".to_string());
         headers.extend(padded_value_headers);
 
         for (col_index, header_text) in headers.iter().enumerate() {
             worksheet.write_string_with_format(
-                row_index,
+                self.row_index,
                 col_index as u16,
                 header_text,
-                &header_format,
+                &self.header_format,
             )?;
         }
-        row_index += 1;
-
-        let item_first_row_index = row_index;
-
-        for item in &self.outline.item {
-            let mut row_data = Vec::new();
-            row_data.push(item.key.clone());
-            // Pad item.value to max_value_length
-            let mut padded_item_values = item.value.clone();
-            padded_item_values.resize(max_value_length, "".to_string());
-            row_data.extend(padded_item_values);
-
-            for (col_index, cell_text) in row_data.iter().enumerate() {
-                worksheet.write_string_with_format(
-                    row_index,
-                    col_index as u16,
-                    cell_text,
-                    &item_format,
-                )?;
-            }
-            row_index += 1;
-        }
-
-        // Group rows if outline_rows option is true
-        if self.options.outline_rows {
-            let levels: Vec<_> = self.outline.item.iter().map(|v| v.level).collect();
-            for (level, v) in Self::find_intervals_hierarchical(&levels)
-                .iter()
-                .enumerate()
-            {
-                if level > 0 {
-                    for (first_index, last_index) in v.iter() {
-                        let first_row = *first_index as u32 + item_first_row_index;
-                        let last_row = *last_index as u32 + item_first_row_index;
-                        worksheet.group_rows(first_row, last_row)?;
-                    }
-                }
-            }
-        }
-
+        self.row_index += 1;
         Ok(())
     }
 
-    fn find_intervals(arr: &[u32], threshold: u32) -> Vec<(usize, usize)> {
-        let mut intervals = Vec::new();
-        let mut start = None;
-
-        for (i, &val) in arr.iter().enumerate() {
-            if val >= threshold {
-                if start.is_none() {
-                    start = Some(i);
-                }
-            } else if let Some(s) = start {
-                intervals.push((s, i - 1));
-                start = None;
-            }
+    fn write_item(&mut self, worksheet: &mut Worksheet, item: &OutlineItem) -> Result<()> {
+        let mut row_data = Vec::new();
+        row_data.push(Value::Text(item.key.clone()));
+        // Pad item.value to max_value_length
+        let mut padded_item_values = item.value.clone();
+        padded_item_values.resize(self.summary.max_value_length, Value::default());
+        row_data.extend(padded_item_values);
+
+        for (col_index, cell_value) in row_data.iter().enumerate() {
+            write_value(
+                worksheet,
+                self.row_index,
+                col_index as u16,
+                cell_value,
+                &self.item_format,
+            )?;
         }
 
-        if let Some(s) = start {
-            intervals.push((s, arr.len() - 1));
+        // Group rows if outline_rows option is true
+        if self.options.outline_rows {
+            group_item_row(worksheet, self.row_index, item.level)?;
         }
-
-        intervals
-    }
-
-    fn find_intervals_hierarchical(arr: &[u32]) -> Vec<Vec<(usize, usize)>> {
-        let max_val = match arr.iter().max() {
-            Some(&max) if max > 0 => max,
-            _ => return Vec::new(),
-        };
-        (1..=max_val)
-            .map(|threshold| Self::find_intervals(arr, threshold))
-            .collect()
+        self.row_index += 1;
+        Ok(())
     }
 }
 
@@ -427,17 +428,4 @@ mod tests {
 
         drop(temp_file);
     }
-
-    #[test]
-    fn test_find_intervals_hierarchical() {
-        let data = [1, 1, 2, 3, 3, 1, 2, 3];
-        let result = XlsxType1Generator::find_intervals_hierarchical(&data);
-
-        let expected = vec![
-            vec![(0, 7)],         // threshold = 1
-            vec![(2, 4), (6, 7)], // threshold = 2
-            vec![(3, 4), (7, 7)], // threshold = 3
-        ];
-        assert_eq!(result, expected);
-    }
 }
@@ -1,7 +1,10 @@
-use crate::generator::IntegrateCellsOption;
-use crate::outline::Outline;
+use crate::generator::{
+    group_item_row, write_items, write_value, IntegrateCellsOption, XlsxGenerator,
+    XlsxStreamGenerator,
+};
+use crate::outline::{Outline, OutlineItem, OutlineSummary};
 use anyhow::Result;
-use rust_xlsxwriter::{Format, FormatBorder, Worksheet};
+use rust_xlsxwriter::{ColNum, Format, FormatBorder, RowNum, Worksheet};
 
 #[derive(Debug, Clone)]
 pub struct XlsxType2GeneratorOptions {
@@ -19,20 +22,110 @@ impl XlsxType2Generator {
     pub fn new(outline: Outline, options: XlsxType2GeneratorOptions) -> Self {
         XlsxType2Generator { outline, options }
     }
+}
+
+impl XlsxGenerator for XlsxType2Generator {
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
+        let mut generator = XlsxType2StreamGenerator::new(
+            self.outline.header(),
+            self.outline.summary(),
+            self.options.clone(),
+        );
+        write_items(&mut generator, worksheet, &self.outline.item)?;
+
+        // Rowspan merges span the descendants of each item, so they are added once all
+        // rows are written
+        if self.options.integrate_cells == Some(IntegrateCellsOption::Rowspan) {
+            let mut format_for_integrate = integrate_format(&self.options);
+            for (item_index, item) in self.outline.item.iter().enumerate() {
+                let min_row_index = (item_index + 1) as u32;
+                let mut max_row_index = min_row_index;
+
+                for i in (item_index + 1)..self.outline.item.len() {
+                    if self.outline.item[i].level <= item.level {
+                        break;
+                    }
+                    max_row_index = (i + 1) as u32;
+                }
+
+                if min_row_index != max_row_index {
+                    format_for_integrate =
+                        format_for_integrate.set_border_bottom(FormatBorder::Thin);
+                    let text = &item.key;
+                    worksheet.merge_range(
+                        min_row_index,
+                        (item.level - 1) as u16,
+                        max_row_index,
+                        (item.level - 1) as u16,
+                        text,
+                        &format_for_integrate,
+                    )?;
+                }
+            }
+        }
 
-    pub fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
-        let max_level = self.outline.max_level();
-        let max_value_length = self.outline.max_value_length();
-        let mut row_index = 0;
+        Ok(())
+    }
+
+    fn value_col(&self, _item: &OutlineItem, index: usize) -> Option<ColNum> {
+        ColNum::try_from(self.outline.max_level() as usize + index).ok()
+    }
+}
+
+/// Returns the base format of integrated cells.
+fn integrate_format(options: &XlsxType2GeneratorOptions) -> Format {
+    let mut format_for_integrate = Format::new();
+    if options.shironuri {
+        format_for_integrate =
+            format_for_integrate.set_background_color(rust_xlsxwriter::Color::White);
+    }
+    format_for_integrate = format_for_integrate.set_border_top(FormatBorder::Thin);
+    format_for_integrate.set_border_left(FormatBorder::Thin)
+}
+
+/// Writes the `xlsx_type2` layout one item at a time.
+///
+/// `integrate_cells` of `Colspan` is applied row by row. `Rowspan` needs the rows of the
+/// descendants of each item and is only applied by [`XlsxType2Generator`].
+pub struct XlsxType2StreamGenerator {
+    header: Outline,
+    summary: OutlineSummary,
+    options: XlsxType2GeneratorOptions,
+    header_format: Format,
+    item_format: Format,
+    row_index: RowNum,
+    item_index: usize,
+}
 
+impl XlsxType2StreamGenerator {
+    /// Creates a generator for an outline with the headers of `header` and the size
+    /// given by `summary`.
+    pub fn new(
+        header: Outline,
+        summary: OutlineSummary,
+        options: XlsxType2GeneratorOptions,
+    ) -> Self {
         let mut header_format = Format::new().set_border(FormatBorder::Thin);
         let mut item_format = Format::new().set_border(FormatBorder::Thin);
 
-        if self.options.shironuri {
+        if options.shironuri {
             header_format = header_format.set_background_color(rust_xlsxwriter::Color::White);
             item_format = item_format.set_background_color(rust_xlsxwriter::Color::White);
         }
+        XlsxType2StreamGenerator {
+            header,
+            summary,
+            options,
+            header_format,
+            item_format,
+            row_index: 0,
+            item_index: 0,
+        }
+    }
+}
 
+impl XlsxStreamGenerator for XlsxType2StreamGenerator {
+    fn start(&mut self, worksheet: &mut Worksheet) -> Result<()> {
         // If shironuri is true, set the background color of all cells to white.
         if self.options.shironuri {
             let cell_format = Format::new().set_background_color(rust_xlsxwriter::Color::White);
@@ -41,209 +134,123 @@ impl XlsxType2Generator {
 
         // Write key header and value headers
         let mut col_index = 0;
-        for level in 1..=max_level {
+        for level in 1..=self.summary.max_level {
             let header_text = self
-                .outline
+                .header
                 .key_header
                 .get((level - 1) as usize)
                 .map_or("".to_string(), |s| s.clone());
             worksheet.write_string_with_format(
-                row_index,
+                self.row_index,
                 col_index as u16,
                 &header_text,
-                &header_format,
+                &self.header_format,
             )?;
             col_index += 1;
         }
 
-        for i in 0..max_value_length {
+        for i in 0..self.summary.max_value_length {
             let header_text = self
-                .outline
+                .header
                 .value_header
                 .get(i)
                 .map_or("".to_string(), |s| s.clone());
             worksheet.write_string_with_format(
-                row_index,
+                self.row_index,
                 col_index as u16,
                 &header_text,
-                &header_format,
+                &self.header_format,
             )?;
             col_index += 1;
         }
-        row_index += 1;
+        self.row_index += 1;
+        Ok(())
+    }
 
-        let item_first_row_index = row_index;
+    fn write_item(&mut self, worksheet: &mut Worksheet, item: &OutlineItem) -> Result<()> {
+        let max_level = self.summary.max_level;
+        let item_index = self.item_index;
+        let is_last_item = item_index + 1 == self.summary.item_count;
 
-        for (item_index, item) in self.outline.item.iter().enumerate() {
-            let _key_col_index = item.level - 1;
+        // Apply borders based on Ruby logic
+        for level in 1..=max_level {
+            let mut format_for_level = Format::new();
+            if self.options.shironuri {
+                format_for_level =
+                    format_for_level.set_background_color(rust_xlsxwriter::Color::White);
+            }
+            if level <= item.level {
+                format_for_level = format_for_level.set_border_left(FormatBorder::Thin);
+            }
+            if (level < item.level) || (level == max_level) {
+                format_for_level = format_for_level.set_border_right(FormatBorder::Thin);
+            }
+            if (level >= item.level) || (item_index == 0) {
+                format_for_level = format_for_level.set_border_top(FormatBorder::Thin);
+            }
+            if (level > item.level) || is_last_item {
+                format_for_level = format_for_level.set_border_bottom(FormatBorder::Thin);
+            }
+            worksheet.write_string_with_format(
+                self.row_index,
+                (level - 1) as u16,
+                if level == item.level {
+                    item.key.clone()
+                } else {
+                    "".to_string()
+                },
+                &format_for_level,
+            )?;
+        }
 
-            // Apply borders based on Ruby logic
-            for level in 1..=max_level {
-                let mut format_for_level = Format::new();
-                if self.options.shironuri {
-                    format_for_level =
-                        format_for_level.set_background_color(rust_xlsxwriter::Color::White);
-                }
-                if level <= item.level {
-                    format_for_level = format_for_level.set_border_left(FormatBorder::Thin);
-                }
-                if (level < item.level) || (level == max_level) {
-                    format_for_level = format_for_level.set_border_right(FormatBorder::Thin);
-                }
-                if (level >= item.level) || (item_index == 0) {
-                    format_for_level = format_for_level.set_border_top(FormatBorder::Thin);
-                }
-                if (level > item.level) || (item_index == self.outline.item.len() - 1) {
-                    format_for_level = format_for_level.set_border_bottom(FormatBorder::Thin);
-                }
+        for i in 0..self.summary.max_value_length {
+            if let Some(value) = item.value.get(i) {
+                write_value(
+                    worksheet,
+                    self.row_index,
+                    (max_level + i as u32) as u16,
+                    value,
+                    &self.item_format,
+                )?;
+            } else {
                 worksheet.write_string_with_format(
-                    row_index,
-                    (level - 1) as u16,
-                    if level == item.level {
-                        item.key.clone()
-                    } else {
-                        "".to_string()
-                    },
-                    &format_for_level,
+                    self.row_index,
+                    (max_level + i as u32) as u16,
+                    "",
+                    &self.item_format,
                 )?;
             }
-
-            for i in 0..max_value_length {
-                if let Some(value) = item.value.get(i) {
-                    worksheet.write_string_with_format(
-                        row_index,
-                        (max_level + i as u32) as u16,
-                        value,
-                        &item_format,
-                    )?;
-                } else {
-                    worksheet.write_string_with_format(
-                        row_index,
-                        (max_level + i as u32) as u16,
-                        "",
-                        &item_format,
-                    )?;
-                }
-            }
-            row_index += 1;
         }
 
         // Group rows if outline_rows option is true
         if self.options.outline_rows {
-            let levels: Vec<_> = self.outline.item.iter().map(|v| v.level).collect();
-            for (level, v) in Self::find_intervals_hierarchical(&levels)
-                .iter()
-                .enumerate()
-            {
-                if level > 0 {
-                    for (first_index, last_index) in v.iter() {
-                        let first_row = *first_index as u32 + item_first_row_index;
-                        let last_row = *last_index as u32 + item_first_row_index;
-                        worksheet.group_rows(first_row, last_row)?;
-                    }
-                }
-            }
+            group_item_row(worksheet, self.row_index, item.level)?;
         }
 
         // Integrate cells
-
-        let mut format_for_integrate = Format::new();
-        if self.options.shironuri {
-            format_for_integrate =
-                format_for_integrate.set_background_color(rust_xlsxwriter::Color::White);
-        }
-        format_for_integrate = format_for_integrate.set_border_top(FormatBorder::Thin);
-        format_for_integrate = format_for_integrate.set_border_left(FormatBorder::Thin);
-
-        match self.options.integrate_cells {
-            Some(IntegrateCellsOption::Colspan) => {
-                let max_level = self.outline.max_level();
-
-                for (item_index, item) in self.outline.item.iter().enumerate() {
-                    if item.level < max_level {
-                        format_for_integrate = format_for_integrate.set_border_bottom(
-                            if item_index == self.outline.item.len() - 1 {
-                                FormatBorder::Thin
-                            } else {
-                                FormatBorder::None
-                            },
-                        );
-                        let text = &item.key;
-                        worksheet.merge_range(
-                            (item_index + 1) as u32,
-                            (item.level - 1) as u16,
-                            (item_index + 1) as u32,
-                            (max_level - 1) as u16,
-                            text,
-                            &format_for_integrate,
-                        )?;
-                    }
-                }
-            }
-            Some(IntegrateCellsOption::Rowspan) => {
-                for (item_index, item) in self.outline.item.iter().enumerate() {
-                    let min_row_index = (item_index + 1) as u32;
-                    let mut max_row_index = min_row_index;
-
-                    for i in (item_index + 1)..self.outline.item.len() {
-                        if self.outline.item[i].level <= item.level {
-                            break;
-                        }
-                        max_row_index = (i + 1) as u32;
-                    }
-
-                    if min_row_index != max_row_index {
-                        format_for_integrate =
-                            format_for_integrate.set_border_bottom(FormatBorder::Thin);
-                        let text = &item.key;
-                        worksheet.merge_range(
-                            min_row_index,
-                            (item.level - 1) as u16,
-                            max_row_index,
-                            (item.level - 1) as u16,
-                            text,
-                            &format_for_integrate,
-                        )?;
-                    }
-                }
-            }
-            _ => {}
+        if self.options.integrate_cells == Some(IntegrateCellsOption::Colspan)
+            && item.level < max_level
+        {
+            let format_for_integrate =
+                integrate_format(&self.options).set_border_bottom(if is_last_item {
+                    FormatBorder::Thin
+                } else {
+                    FormatBorder::None
+                });
+            worksheet.merge_range(
+                self.row_index,
+                (item.level - 1) as u16,
+                self.row_index,
+                (max_level - 1) as u16,
+                &item.key,
+                &format_for_integrate,
+            )?;
         }
 
+        self.row_index += 1;
+        self.item_index += 1;
         Ok(())
     }
-
-    fn find_intervals(arr: &[u32], threshold: u32) -> Vec<(usize, usize)> {
-        let mut intervals = Vec::new();
-        let mut start = None;
-
-        for (i, &val) in arr.iter().enumerate() {
-            if val >= threshold {
-                if start.is_none() {
-                    start = Some(i);
-                }
-            } else if let Some(s) = start {
-                intervals.push((s, i - 1));
-                start = None;
-            }
-        }
-
-        if let Some(s) = start {
-            intervals.push((s, arr.len() - 1));
-        }
-
-        intervals
-    }
-
-    fn find_intervals_hierarchical(arr: &[u32]) -> Vec<Vec<(usize, usize)>> {
-        let max_val = match arr.iter().max() {
-            Some(&max) if max > 0 => max,
-            _ => return Vec::new(),
-        };
-        (1..=max_val)
-            .map(|threshold| Self::find_intervals(arr, threshold))
-            .collect()
-    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,7 @@
-use crate::generator::IntegrateCellsOption;
-use crate::outline::Outline;
+use crate::generator::{write_value, IntegrateCellsOption, XlsxGenerator};
+use crate::outline::{Outline, OutlineItem};
 use anyhow::Result;
-use rust_xlsxwriter::{Format, FormatBorder, Worksheet};
+use rust_xlsxwriter::{ColNum, Format, FormatBorder, Worksheet};
 
 #[derive(Debug, Clone)]
 pub struct XlsxType3GeneratorOptions {
@@ -19,8 +19,10 @@ impl XlsxType3Generator {
     pub fn new(outline: Outline, options: XlsxType3GeneratorOptions) -> Self {
         XlsxType3Generator { outline, options }
     }
+}
 
-    pub fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
+impl XlsxGenerator for XlsxType3Generator {
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
         let max_level = self.outline.max_level();
         let max_value_length = self.outline.max_value_length();
         let mut row_index = 0;
@@ -127,17 +129,13 @@ impl XlsxType3Generator {
             }
 
             if let Some(value) = item.value.first() {
-                worksheet.write_string_with_format(
-                    row_index,
-                    item.level as u16,
-                    value,
-                    &item_format,
-                )?;
+                write_value(worksheet, row_index, item.level as u16, value, &item_format)?;
             }
 
             for i in 1..max_value_length {
                 if let Some(value) = item.value.get(i) {
-                    worksheet.write_string_with_format(
+                    write_value(
+                        worksheet,
                         row_index,
                         (max_level + i as u32) as u16,
                         value,
@@ -193,13 +191,20 @@ impl XlsxType3Generator {
             }
             for (item_index, item) in self.outline.item.iter().enumerate() {
                 if item.level < max_level {
-                    let text = item.value.first().map_or("".to_string(), |s| s.clone());
+                    let value = item.value.first().cloned().unwrap_or_default();
                     worksheet.merge_range(
                         item_first_row_index + item_index as u32,
                         item.level as u16,
                         item_first_row_index + item_index as u32,
                         max_level as u16,
-                        &text,
+                        "",
+                        &format_for_integrate,
+                    )?;
+                    write_value(
+                        worksheet,
+                        item_first_row_index + item_index as u32,
+                        item.level as u16,
+                        &value,
                         &format_for_integrate,
                     )?;
                 }
@@ -237,6 +242,16 @@ impl XlsxType3Generator {
         Ok(())
     }
 
+    fn value_col(&self, item: &OutlineItem, index: usize) -> Option<ColNum> {
+        // The first value is right after the key
+        match index {
+            0 => ColNum::try_from(item.level).ok(),
+            _ => ColNum::try_from(self.outline.max_level() as usize + index).ok(),
+        }
+    }
+}
+
+impl XlsxType3Generator {
     fn find_intervals(arr: &[u32], threshold: u32) -> Vec<(usize, usize)> {
         let mut intervals = Vec::new();
         let mut start = None;
@@ -1,5 +1,6 @@
-use crate::generator::IntegrateCellsOption;
-use crate::outline::{Outline, OutlineTree};
+use crate::generator::{write_value, IntegrateCellsOption, XlsxGenerator};
+use crate::outline::Outline;
+use crate::value::Value;
 use anyhow::Result;
 use rust_xlsxwriter::{Format, FormatBorder, Worksheet};
 
@@ -18,8 +19,10 @@ impl XlsxType4Generator {
     pub fn new(outline: Outline, options: XlsxType4GeneratorOptions) -> Self {
         XlsxType4Generator { outline, options }
     }
+}
 
-    pub fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
+impl XlsxGenerator for XlsxType4Generator {
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
         let max_level = self.outline.max_level() as usize;
         let max_value_length = self.outline.max_value_length();
 
@@ -73,21 +76,21 @@ impl XlsxType4Generator {
 
         row_index += 1;
 
-        let mut cell_data: Vec<Vec<Option<String>>> = Vec::new();
+        let mut cell_data: Vec<Vec<Option<Value>>> = Vec::new();
 
         // Pass 1: build display
         let tree = self.outline.to_tree();
-        for node_rc in OutlineTree::descendants(&tree) {
+        for node in tree.root().descendants() {
             while cell_data.len() < row_index as usize {
                 cell_data.push(vec![None; col_index]);
             }
             let row_data = cell_data.last_mut().unwrap();
 
-            if let Some(item) = node_rc.borrow().item() {
-                row_data[item.level as usize - 1] = Some(item.key.clone());
+            if let Some(item) = node.item() {
+                row_data[item.level as usize - 1] = Some(Value::Text(item.key.clone()));
             }
-            if node_rc.borrow().is_leaf() {
-                if let Some(item) = node_rc.borrow().item() {
+            if node.is_leaf() {
+                if let Some(item) = node.item() {
                     for (i, v) in item.value.iter().enumerate() {
                         row_data[max_level + i] = Some(v.clone());
                     }
@@ -120,10 +123,11 @@ impl XlsxType4Generator {
                 }
 
                 let (row_index, col_index) = (j + 1, i);
-                worksheet.write_string_with_format(
+                write_value(
+                    worksheet,
                     row_index as u32,
                     col_index as u16,
-                    key.clone().unwrap_or_default(),
+                    &key.clone().unwrap_or_default(),
                     &format,
                 )?;
             }
@@ -146,6 +150,13 @@ impl XlsxType4Generator {
                             first_col as u16,
                             row_index as u32,
                             last_col as u16,
+                            "",
+                            &item_format,
+                        )?;
+                        write_value(
+                            worksheet,
+                            row_index as u32,
+                            first_col as u16,
                             &row_data[leaf_col_index].clone().unwrap_or_default(),
                             &item_format,
                         )?;
@@ -159,10 +170,11 @@ impl XlsxType4Generator {
             || self.options.integrate_cells == Some(IntegrateCellsOption::Both)
         {
             for (j, row_data) in cell_data.iter().enumerate() {
-                for (i, cell) in row_data.iter().enumerate() {
-                    if let Some(key) = cell {
+                for (i, cell) in row_data[0..max_level].iter().enumerate() {
+                    if let Some(value) = cell {
+                        // The subtree of the item ends at the next item at its level or above
                         if let Some(last_empty_cell_row_index) = (j + 1..cell_data.len())
-                            .take_while(|&row| cell_data[row][i].is_none())
+                            .take_while(|&row| cell_data[row][0..=i].iter().all(|x| x.is_none()))
                             .last()
                         {
                             let ((first_row, last_row), col_index) =
@@ -172,7 +184,14 @@ impl XlsxType4Generator {
                                 col_index as u16,
                                 last_row as u32,
                                 col_index as u16,
-                                key,
+                                "",
+                                &item_format,
+                            )?;
+                            write_value(
+                                worksheet,
+                                first_row as u32,
+                                col_index as u16,
+                                value,
                                 &item_format,
                             )?;
                         }
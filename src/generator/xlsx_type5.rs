@@ -1,8 +1,8 @@
-use crate::generator::IntegrateCellsOption;
-use crate::outline::{Outline, OutlineTree};
+use crate::generator::{write_value, IntegrateCellsOption, XlsxGenerator};
+use crate::outline::Outline;
+use crate::value::Value;
 use anyhow::Result;
 use rust_xlsxwriter::{Format, FormatBorder, Worksheet};
-use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct XlsxType5GeneratorOptions {
@@ -19,8 +19,10 @@ impl XlsxType5Generator {
     pub fn new(outline: Outline, options: XlsxType5GeneratorOptions) -> Self {
         XlsxType5Generator { outline, options }
     }
+}
 
-    pub fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
+impl XlsxGenerator for XlsxType5Generator {
+    fn output_to_worksheet(&self, worksheet: &mut Worksheet) -> Result<()> {
         let max_level = self.outline.max_level() as usize;
         let max_value_length = self.outline.max_value_length();
 
@@ -73,52 +75,36 @@ impl XlsxType5Generator {
         let mut row_index: u32 = 1; // Start from row 1 for data
 
         let tree = self.outline.to_tree();
-        for node_rc in OutlineTree::descendants(&tree) {
-            let node = node_rc.borrow();
+        for node in tree.root().descendants() {
             if node.is_leaf() {
                 let item = node.item().unwrap(); // Leaf node must have an item
 
-                let mut key_cell: Vec<Option<String>> = vec![None; max_level];
-                key_cell[item.level as usize - 1] = Some(item.key.clone());
+                let mut key_cell: Vec<Option<Value>> = vec![None; max_level];
+                key_cell[item.level as usize - 1] = Some(Value::Text(item.key.clone()));
 
                 // Populate ancestors' keys (parent key repetition)
-                let mut current_node_rc = Rc::clone(&node_rc);
-                loop {
-                    let parent_option = {
-                        let node_borrowed = current_node_rc.borrow();
-                        node_borrowed.parent().map(|p| Rc::clone(&p))
-                    };
-
-                    if let Some(parent_rc) = parent_option {
-                        let parent_node = parent_rc.borrow();
-                        if let Some(parent_item) = parent_node.item() {
-                            key_cell[parent_item.level as usize - 1] =
-                                Some(parent_item.key.clone());
-                        }
-                        current_node_rc = Rc::clone(&parent_rc);
-                    } else {
-                        break;
-                    }
+                for parent_item in node.ancestors().filter_map(|ancestor| ancestor.item()) {
+                    key_cell[parent_item.level as usize - 1] =
+                        Some(Value::Text(parent_item.key.clone()));
                 }
 
                 let value_cell = item
                     .value
                     .iter()
                     .map(|s| Some(s.clone()))
-                    .collect::<Vec<Option<String>>>();
+                    .collect::<Vec<Option<Value>>>();
                 let padded_value_cell = pad_array(value_cell, max_value_length);
 
-                let combined_cells: Vec<Option<String>> = key_cell
-                    .into_iter()
-                    .chain(padded_value_cell.into_iter())
-                    .collect();
+                let combined_cells: Vec<Option<Value>> =
+                    key_cell.into_iter().chain(padded_value_cell).collect();
 
                 for (c_idx, cell_val_opt) in combined_cells.iter().enumerate() {
-                    let cell_val = cell_val_opt.as_deref().unwrap_or(""); // Get string slice or empty string
-                    worksheet.write_string_with_format(
+                    let cell_val = cell_val_opt.clone().unwrap_or_default(); // Get value or empty string
+                    write_value(
+                        worksheet,
                         row_index,
                         c_idx as u16,
-                        cell_val,
+                        &cell_val,
                         &item_format, // Always apply thin border
                     )?;
                 }
@@ -144,10 +130,11 @@ impl XlsxType5Generator {
                         if level != max_level {
                             current_format = current_format.set_border_right(FormatBorder::None);
                         }
-                        worksheet.write_string_with_format(
+                        write_value(
+                            worksheet,
                             row_index,
                             (level - 1) as u16,
-                            combined_cells[level - 1].clone().unwrap_or_default(),
+                            &combined_cells[level - 1].clone().unwrap_or_default(),
                             &current_format,
                         )?;
                     }
@@ -178,7 +165,7 @@ impl XlsxType5Generator {
 }
 
 // Helper function for padding array, similar to Ruby's Util.pad_array
-fn pad_array(mut arr: Vec<Option<String>>, target_len: usize) -> Vec<Option<String>> {
+fn pad_array(mut arr: Vec<Option<Value>>, target_len: usize) -> Vec<Option<Value>> {
     while arr.len() < target_len {
         arr.push(None);
     }
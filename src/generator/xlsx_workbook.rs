@@ -0,0 +1,215 @@
+//! Output of several worksheets in one workbook.
+//! 
+//! An `XlsxWorkbookGenerator` writes each of its `XlsxGenerator`s to its own
+//! worksheet, e.g. one per top-level item, one per input file, or one per
+//! `xlsx_typeN` layout of the same outline.
+
+use crate::generator::{Generator, XlsxGenerator};
+use crate::outline::Outline;
+use anyhow::Result;
+use clap::ValueEnum;
+use rust_xlsxwriter::Workbook;
+use std::io::Write;
+
+/// The maximum length of an Excel worksheet name, in characters.
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+
+/// How the output is split across worksheets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SheetSplit {
+    /// One worksheet per level-1 item, with its descendants.
+    TopLevel,
+    /// One worksheet per input file.
+    Input,
+}
+
+/// A generator writing a workbook with one worksheet per `XlsxGenerator`.
+pub struct XlsxWorkbookGenerator {
+    sheets: Vec<(String, Box<dyn XlsxGenerator>)>,
+}
+
+impl XlsxWorkbookGenerator {
+    /// Creates a generator for `sheets`, given as (name, generator) pairs in order.
+    ///
+    /// The names are made valid and unique with [`sheet_names`].
+    pub fn new(sheets: Vec<(String, Box<dyn XlsxGenerator>)>) -> Self {
+        XlsxWorkbookGenerator { sheets }
+    }
+}
+
+impl Generator for XlsxWorkbookGenerator {
+    fn output(&self, writer: &mut dyn Write) -> Result<()> {
+        let names = sheet_names(self.sheets.iter().map(|(name, _)| name.as_str()));
+        let mut workbook = Workbook::new();
+        for ((_, generator), name) in self.sheets.iter().zip(names) {
+            let worksheet = workbook.add_worksheet();
+            worksheet.set_name(name)?;
+            generator.output_to_worksheet(worksheet)?;
+        }
+
+        let buffer = workbook.save_to_buffer()?;
+        writer.write_all(&buffer)?;
+        Ok(())
+    }
+}
+
+/// Derives a valid, unique worksheet name from each of `keys`, in order.
+///
+/// Characters not allowed in worksheet names (`[ ] : * ? / \`) are replaced with `_`,
+/// leading and trailing apostrophes are removed and names are truncated to 31 characters.
+/// An empty name becomes `Sheet`. Names already taken (ignoring case, as Excel does) get a
+/// ` (2)`, ` (3)`, ... suffix.
+pub fn sheet_names<'a>(keys: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    // "History" is reserved by Excel
+    let mut taken = vec!["history".to_string()];
+    let mut names = Vec::new();
+    for key in keys {
+        let base: String = key
+            .chars()
+            .map(|c| match c {
+                '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+                c if c.is_control() => ' ',
+                c => c,
+            })
+            .collect();
+        let base = base.trim().trim_matches('\'');
+        let base = if base.is_empty() { "Sheet" } else { base };
+
+        let mut name = truncate(base, MAX_SHEET_NAME_LENGTH)
+            .trim_end_matches('\'')
+            .trim_end()
+            .to_string();
+        let mut count = 1;
+        while taken.contains(&name.to_lowercase()) {
+            count += 1;
+            let suffix = format!(" ({})", count);
+            name = format!(
+                "{}{}",
+                truncate(base, MAX_SHEET_NAME_LENGTH - suffix.len())
+                    .trim_end_matches('\'')
+                    .trim_end(),
+                suffix
+            );
+        }
+        taken.push(name.to_lowercase());
+        names.push(name);
+    }
+    names
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    s.chars()
+        .take(max_chars)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// Splits `outline` into one outline per level-1 item and its descendants.
+///
+/// # Returns
+///
+/// The key of each level-1 item and its outline, with the headers of `outline`. Items
+/// before the first level-1 item form an outline of their own, named after the first of them.
+pub fn split_top_level(outline: Outline) -> Vec<(String, Outline)> {
+    let mut parts: Vec<(String, Outline)> = Vec::new();
+    for item in outline.item.iter() {
+        if item.level <= 1 || parts.is_empty() {
+            parts.push((item.key.clone(), outline.header()));
+        }
+        if let Some((_, part)) = parts.last_mut() {
+            part.item.push(item.clone());
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::xlsx_type0::{XlsxType0Generator, XlsxType0GeneratorOptions};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_sheet_names() {
+        assert_eq!(
+            sheet_names(["a/b", "A_B", "'quoted'", "", "History", "a_b"]),
+            vec![
+                "a_b",
+                "A_B (2)",
+                "quoted",
+                "Sheet",
+                "History (2)",
+                "a_b (3)"
+            ]
+        );
+
+        let long = "x".repeat(40);
+        let names = sheet_names([long.as_str(), long.as_str()]);
+        assert_eq!(names[0], "x".repeat(31));
+        assert_eq!(names[1], format!("{} (2)", "x".repeat(27)));
+
+        // The 31st character is an apostrophe
+        let quoted = format!("{}'s plan", "a".repeat(30));
+        let names = sheet_names([quoted.as_str(), quoted.as_str()]);
+        assert_eq!(names[0], "a".repeat(30));
+        assert_eq!(names[1], format!("{} (2)", "a".repeat(27)));
+    }
+
+    #[test]
+    fn test_split_top_level() {
+        let mut outline = Outline::new();
+        outline.key_header = vec!["H".to_string()];
+        outline.add_item("0.1", 2, vec![]);
+        outline.add_item("1", 1, vec![]);
+        outline.add_item("1.1", 2, vec![]);
+        outline.add_item("2", 1, vec![]);
+
+        let parts = split_top_level(outline);
+        let keys: Vec<(&str, Vec<&str>)> = parts
+            .iter()
+            .map(|(name, part)| {
+                (
+                    name.as_str(),
+                    part.item.iter().map(|i| i.key.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("0.1", vec!["0.1"]),
+                ("1", vec!["1", "1.1"]),
+                ("2", vec!["2"])
+            ]
+        );
+        assert_eq!(parts[1].1.key_header, vec!["H"]);
+    }
+
+    #[test]
+    fn test_xlsx_workbook_generator() -> Result<()> {
+        let mut outline = Outline::new();
+        outline.add_item("1", 1, vec![]);
+        outline.add_item("2", 1, vec![]);
+
+        let sheets: Vec<(String, Box<dyn XlsxGenerator>)> = split_top_level(outline)
+            .into_iter()
+            .map(|(name, part)| {
+                let generator: Box<dyn XlsxGenerator> = Box::new(XlsxType0Generator::new(
+                    part,
+                    XlsxType0GeneratorOptions::default(),
+                ));
+                (format!("Item {}?", name), generator)
+            })
+            .collect();
+
+        let temp_file = NamedTempFile::with_suffix(".xlsx")?;
+        XlsxWorkbookGenerator::new(sheets).output(&mut std::fs::File::create(temp_file.path())?)?;
+
+        let spreadsheet = umya_spreadsheet::reader::xlsx::read(temp_file.path()).unwrap();
+        assert_eq!(spreadsheet.get_sheet_count(), 2);
+        let worksheet = spreadsheet.get_sheet_by_name("Item 2_").unwrap();
+        assert_eq!(worksheet.get_value((1, 2)), "2");
+        Ok(())
+    }
+}
@@ -15,6 +15,10 @@
 //! - `<indent>` : specified by `--from-indent` option
 //! - `<delimiter>` : specified by `--from-delimiter` option
 //! 
+//! With `--from-auto-indent`, `<indent>` is any run of spaces and tabs instead (tabs advance to the next multiple of `--from-tab-width` columns, default: 4).
+//! A line is nested under the closest previous line with a narrower indentation, so 2-space, 4-space and tab-indented files are read alike.
+//! Lines whose indentation is ambiguous (a step other than the first one of the file, a dedent matching no enclosing line, or a mix of tabs and spaces) are reported as warnings; they do not change the level a line is read at.
+//! 
 //! ### `dir_tree`
 //! 
 //! Directory tree with the glob pattern specified by `--from-glob-pattern` (default: `**/*`)
@@ -34,6 +38,96 @@
 //! [OPML](http://dev.opml.org/)
 //! Treat the `text` attribute as a key text, the other attributes as values.
 //! 
+//! ### `json`
+//! 
+//! JSON interchange format written by the `json` output type.
+//! The key header, value header and every item (key, level, value) are read back losslessly.
+//! 
+//! ### `markdown`
+//! 
+//! [CommonMark](https://commonmark.org/) document.
+//! With `--from-structure=headings` (default), ATX (`#`) and Setext headings form the outline at their heading level;
+//! with `--from-structure=lists`, the items of nested `-`, `*`, `+` and `1.` lists do, at their nesting depth.
+//! Inline markup is removed from keys and code blocks are ignored.
+//! - `--from-task-column` : add a `Done` value column with the state of task-list items (`true` for `[x]`, `false` for `[ ]`)
+//! - `--from-link-column` : add a `URL` value column with the first link of each item
+//! 
+//! ### `json_data`, `yaml`, `toml`
+//! 
+//! JSON, [YAML](https://yaml.org/) and [TOML](https://toml.io/) documents such as configuration files and API payloads.
+//! Object members and array elements become items keyed by member name and by 0-based index;
+//! scalars are the value of their item in the `Value` column (`null` is empty).
+//! - `--from-key-field=FIELD` : in arrays of objects, use the member `FIELD` as the key of each element instead of its index
+//! - `--from-value-fields=FIELD,...` : in arrays of objects, read these members as value columns instead of child items
+//! - `--from-max-depth=N` : stop at level `N`; deeper objects and arrays are kept as compact JSON text
+//! 
+//! ### `csv_adjacency`
+//! 
+//! CSV with a header row where each row refers to its parent row by id (e.g. HR and CMDB exports).
+//! The hierarchy is rebuilt depth-first, siblings in the order of their rows; the other columns are read as values, named after their headers.
+//! Parents missing from the input, duplicate ids and cycles are reported as errors.
+//! - `--from-id-column`, `--from-parent-column`, `--from-key-column` : the headers of the id, parent id and key columns (default: `id`, `parent_id`, `name`); top-level rows have an empty parent id
+//! - `--from-delimiter` : the field delimiter, e.g. `;` or `\t` (default: `,`)
+//! 
+//! ### `csv`, `tsv`
+//! 
+//! Delimited text with one item per row, the inverse of `xlsx_type0` (`tsv` is the same with tab-separated fields).
+//! The first row holds the headers: the header of the key column becomes the key header and the headers of the other columns the value headers,
+//! unless `--from-key-header` / `--from-value-header` are given.
+//! Columns are given by header or by 1-based index.
+//! - `--from-level-column=COLUMN` : the column holding the level of each item (default: `2`, the `Outline Level` column of `xlsx_type0`)
+//! - `--from-key-column=COLUMN` : the column holding the key of each item (default: `1`)
+//! - `--from-path-column=COLUMN` : take the hierarchy from a column holding paths like `a/b/c` instead; the last key of the path is the key of the item, and ancestors without a row of their own are added with empty values
+//! - `--from-path-separator` : the separator of the keys in the path column (default: `/`)
+//! - `--from-no-header` : read the first row as data
+//! - `--from-delimiter` : the field delimiter, e.g. `;` (default: `,` for `csv`, `\t` for `tsv`)
+//! 
+//! ### `xlsx`
+//! 
+//! A worksheet laid out by one of the `xlsx_type*` output types, e.g. a generated workbook edited since.
+//! The first row holds the key and value headers, unless `--from-key-header` / `--from-value-header` are given.
+//! Values are read as text (dates, date-times and durations in ISO 8601), so that `--value-type` can restore their type.
+//! The value of a merged range is the value of its top-left cell.
+//! - `--from-layout={xlsx_type0,xlsx_type1,xlsx_type2,xlsx_type3,xlsx_type4,xlsx_type5}` : the layout of the worksheet (default: `xlsx_type0`).
+//!   With `xlsx_type1`, the level of each row is its Excel row outline level (grouping) plus one, so any sheet with grouped rows can be read.
+//!   With `xlsx_type4` and `xlsx_type5`, only the values of leaves are in the worksheet; ancestors repeated on the following rows (`xlsx_type5`) or left empty or merged (`xlsx_type4`) are read once.
+//! - `--from-sheet=NAME` : the worksheet to read (default: the first worksheet)
+//! - `--from-key-column=COLUMN` : the header or 1-based index of the key column of the `xlsx_type1` layout (default: `1`); the other columns are values
+//! - `--from-levels=N` : the number of key columns of the `xlsx_type2` to `xlsx_type5` layouts, inferred from the cells if not given
+//! - `--from-no-header` : read the first row as data
+//! 
+//! ### `org`
+//! 
+//! [Org](https://orgmode.org/) document.
+//! The number of `*` of a heading is its level, and its title without the TODO keyword, priority and tags is its key.
+//! The TODO keyword, priority, tags (joined with `:`), `SCHEDULED` / `DEADLINE` / `CLOSED` timestamps (in ISO 8601) and the `:PROPERTIES:` drawer entries are values.
+//! TODO keywords are `TODO` and `DONE`, or those of the `#+TODO:` lines of the document.
+//! - `--from-value-header=NAMES` : the value columns to keep, among `TODO`, `PRIORITY`, `TAGS`, `SCHEDULED`, `DEADLINE`, `CLOSED` and property names (default: the ones used in the document)
+//! 
+//! ### `freemind`
+//! 
+//! [FreeMind](https://freemind.sourceforge.io/) / [Freeplane](https://www.freeplane.org/) mind map (`.mm`).
+//! Each `<node>` is an item at its nesting depth, with its `TEXT` attribute (or `NODE` rich content) as the key.
+//! The `<attribute NAME=... VALUE=...>` children of a node are values.
+//! - `--from-value-header=NAMES` : the value columns to keep, among `<attribute>` names and attributes of `<node>` such as `LINK` (default: every `<attribute>` name)
+//! - `--from-note-column` : add a last `Note` column with the notes, converted to plain text
+//! 
+//! ### `xmind`
+//! 
+//! [XMind](https://xmind.com/) workbook (`.xmind`), read from `content.json` (XMind 2020 and later) or `content.xml` (XMind 8 and earlier).
+//! The topic tree of one sheet is read, with the central topic at level 1; attached and floating subtopics are children.
+//! - `--from-sheet=TITLE` : the title of the sheet to read (default: the first sheet)
+//! - `--from-label-column` : add a `Labels` column with the labels of each topic, joined with `, `
+//! - `--from-marker-column` : add a `Markers` column with the marker ids of each topic (e.g. `priority-1`), joined with `, `
+//! - `--from-note-column` : add a `Note` column with the plain-text notes
+//! 
+//! ### `text_tree`
+//! 
+//! Text tree drawn with box-drawing characters, as printed by `tree` or `cargo tree` (`├──`, `└──`, `│`), or with the ASCII variants `|--`, `` `-- ``, `+---` and `\---`.
+//! The level of an item follows from the column of its connector, and the prefix is stripped from the key.
+//! A line without a prefix (such as the `.` root of `tree`) is at level 1; blank lines and the `N directories, M files` report are skipped.
+//! - `--from-split-annotations` : move a trailing version (e.g. `v1.0.86`) and trailing annotations such as `(*)` or `(proc-macro)` to the `Version` and `Annotation` columns
+//! 
 //! ## Types of Output
 //! 
 //! The sample input used in this section are as follows:
@@ -96,29 +190,187 @@
 //! #### Options for `xlsx_type5`
 //! 
 //! `--integrate-cells=colspan` : group columns/rows.
+//! 
+//! ### `json`
+//! 
+//! Lossless JSON serialization of the outline, e.g.:
+//! 
+//! ```json
+//! {"key_header":["H1"],"value_header":["H(1)"],"item":[{"key":"1","level":1,"value":["1(1)"]}]}
+//! ```
+//! 
+//! #### Options for `json`
+//! 
+//! `--pretty=yes` : indent the output.
+//! 
+//! ## Multiple Inputs
+//! 
+//! `--add-input=[TYPE:]FILE` (repeatable) merges other input files with the input. Each file is
+//! read with `TYPE` if given, otherwise with `--from-type`, and with the same `--from-*` options.
+//! `--merge=MODE` selects how they are combined:
+//! 
+//! - `subtree` (default) : each input becomes a level-1 item named after its file, with its items below; files with the same name get a ` (2)`, ` (3)`, ... suffix.
+//! - `concat` : the items of each input follow one another at their own levels.
+//! 
+//! Key headers are reconciled by level (different names for a level are joined with ` / `) and
+//! value headers by name, so that each value stays under its header. A value header repeated
+//! within an input is matched by occurrence: its nth column goes to the nth column of that name.
+//! 
+//! ## Multiple Worksheets
+//! 
+//! `--split-sheets=MODE` writes one worksheet per part of the outline:
+//! 
+//! - `top-level` : one worksheet per level-1 item, with its descendants.
+//! - `input` : one worksheet per input file (see Multiple Inputs).
+//! 
+//! A comma-separated list of xlsx types, e.g. `-t xlsx_type2,xlsx_type3`, writes one worksheet
+//! per type. Worksheet names are derived from the keys, file names and types: characters not
+//! allowed by Excel are replaced with `_`, names are truncated to 31 characters and duplicates
+//! get a ` (2)`, ` (3)`, ... suffix.
+//! 
+//! ## Diff
+//! 
+//! `--diff` compares two revisions of an outline: the input is the old revision and a single
+//! `--add-input` the new one. Items are matched by key path, then items left over by key, so
+//! that a subtree moved to another parent is reported as moved:
+//! 
+//! ```text
+//! htot-conv-rs --diff -t xlsx_type2 old.txt diff.xlsx --add-input new.txt
+//! ```
+//! 
+//! - The output holds the items of both revisions, removed items at their old position, with a
+//!   `Change` value column (`added`, `removed`, `moved` or `changed`). Rows are filled green,
+//!   red (struck through), blue or yellow accordingly, with the `xlsx_type0` to `xlsx_type3`
+//!   layouts (`xlsx_type4` and `xlsx_type5` write no change column for parent items); a
+//!   comma-separated `-t` writes one worksheet per layout.
+//! - When both revisions have value headers, value columns are matched by header name.
+//! - A summary is printed to stderr, e.g. `~ President/VP Sales: Budget 10 -> 12`.
+//! - Transformations such as `--value-type` or `--select` apply to both revisions.
+//! 
+//! ## Value Types
+//! 
+//! Values are read as text. `--value-type` converts them to typed values, which XLSX outputs
+//! write as native Excel numbers, booleans and dates:
+//! 
+//! - `--value-type=auto` : infer the type of each value (`true`/`false`, integers, decimal
+//!   numbers, `2025-01-31`, `2025-01-31T09:00:00`, ISO 8601 durations such as `PT8H0M0S`).
+//! - `--value-type=text,number,date,duration` : declare the type of each value column
+//!   (`auto`, `text`, `integer`, `number`, `boolean`, `date`, `datetime`, `duration`).
+//!   The last type applies to the remaining columns. Durations also accept `h:mm[:ss]`.
+//! 
+//! Dates are formatted as `yyyy-mm-dd`, date-times as `yyyy-mm-dd hh:mm:ss` and durations as
+//! `[h]:mm:ss`. A value that cannot be converted to its declared type is an error.
+//! 
+//! ## Validation
+//! 
+//! `--validate=POLICY` checks the outline for level jumps (e.g. level 1 followed by level 3),
+//! empty keys, duplicate keys among siblings and items whose number of values differs from the
+//! value header:
+//! 
+//! - `error` : fail with the list of issues.
+//! - `warn` : print the issues and continue.
+//! - `clamp` : warn, and repair level jumps by moving items up to one level below their parent.
+//! - `insert-parents` : warn, and repair level jumps by inserting items with an empty key for the missing levels.
+//! 
+//! ## Subtrees
+//! 
+//! `--select`, `--drop` and `--max-level` trim the outline after validation and before any
+//! output, so they apply to every output type:
+//! 
+//! - `--select=PATH` : keep only the item at a `/`-separated key path, e.g. `President/VP Sales`,
+//!   with its descendants, moved up to level 1. The key headers of the levels above are removed.
+//! - `--drop=REGEX` : drop the items whose key matches the regular expression, with their
+//!   descendants (repeatable).
+//! - `--max-level=N` : keep only the items at levels 1 to N.
+//! 
+//! ## Roll-up
+//! 
+//! `--rollup=COLUMN[:FUNC]` fills the empty values of items with children with an aggregate
+//! of the values of their descendant leaves, so that totals appear on every branch row
+//! (repeatable, one per column):
+//! 
+//! - `COLUMN` : a value column, by its 1-based number or its value header.
+//! - `FUNC` : `sum` (default), `min`, `max`, `count` (number of leaves with a numeric value)
+//!   or `average`.
+//! 
+//! Only leaves are aggregated and values already set on branches are kept. Text values are read
+//! as numbers or durations when their type can be inferred; sums of integers stay integers and
+//! sums of durations stay durations. Roll-up happens before `--max-level`, so pruned levels
+//! still count.
+//! 
+//! ## Sorting
+//! 
+//! `--sort=ORDER` reorders the children of each item, keeping their descendants attached, for
+//! any input type. Siblings with equal sort keys keep their order.
+//! 
+//! - `--sort=text` : compare by Unicode code point.
+//! - `--sort=numeric` : compare as numbers; items without a number come last.
+//! - `--sort=natural` : compare runs of digits by value (`item2` before `item10`) and letters
+//!   by the collation rules of `--sort-locale=LOCALE` (e.g. `en`, `ja`, `sv`).
+//! - `--sort-by=COLUMN` : sort by `key` (default), or by a value column given by its 1-based
+//!   number or its value header.
+//! - `--sort-desc` : sort in descending order.
+//! 
+//! ## Computed Columns
+//! 
+//! `--add-column=COLUMNS` adds value columns derived from the tree after the existing values,
+//! in the order given (comma-separated):
+//! 
+//! - `wbs` : hierarchical numbering (`1`, `1.2`, `1.2.1`).
+//! - `path` : the keys of the item and its ancestors, joined by `--path-separator` (default `/`).
+//! - `depth` : the depth of the item, 1 for top-level items.
+//! - `children` : the number of children.
+//! - `leaves` : the number of descendants without children.
+//! - `kind` : `leaf` or `branch`.
+//! 
+//! The columns are computed after all other transformations, so that numbering follows
+//! `--sort`.
+//! 
+//! ## Streaming
+//! 
+//! `--streaming` converts very large outlines in constant memory. The input file is read
+//! twice, once to measure the outline and once to write it, and each row is written straight
+//! to the output file instead of building the outline and the workbook in memory.
+//! 
+//! - Input types: `simple_text`, `mspdi`, `opml`.
+//! - Output types: `xlsx_type0`, `xlsx_type1`, `xlsx_type2` (`--to-integrate-cells=rowspan` is not supported).
+//! - An input file and an output file are required (not stdin / stdout).
+//! - `--value-type` is applied item by item; `--validate`, `--select`, `--drop`,
+//!   `--rollup`, `--max-level`, `--sort`, `--add-column`
+//!   and `--diff` are not supported.
+//! - `--to-outline-rows` and `--to-integrate-cells=colspan` still keep a small record per row.
+//! 
+//! ## Errors
+//! 
+//! Malformed input is reported with the file name, line and column of the offending
+//! position (see [`parser::error::ParseError`]), e.g.:
+//! 
+//! ```text
+//! error: ill-formed document: expected `</outline>`, but `</body>` was found
+//!  --> outline.opml:4:3
+//!   |
+//! 4 |   </body>
+//!   |   ^
+//! ```
 
 pub mod cli;
+pub mod diff;
 pub mod generator;
+pub mod merge;
 pub mod outline;
 pub mod parser;
+pub mod registry;
+pub mod transform;
+pub mod value;
+
+use registry::Registry;
 
+/// Returns the names of the built-in input types.
 pub fn get_parser_types() -> Vec<String> {
-    vec![
-        "simple_text".to_string(),
-        "dir_tree".to_string(),
-        "html_list".to_string(),
-        "mspdi".to_string(),
-        "opml".to_string(),
-    ]
+    Registry::default().parser_types()
 }
 
+/// Returns the names of the built-in output types.
 pub fn get_generator_types() -> Vec<String> {
-    vec![
-        "xlsx_type0".to_string(),
-        "xlsx_type1".to_string(),
-        "xlsx_type2".to_string(),
-        "xlsx_type3".to_string(),
-        "xlsx_type4".to_string(),
-        "xlsx_type5".to_string(),
-    ]
+    Registry::default().generator_types()
 }
@@ -1,23 +1,32 @@
-use clap::Parser;
-use htot_conv_rs::cli::run_conversion;
-
-use htot_conv_rs::generator::xlsx_type0::XlsxType0GeneratorOptions;
-use htot_conv_rs::generator::xlsx_type1::XlsxType1GeneratorOptions;
-use htot_conv_rs::generator::xlsx_type2::XlsxType2GeneratorOptions;
-use htot_conv_rs::generator::xlsx_type3::XlsxType3GeneratorOptions;
-use htot_conv_rs::generator::xlsx_type4::XlsxType4GeneratorOptions;
-use htot_conv_rs::generator::xlsx_type5::XlsxType5GeneratorOptions;
-use htot_conv_rs::generator::GeneratorOptions;
-use htot_conv_rs::parser::dir_tree::DirTreeParserOptions;
-use htot_conv_rs::parser::html_list::HtmlListParserOptions;
-use htot_conv_rs::parser::mspdi::MspdiParserOptions;
-use htot_conv_rs::parser::opml::OpmlParserOptions;
-use htot_conv_rs::parser::simple_text::SimpleTextParserOptions;
-use htot_conv_rs::parser::ParserOptions;
-use htot_conv_rs::{get_generator_types, get_parser_types};
+use clap::{Parser, ValueEnum};
+use htot_conv_rs::cli::{
+    run_conversion, run_diff_conversion, run_streaming_conversion, run_workbook_conversion,
+    ConversionInput,
+};
+use htot_conv_rs::generator::xlsx_workbook::SheetSplit;
+use htot_conv_rs::merge::MergeMode;
+use htot_conv_rs::parser::error::ParseError;
+use htot_conv_rs::registry::{OptionMap, Registry};
+use htot_conv_rs::transform::computed::{
+    ComputedColumn, ComputedColumnsTransform, ComputedColumnsTransformOptions,
+};
+use htot_conv_rs::transform::rollup::{Aggregate, RollupTransform, RollupTransformOptions};
+use htot_conv_rs::transform::sort::{SortBy, SortOrder, SortTransform, SortTransformOptions};
+use htot_conv_rs::transform::subtree::{
+    DropSubtreeTransform, DropSubtreeTransformOptions, MaxLevelTransform, MaxLevelTransformOptions,
+    SelectSubtreeTransform, SelectSubtreeTransformOptions,
+};
+use htot_conv_rs::transform::validate::{
+    ValidateTransform, ValidateTransformOptions, ValidationPolicy,
+};
+use htot_conv_rs::transform::value_type::{ValueTypeTransform, ValueTypeTransformOptions};
+use htot_conv_rs::transform::{ItemTransform, Transform};
+use htot_conv_rs::value::ValueType;
 
 use std::fs::File;
 use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -26,23 +35,30 @@ struct Cli {
     #[arg(short = 'f', long, value_name = "TYPE", default_value = "simple_text")]
     from_type: String,
 
-    /// Type of output (e.g., xlsx_type0, xlsx_type1)
+    /// Type of output (e.g., xlsx_type0, xlsx_type1). A comma-separated list of xlsx types
+    /// writes one worksheet per type.
     #[arg(short = 't', long, value_name = "TYPE", default_value = "xlsx_type2")]
     to_type: String,
 
     /// The string used for indentation (e.g., "  " for two spaces, "\t" for tab).
     #[arg(long = "from-indent", default_value = "\t")]
     indent: String,
-    /// An optional delimiter string used to separate the key from its values.
+    /// Infer the indentation instead of using --from-indent, nesting lines by the width of their leading whitespace and warning about ambiguous lines (for simple_text).
+    #[arg(long = "from-auto-indent", default_value_t = false)]
+    auto_indent: bool,
+    /// The number of columns between tab stops with --from-auto-indent (for simple_text, default: 4).
+    #[arg(long = "from-tab-width", value_name = "N")]
+    tab_width: Option<usize>,
+    /// An optional delimiter string used to separate the key from its values (for simple_text), or the field delimiter (for csv_adjacency, csv, tsv).
     #[arg(long = "from-delimiter")]
     delimiter: Option<String>,
     /// If true, empty lines in the input will be preserved as level-1 items.
     #[arg(long = "from-preserve-empty-line")]
     preserve_empty_line: bool,
-    /// A comma-separated list of strings representing the key headers (for simple_text, dir_tree, html_list, mspdi, opml).
+    /// A comma-separated list of strings representing the key headers (for simple_text, dir_tree, html_list, markdown, mspdi, opml, json_data, yaml, toml, csv_adjacency, csv, tsv, xlsx, org, freemind, xmind, text_tree).
     #[arg(long = "from-key-header")]
     key_header: Option<String>,
-    /// A comma-separated list of strings representing the value headers (for simple_text, mspdi, opml).
+    /// A comma-separated list of strings representing the value headers (for simple_text, markdown, mspdi, opml, json_data, yaml, toml, csv_adjacency, csv, tsv, xlsx, org, freemind, xmind, text_tree).
     #[arg(long = "from-value-header")]
     value_header: Option<String>,
 
@@ -53,6 +69,71 @@ struct Cli {
     #[arg(long = "from-dir-indicator")]
     dir_indicator: Option<String>,
 
+    /// Elements forming the outline of markdown input: headings or nested lists.
+    #[arg(long = "from-structure", value_name = "STRUCTURE")]
+    structure: Option<htot_conv_rs::parser::markdown::MarkdownStructure>,
+    /// Add a value column with the state of task-list items (for markdown).
+    #[arg(long = "from-task-column", default_value_t = false)]
+    task_column: bool,
+    /// Add a value column with the URL of the first link of each item (for markdown).
+    #[arg(long = "from-link-column", default_value_t = false)]
+    link_column: bool,
+    /// Add a value column with the note of each node (for freemind, xmind).
+    #[arg(long = "from-note-column", default_value_t = false)]
+    note_column: bool,
+    /// Add a value column with the labels of each topic (for xmind).
+    #[arg(long = "from-label-column", default_value_t = false)]
+    label_column: bool,
+    /// Add a value column with the markers of each topic (for xmind).
+    #[arg(long = "from-marker-column", default_value_t = false)]
+    marker_column: bool,
+    /// Move trailing versions and parenthesized annotations such as `(*)` to value columns (for text_tree).
+    #[arg(long = "from-split-annotations", default_value_t = false)]
+    split_annotations: bool,
+
+    /// In arrays of objects, the field used as the key of each element (for json_data, yaml, toml).
+    #[arg(long = "from-key-field", value_name = "FIELD")]
+    key_field: Option<String>,
+    /// A comma-separated list of fields of arrays of objects read as value columns (for json_data, yaml, toml).
+    #[arg(long = "from-value-fields", value_name = "FIELDS")]
+    value_fields: Option<String>,
+    /// The deepest level of items; deeper structures are kept as JSON text (for json_data, yaml, toml).
+    #[arg(long = "from-max-depth", value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    max_depth: Option<u32>,
+
+    /// The header of the column identifying each row (for csv_adjacency, default: id).
+    #[arg(long = "from-id-column", value_name = "HEADER")]
+    id_column: Option<String>,
+    /// The header of the column holding the id of the parent row (for csv_adjacency, default: parent_id).
+    #[arg(long = "from-parent-column", value_name = "HEADER")]
+    parent_column: Option<String>,
+    /// The header or 1-based index of the column holding the key of each item (for csv_adjacency, default: name; for csv, tsv, xlsx_type1, default: 1).
+    #[arg(long = "from-key-column", value_name = "HEADER")]
+    key_column: Option<String>,
+
+    /// The header or 1-based index of the column holding the level of each item (for csv, tsv, default: 2).
+    #[arg(long = "from-level-column", value_name = "COLUMN")]
+    level_column: Option<String>,
+    /// The header or 1-based index of a column holding the path of each item, used instead of a level column (for csv, tsv).
+    #[arg(long = "from-path-column", value_name = "COLUMN")]
+    path_column: Option<String>,
+    /// The separator of the keys in the path column (for csv, tsv, default: /).
+    #[arg(long = "from-path-separator", value_name = "SEPARATOR")]
+    from_path_separator: Option<String>,
+    /// Read the first row as data rather than as the key and value headers (for csv, tsv, xlsx).
+    #[arg(long = "from-no-header", default_value_t = false)]
+    no_header: bool,
+
+    /// The layout of the worksheet, named after the output type that writes it (for xlsx).
+    #[arg(long = "from-layout", value_name = "LAYOUT")]
+    layout: Option<htot_conv_rs::parser::xlsx::XlsxLayout>,
+    /// The name of the worksheet to read (for xlsx, default: the first worksheet), or the title of the sheet to read (for xmind, default: the first sheet).
+    #[arg(long = "from-sheet", value_name = "NAME")]
+    sheet: Option<String>,
+    /// The number of key columns, inferred if not given (for xlsx with xlsx_type2 to xlsx_type5 layouts).
+    #[arg(long = "from-levels", value_name = "N")]
+    levels: Option<usize>,
+
     /// Group rows in XLSX output (for xlsx_type1, xlsx_type2, xlsx_type3).
     #[arg(long = "to-outline-rows", default_value_t = false)]
     to_outline_rows: bool,
@@ -65,6 +146,101 @@ struct Cli {
     #[arg(long = "to-shironuri", default_value_t = false)]
     to_shironuri: bool,
 
+    /// Indent the output for readability (for json).
+    #[arg(long = "to-pretty", default_value_t = false)]
+    to_pretty: bool,
+
+    /// Check the outline for level jumps, empty keys, duplicate sibling keys and value-count
+    /// mismatches, and fail, warn or repair level jumps.
+    #[arg(long = "validate", value_name = "POLICY")]
+    validate: Option<ValidationPolicy>,
+
+    /// Keep only the subtree of the item at a key path (e.g. "President/VP Sales"), moved up
+    /// to level 1.
+    #[arg(long = "select", value_name = "PATH")]
+    select: Option<String>,
+
+    /// Drop the items whose key matches a regular expression, with their descendants
+    /// (repeatable).
+    #[arg(long = "drop", value_name = "REGEX")]
+    drop: Vec<regex::Regex>,
+
+    /// Fill the empty values of items with children with the sum, min, max, count or average
+    /// of the values of their descendant leaves, for a value column given by its 1-based
+    /// number or value header (repeatable; default: sum).
+    #[arg(long = "rollup", value_name = "COLUMN[:FUNC]")]
+    rollup: Vec<String>,
+
+    /// Keep only the items up to a maximum level.
+    #[arg(long = "max-level", value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    max_level: Option<u32>,
+
+    /// Sort the children of each item, keeping their descendants attached: by text, as
+    /// numbers, or in natural order (digits by value, letters by the rules of --sort-locale).
+    #[arg(long = "sort", value_name = "ORDER")]
+    sort: Option<SortOrder>,
+
+    /// The sort key: "key", or a value column given by its 1-based number or value header.
+    #[arg(long = "sort-by", value_name = "COLUMN", default_value = "key")]
+    sort_by: SortBy,
+
+    /// Sort in descending order.
+    #[arg(long = "sort-desc", default_value_t = false)]
+    sort_desc: bool,
+
+    /// The locale of --sort=natural (e.g. "en", "ja", "sv").
+    #[arg(long = "sort-locale", value_name = "LOCALE")]
+    sort_locale: Option<String>,
+
+    /// A comma-separated list of value columns computed from the tree, added after the
+    /// values: wbs (1.2.1 numbering), path, depth, children, leaves, kind (leaf/branch).
+    #[arg(long = "add-column", value_name = "COLUMNS", value_delimiter = ',')]
+    add_column: Vec<ComputedColumn>,
+
+    /// The separator of the keys of the path column.
+    #[arg(long = "path-separator", value_name = "SEP", default_value = "/")]
+    path_separator: String,
+
+    /// A comma-separated list of value column types (auto, text, integer, number, boolean, date, datetime, duration).
+    /// The last type applies to the remaining columns.
+    #[arg(long = "value-type", value_name = "TYPES")]
+    value_type: Option<String>,
+
+    /// Convert in constant memory, for very large outlines. The input file is read twice and
+    /// rows are written directly to the output file (simple_text, mspdi and opml input;
+    /// xlsx_type0, xlsx_type1 and xlsx_type2 output).
+    #[arg(long = "streaming", default_value_t = false)]
+    streaming: bool,
+
+    /// Another input file merged with the input, as FILE or TYPE:FILE to read it with another
+    /// input type (repeatable).
+    #[arg(long = "add-input", value_name = "[TYPE:]FILE")]
+    add_input: Vec<String>,
+
+    /// How multiple input files are merged: as subtrees under an item named after each file,
+    /// or concatenated at their own levels.
+    #[arg(long = "merge", value_name = "MODE", default_value = "subtree")]
+    merge: MergeMode,
+
+    /// Compare the input, as the old revision, with a single --add-input, as the new revision:
+    /// write the merged outline with a change column and filled changed rows, and print a
+    /// summary of the changes to stderr.
+    #[arg(long = "diff", default_value_t = false)]
+    diff: bool,
+
+    /// Split the XLSX output into one worksheet per level-1 item (top-level) or per input file
+    /// (input).
+    #[arg(long = "split-sheets", value_name = "MODE")]
+    split_sheets: Option<SheetSplit>,
+
+    /// Additional input option for custom input types, as KEY=VALUE (repeatable).
+    #[arg(long = "from-option", value_name = "KEY=VALUE")]
+    from_option: Vec<String>,
+
+    /// Additional output option for custom output types, as KEY=VALUE (repeatable).
+    #[arg(long = "to-option", value_name = "KEY=VALUE")]
+    to_option: Vec<String>,
+
     /// Input file (default: stdin)
     input: Option<String>,
 
@@ -76,15 +252,40 @@ struct Cli {
     list_type: bool,
 }
 
-fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+/// Adds `KEY=VALUE` pairs given by `--from-option` / `--to-option` to `options`.
+fn set_extra_options(options: &mut OptionMap, pairs: &[String]) -> anyhow::Result<()> {
+    for pair in pairs {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid option '{}': expected KEY=VALUE", pair))?;
+        options.set(key.trim(), value);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // Parse errors are shown as compiler-style diagnostics pointing at the input line
+            match e.downcast_ref::<ParseError>() {
+                Some(parse_error) => eprintln!("{}", parse_error.render()),
+                None => eprintln!("Error: {:?}", e),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> anyhow::Result<()> {
+    let registry = Registry::default();
 
     if cli.list_type {
         println!("type of input:");
-        println!("{}", get_parser_types().join(" "));
+        println!("{}", registry.parser_types().join(" "));
         println!();
         println!("type of output:");
-        println!("{}", get_generator_types().join(" "));
+        println!("{}", registry.generator_types().join(" "));
         println!();
         return Ok(());
     }
@@ -92,91 +293,324 @@ fn main() -> anyhow::Result<()> {
     let input_path_option = cli.input;
     let output_path = cli.output;
 
+    let mut from_options = OptionMap::new();
+    from_options.set("indent", &cli.indent);
+    from_options.set("auto-indent", &cli.auto_indent.to_string());
+    if let Some(tab_width) = cli.tab_width {
+        from_options.set("tab-width", &tab_width.to_string());
+    }
+    if let Some(delimiter) = &cli.delimiter {
+        from_options.set("delimiter", delimiter);
+    }
+    from_options.set("preserve-empty-line", &cli.preserve_empty_line.to_string());
+    if let Some(key_header) = &cli.key_header {
+        from_options.set("key-header", key_header);
+    }
+    if let Some(value_header) = &cli.value_header {
+        from_options.set("value-header", value_header);
+    }
+    if let Some(glob_pattern) = &cli.glob_pattern {
+        from_options.set("glob-pattern", glob_pattern);
+    }
+    if let Some(dir_indicator) = &cli.dir_indicator {
+        from_options.set("dir-indicator", dir_indicator);
+    }
+    if let Some(structure) = &cli.structure {
+        from_options.set(
+            "structure",
+            structure.to_possible_value().unwrap().get_name(),
+        );
+    }
+    from_options.set("task-column", &cli.task_column.to_string());
+    from_options.set("link-column", &cli.link_column.to_string());
+    from_options.set("note-column", &cli.note_column.to_string());
+    from_options.set("label-column", &cli.label_column.to_string());
+    from_options.set("marker-column", &cli.marker_column.to_string());
+    from_options.set("split-annotations", &cli.split_annotations.to_string());
+    if let Some(key_field) = &cli.key_field {
+        from_options.set("key-field", key_field);
+    }
+    if let Some(value_fields) = &cli.value_fields {
+        from_options.set("value-fields", value_fields);
+    }
+    if let Some(max_depth) = cli.max_depth {
+        from_options.set("max-depth", &max_depth.to_string());
+    }
+    for (key, value) in [
+        ("id-column", &cli.id_column),
+        ("parent-column", &cli.parent_column),
+        ("key-column", &cli.key_column),
+        ("level-column", &cli.level_column),
+        ("path-column", &cli.path_column),
+        ("path-separator", &cli.from_path_separator),
+        ("sheet", &cli.sheet),
+    ] {
+        if let Some(value) = value {
+            from_options.set(key, value);
+        }
+    }
+    from_options.set("no-header", &cli.no_header.to_string());
+    if let Some(layout) = &cli.layout {
+        from_options.set("layout", layout.to_possible_value().unwrap().get_name());
+    }
+    if let Some(levels) = cli.levels {
+        from_options.set("levels", &levels.to_string());
+    }
+    set_extra_options(&mut from_options, &cli.from_option)?;
+
+    let mut to_options = OptionMap::new();
+    to_options.set("outline-rows", &cli.to_outline_rows.to_string());
+    if let Some(integrate_cells) = &cli.to_integrate_cells {
+        to_options.set(
+            "integrate-cells",
+            integrate_cells.to_possible_value().unwrap().get_name(),
+        );
+    }
+    to_options.set("shironuri", &cli.to_shironuri.to_string());
+    to_options.set("pretty", &cli.to_pretty.to_string());
+    set_extra_options(&mut to_options, &cli.to_option)?;
+
+    let value_type_transform = match &cli.value_type {
+        Some(value_type) => {
+            let value_type = value_type
+                .split(',')
+                .map(|s| ValueType::from_str(s.trim(), true).map_err(|e| anyhow::anyhow!(e)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Some(ValueTypeTransform::new(ValueTypeTransformOptions {
+                value_type,
+            }))
+        }
+        None => None,
+    };
+
+    if cli.streaming {
+        // Items are never held together, so only per-item transforms apply
+        if cli.validate.is_some() {
+            anyhow::bail!("--validate is not supported with --streaming");
+        }
+        if cli.select.is_some()
+            || !cli.drop.is_empty()
+            || cli.max_level.is_some()
+            || !cli.rollup.is_empty()
+        {
+            anyhow::bail!(
+                "--select, --drop, --rollup and --max-level are not supported with --streaming"
+            );
+        }
+        if cli.sort.is_some() || !cli.add_column.is_empty() {
+            anyhow::bail!("--sort and --add-column are not supported with --streaming");
+        }
+        if cli.diff {
+            anyhow::bail!("--diff is not supported with --streaming");
+        }
+        if !cli.add_input.is_empty() {
+            anyhow::bail!("--add-input is not supported with --streaming");
+        }
+        if cli.split_sheets.is_some() {
+            anyhow::bail!("--split-sheets is not supported with --streaming");
+        }
+        let (Some(input_path), Some(output_path)) = (
+            input_path_option.as_deref().filter(|p| *p != "-"),
+            output_path.as_deref().filter(|p| *p != "-"),
+        ) else {
+            anyhow::bail!("--streaming requires an input file and an output file");
+        };
+        let mut item_transforms: Vec<Box<dyn ItemTransform>> = Vec::new();
+        if let Some(transform) = value_type_transform {
+            item_transforms.push(Box::new(transform));
+        }
+
+        let parser = registry.create_stream_parser(&cli.from_type, &from_options)?;
+        return run_streaming_conversion(
+            Path::new(input_path),
+            Path::new(output_path),
+            parser.as_ref(),
+            &item_transforms,
+            &|header, summary| {
+                registry.create_stream_generator(&cli.to_type, header, summary, &to_options)
+            },
+            &mut |warning| eprintln!("warning: {}", warning),
+        );
+    }
+
+    let mut transforms: Vec<Box<dyn Transform>> = Vec::new();
+    if let Some(policy) = cli.validate {
+        transforms.push(Box::new(
+            ValidateTransform::new(ValidateTransformOptions { policy })
+                .with_on_issue(|issue| eprintln!("warning: {}", issue)),
+        ));
+    }
+    if let Some(path) = &cli.select {
+        transforms.push(Box::new(SelectSubtreeTransform::new(
+            SelectSubtreeTransformOptions {
+                path: path.split('/').map(str::to_string).collect(),
+            },
+        )));
+    }
+    for pattern in &cli.drop {
+        transforms.push(Box::new(DropSubtreeTransform::new(
+            DropSubtreeTransformOptions {
+                pattern: pattern.clone(),
+            },
+        )));
+    }
+    // Typed before --rollup, so that declared types are aggregated
+    if let Some(transform) = value_type_transform {
+        transforms.push(Box::new(transform));
+    }
+    // Rolled up before --max-level, so that pruned levels are still counted
+    if !cli.rollup.is_empty() {
+        let columns = cli
+            .rollup
+            .iter()
+            .map(|spec| {
+                let (column, aggregate) = match spec.rsplit_once(':') {
+                    Some((column, name)) => match Aggregate::from_str(name, true) {
+                        Ok(aggregate) => (column, aggregate),
+                        Err(_) => (spec.as_str(), Aggregate::default()),
+                    },
+                    None => (spec.as_str(), Aggregate::default()),
+                };
+                Ok((
+                    column.parse().map_err(|e| anyhow::anyhow!("{}", e))?,
+                    aggregate,
+                ))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        transforms.push(Box::new(RollupTransform::new(RollupTransformOptions {
+            columns,
+        })));
+    }
+    if let Some(max_level) = cli.max_level {
+        transforms.push(Box::new(MaxLevelTransform::new(MaxLevelTransformOptions {
+            max_level,
+        })));
+    }
+    // Sorted last, so that typed values are compared
+    if let Some(order) = cli.sort {
+        transforms.push(Box::new(SortTransform::new(SortTransformOptions {
+            by: cli.sort_by.clone(),
+            order,
+            descending: cli.sort_desc,
+            locale: cli.sort_locale.clone(),
+        })));
+    }
+
+    // Computed last, so that numbering follows the sorted order and is not typed
+    if !cli.add_column.is_empty() {
+        transforms.push(Box::new(ComputedColumnsTransform::new(
+            ComputedColumnsTransformOptions {
+                columns: cli.add_column.clone(),
+                path_separator: cli.path_separator.clone(),
+            },
+        )));
+    }
+
     // Prepare output writer
     let mut output_writer: Box<dyn Write> = match output_path {
         Some(path) if path != "-" => Box::new(File::create(path)?),
         _ => Box::new(io::stdout()),
     };
 
-    let parsed_key_header: Vec<String> = cli
-        .key_header
-        .map(|s| s.split(',').map(|s| s.trim().to_string()).collect())
-        .unwrap_or_default();
-    let parsed_value_header: Vec<String> = cli
-        .value_header
-        .map(|s| s.split(',').map(|s| s.trim().to_string()).collect())
-        .unwrap_or_default();
-
-    let from_options = match cli.from_type.as_str() {
-        "simple_text" => ParserOptions::SimpleText(SimpleTextParserOptions {
-            indent: cli.indent,
-            delimiter: cli.delimiter,
-            preserve_empty_line: cli.preserve_empty_line,
-            key_header: parsed_key_header,
-            value_header: parsed_value_header,
-        }),
-        "dir_tree" => ParserOptions::DirTree(DirTreeParserOptions {
-            key_header: parsed_key_header,
-            glob_pattern: cli.glob_pattern,
-            dir_indicator: cli.dir_indicator,
-        }),
-        "html_list" => ParserOptions::HtmlList(HtmlListParserOptions {
-            key_header: parsed_key_header,
-        }),
-        "mspdi" => ParserOptions::Mspdi(MspdiParserOptions {
-            key_header: parsed_key_header,
-            value_header: parsed_value_header,
-        }),
-        "opml" => ParserOptions::Opml(OpmlParserOptions {
-            key_header: parsed_key_header,
-            value_header: parsed_value_header,
-        }),
-        _ => anyhow::bail!(
-            "Unsupported from_type: {}. Supported types are: {}",
-            cli.from_type,
-            get_parser_types().join(", ")
-        ),
-    };
-
-    let to_options = match cli.to_type.as_str() {
-        "xlsx_type0" => GeneratorOptions::XlsxType0(XlsxType0GeneratorOptions {
-            shironuri: cli.to_shironuri,
-        }),
-        "xlsx_type1" => GeneratorOptions::XlsxType1(XlsxType1GeneratorOptions {
-            outline_rows: cli.to_outline_rows,
-            shironuri: cli.to_shironuri,
-        }),
-        "xlsx_type2" => GeneratorOptions::XlsxType2(XlsxType2GeneratorOptions {
-            outline_rows: cli.to_outline_rows,
-            integrate_cells: cli.to_integrate_cells,
-            shironuri: cli.to_shironuri,
-        }),
-        "xlsx_type3" => GeneratorOptions::XlsxType3(XlsxType3GeneratorOptions {
-            outline_rows: cli.to_outline_rows,
-            integrate_cells: cli.to_integrate_cells,
-            shironuri: cli.to_shironuri,
-        }),
-        "xlsx_type4" => GeneratorOptions::XlsxType4(XlsxType4GeneratorOptions {
-            integrate_cells: cli.to_integrate_cells,
-            shironuri: cli.to_shironuri,
-        }),
-        "xlsx_type5" => GeneratorOptions::XlsxType5(XlsxType5GeneratorOptions {
-            integrate_cells: cli.to_integrate_cells,
-            shironuri: cli.to_shironuri,
-        }),
-        _ => anyhow::bail!(
-            "Unsupported to_type: {}. Supported types are: {}",
-            cli.to_type,
-            get_generator_types().join(", ")
-        ),
-    };
+    // The input, then each --add-input with its own parser
+    let mut input_specs = vec![(cli.from_type.as_str(), input_path_option)];
+    for spec in &cli.add_input {
+        input_specs.push(match spec.split_once(':') {
+            Some((from_type, path)) if registry.parser_types().iter().any(|t| t == from_type) => {
+                (from_type, Some(path.to_string()))
+            }
+            _ => (cli.from_type.as_str(), Some(spec.clone())),
+        });
+    }
+    let parsers = input_specs
+        .iter()
+        .map(|(from_type, _)| registry.create_parser(from_type, &from_options))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let inputs: Vec<ConversionInput> = input_specs
+        .into_iter()
+        .zip(&parsers)
+        .map(|((_, path), parser)| ConversionInput {
+            path,
+            parser: parser.as_ref(),
+        })
+        .collect();
 
-    run_conversion(
-        &input_path_option,
-        &mut output_writer,
-        from_options,
-        to_options,
-    )?;
+    let layouts: Vec<String> = cli
+        .to_type
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .collect();
+    if cli.diff {
+        let [old, new] = inputs.as_slice() else {
+            anyhow::bail!("--diff requires an input file and a single --add-input");
+        };
+        if cli.split_sheets.is_some() {
+            anyhow::bail!("--split-sheets is not supported with --diff");
+        }
+        run_diff_conversion(
+            old,
+            new,
+            &layouts,
+            &mut output_writer,
+            &mut io::stderr(),
+            &transforms,
+            &|layout, outline| registry.create_xlsx_generator(layout, outline, &to_options),
+            &mut |warning| eprintln!("warning: {}", warning),
+        )?;
+    } else if layouts.len() > 1 || cli.split_sheets.is_some() {
+        run_workbook_conversion(
+            &inputs,
+            cli.merge,
+            cli.split_sheets,
+            &layouts,
+            &mut output_writer,
+            &transforms,
+            &|layout, outline| registry.create_xlsx_generator(layout, outline, &to_options),
+            &mut |warning| eprintln!("warning: {}", warning),
+        )?;
+    } else {
+        run_conversion(
+            &inputs,
+            cli.merge,
+            &mut output_writer,
+            &transforms,
+            &|outline| registry.create_generator(&cli.to_type, outline, &to_options),
+            &mut |warning| eprintln!("warning: {}", warning),
+        )?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use htot_conv_rs::parser::json::JsonParser;
+    use htot_conv_rs::value::Value;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_run_rollup_declared_value_type() -> anyhow::Result<()> {
+        let mut input = NamedTempFile::new()?;
+        input.write_all(b"Plan\n\tDesign,8:30\n\tBuild,1:30\n")?;
+        let output = NamedTempFile::new()?;
+        let path = |file: &NamedTempFile| file.path().to_string_lossy().into_owned();
+        run(Cli::try_parse_from([
+            "htot-conv-rs".to_string(),
+            "--from-delimiter=,".to_string(),
+            "--value-type=duration".to_string(),
+            "--rollup=1".to_string(),
+            "-t".to_string(),
+            "json".to_string(),
+            path(&input),
+            path(&output),
+        ])?)?;
+
+        let outline = JsonParser::new().parse(&std::fs::read_to_string(output.path())?)?;
+        assert_eq!(
+            outline.item[0].value,
+            vec![Value::Duration(chrono::TimeDelta::hours(10))]
+        );
+        Ok(())
+    }
+}
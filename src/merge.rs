@@ -0,0 +1,318 @@
+//! Combining the outlines of several input files into one.
+//! 
+//! Headers are reconciled across the inputs: key headers by level, value
+//! headers by name and occurrence, so that the values of every item stay under the right
+//! column.
+
+use crate::outline::{Outline, OutlineItem};
+use crate::value::Value;
+use clap::ValueEnum;
+
+/// How the outlines of several inputs are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum MergeMode {
+    /// Put each input under a level-1 item named after its file.
+    #[default]
+    Subtree,
+    /// Append the items of each input one after another, at their own levels.
+    Concat,
+}
+
+/// Identifies a value column across inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Column {
+    /// A column with a non-empty value header, matched by name and 0-based occurrence of
+    /// the name in its input, so that the nth column named `X` of an input goes to the nth
+    /// column named `X` of the others.
+    Named(String, usize),
+    /// A column without a header, matched by position.
+    Unnamed(usize),
+}
+
+/// Merges the outlines of `inputs`, given as (name, outline) pairs in order.
+///
+/// Key headers are reconciled by level: the distinct non-empty headers given for a level
+/// are joined with `" / "`. In `Subtree` mode, the levels of the inputs are shifted down by
+/// one, below an item named after each input, and an empty key header is added for the new
+/// first level when any key header is set.
+///
+/// Value headers are reconciled by name: the merged value header lists the distinct names
+/// in order of first appearance, a name repeated within an input as many times as it is
+/// repeated there, followed by the columns without a header, and the values
+/// of every item are moved to the column of their header. When no input has a value header,
+/// values are kept as they are.
+pub fn merge_outlines(inputs: Vec<(String, Outline)>, mode: MergeMode) -> Outline {
+    let key_header = merge_key_headers(inputs.iter().map(|(_, o)| &o.key_header));
+    let mut merged = Outline::new();
+    merged.key_header = match mode {
+        MergeMode::Subtree if !key_header.is_empty() => {
+            std::iter::once(String::new()).chain(key_header).collect()
+        }
+        _ => key_header,
+    };
+
+    let has_value_header = inputs
+        .iter()
+        .any(|(_, o)| o.value_header.iter().any(|h| !h.is_empty()));
+    let columns = merge_columns(inputs.iter().map(|(_, o)| o));
+    if has_value_header {
+        merged.value_header = column_headers(&columns);
+    }
+
+    let names = unique_names(inputs.iter().map(|(name, _)| name.as_str()));
+    for ((_, outline), name) in inputs.into_iter().zip(names) {
+        let level_offset = match mode {
+            MergeMode::Subtree => {
+                merged.item.push(OutlineItem {
+                    key: name,
+                    level: 1,
+                    value: vec![Value::default(); merged.value_header.len()],
+                });
+                1
+            }
+            MergeMode::Concat => 0,
+        };
+
+        let positions = column_positions(&outline, &columns);
+        for mut item in outline.item {
+            item.level += level_offset;
+            if has_value_header {
+                move_values(&mut item, &positions, columns.len());
+            }
+            merged.item.push(item);
+        }
+    }
+    merged
+}
+
+/// Reconciles the value headers of `outlines` by name, as [`merge_outlines`] does: each
+/// outline gets the merged value header, and the values of its items are moved to the
+/// column of their header.
+pub(crate) fn reconcile_value_headers(outlines: &mut [&mut Outline]) {
+    let columns = merge_columns(outlines.iter().map(|o| &**o));
+    let header = column_headers(&columns);
+    for outline in outlines.iter_mut() {
+        let positions = column_positions(outline, &columns);
+        for item in &mut outline.item {
+            move_values(item, &positions, columns.len());
+        }
+        outline.value_header = header.clone();
+    }
+}
+
+/// Returns the value header of the merged `columns`.
+fn column_headers(columns: &[Column]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|c| match c {
+            Column::Named(name, _) => name.clone(),
+            Column::Unnamed(_) => String::new(),
+        })
+        .collect()
+}
+
+/// Returns the position in the merged `columns` of each value column of `outline`.
+fn column_positions(outline: &Outline, columns: &[Column]) -> Vec<usize> {
+    input_columns(outline)
+        .iter()
+        .map(|c| {
+            columns
+                .iter()
+                .position(|m| m == c)
+                .expect("column is merged")
+        })
+        .collect()
+}
+
+/// Moves the values of `item` to their `positions` among `len` merged columns.
+fn move_values(item: &mut OutlineItem, positions: &[usize], len: usize) {
+    let mut value = vec![Value::default(); len];
+    for (v, &position) in std::mem::take(&mut item.value).into_iter().zip(positions) {
+        value[position] = v;
+    }
+    item.value = value;
+}
+
+fn merge_key_headers<'a>(headers: impl Iterator<Item = &'a Vec<String>>) -> Vec<String> {
+    let mut names: Vec<Vec<&str>> = Vec::new();
+    for header in headers {
+        if names.len() < header.len() {
+            names.resize(header.len(), Vec::new());
+        }
+        for (level_names, name) in names.iter_mut().zip(header) {
+            if !name.is_empty() && !level_names.contains(&name.as_str()) {
+                level_names.push(name);
+            }
+        }
+    }
+    names.iter().map(|n| n.join(" / ")).collect()
+}
+
+/// Makes `names` unique: names already taken get a ` (2)`, ` (3)`, ... suffix, as worksheet
+/// names do in [`crate::generator::xlsx_workbook::sheet_names`].
+fn unique_names<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for name in names {
+        let mut candidate = name.to_string();
+        let mut count = 1;
+        while unique.contains(&candidate) {
+            count += 1;
+            candidate = format!("{} ({})", name, count);
+        }
+        unique.push(candidate);
+    }
+    unique
+}
+
+/// Returns the value columns of `outline`, one per value of its widest item or header.
+fn input_columns(outline: &Outline) -> Vec<Column> {
+    let mut occurrences: Vec<&str> = Vec::new();
+    (0..outline.max_value_length())
+        .map(|i| match outline.value_header.get(i) {
+            Some(name) if !name.is_empty() => {
+                let occurrence = occurrences.iter().filter(|n| *n == name).count();
+                occurrences.push(name);
+                Column::Named(name.clone(), occurrence)
+            }
+            _ => Column::Unnamed(i),
+        })
+        .collect()
+}
+
+fn merge_columns<'a>(outlines: impl Iterator<Item = &'a Outline>) -> Vec<Column> {
+    let mut named = Vec::new();
+    let mut unnamed = Vec::new();
+    for column in outlines.flat_map(input_columns) {
+        let columns = match column {
+            Column::Named(..) => &mut named,
+            Column::Unnamed(_) => &mut unnamed,
+        };
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    named.extend(unnamed);
+    named
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(
+        key_header: &[&str],
+        value_header: &[&str],
+        items: &[(&str, u32, &[&str])],
+    ) -> Outline {
+        Outline {
+            key_header: key_header.iter().map(|s| s.to_string()).collect(),
+            value_header: value_header.iter().map(|s| s.to_string()).collect(),
+            item: items
+                .iter()
+                .map(|(key, level, value)| {
+                    OutlineItem::new(key, *level, value.iter().map(|s| s.to_string()).collect())
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_outlines_subtree() {
+        let a = outline(&["Task"], &[], &[("1", 1, &[]), ("1.1", 2, &[])]);
+        let b = outline(&[], &[], &[("2", 1, &[])]);
+        let merged = merge_outlines(
+            vec![("a.txt".to_string(), a), ("b.txt".to_string(), b)],
+            MergeMode::Subtree,
+        );
+        assert_eq!(
+            merged,
+            outline(
+                &["", "Task"],
+                &[],
+                &[
+                    ("a.txt", 1, &[]),
+                    ("1", 2, &[]),
+                    ("1.1", 3, &[]),
+                    ("b.txt", 1, &[]),
+                    ("2", 2, &[]),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_merge_outlines_concat_reconciles_headers() {
+        let a = outline(
+            &["Phase", "Task"],
+            &["Start", "Owner"],
+            &[("1", 1, &["s1", "o1"]), ("1.1", 2, &["s2"])],
+        );
+        let b = outline(
+            &["Stage"],
+            &["Owner", "Cost", ""],
+            &[("2", 1, &["o3", "c3", "x3"])],
+        );
+        let merged = merge_outlines(
+            vec![("a".to_string(), a), ("b".to_string(), b)],
+            MergeMode::Concat,
+        );
+        assert_eq!(
+            merged,
+            outline(
+                &["Phase / Stage", "Task"],
+                &["Start", "Owner", "Cost", ""],
+                &[
+                    ("1", 1, &["s1", "o1", "", ""]),
+                    ("1.1", 2, &["s2", "", "", ""]),
+                    ("2", 1, &["", "o3", "c3", "x3"]),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_merge_outlines_duplicate_names() {
+        let a = outline(
+            &[],
+            &["Owner", "Note", "Owner"],
+            &[("1", 1, &["o1", "n1", "o2"])],
+        );
+        let b = outline(&[], &["Owner"], &[("2", 1, &["o3"])]);
+        let c = outline(&[], &[], &[("3", 1, &[])]);
+        let merged = merge_outlines(
+            vec![
+                ("data.txt".to_string(), a),
+                ("data.txt".to_string(), b),
+                ("data.txt".to_string(), c),
+            ],
+            MergeMode::Subtree,
+        );
+        assert_eq!(
+            merged,
+            outline(
+                &[],
+                &["Owner", "Note", "Owner"],
+                &[
+                    ("data.txt", 1, &["", "", ""]),
+                    ("1", 2, &["o1", "n1", "o2"]),
+                    ("data.txt (2)", 1, &["", "", ""]),
+                    ("2", 2, &["o3", "", ""]),
+                    ("data.txt (3)", 1, &["", "", ""]),
+                    ("3", 2, &["", "", ""]),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_merge_outlines_without_value_header() {
+        let a = outline(&[], &[], &[("1", 1, &["a", "b"])]);
+        let b = outline(&[], &[], &[("2", 1, &["c"])]);
+        let merged = merge_outlines(
+            vec![("a".to_string(), a.clone()), ("b".to_string(), b)],
+            MergeMode::Concat,
+        );
+        assert_eq!(merged.item[0], a.item[0]);
+        assert_eq!(merged.item[1].value, vec![Value::from("c")]);
+    }
+}
@@ -3,8 +3,8 @@
 //! This module defines the fundamental data structures used to represent
 //! hierarchical outline data within the application.
 
-use std::cell::RefCell;
-use std::rc::{Rc, Weak};
+use crate::value::Value;
+use serde::{de, Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 
 /// Represents errors that can occur during Outline validation.
@@ -18,14 +18,30 @@ pub enum OutlineError {
 /// Represents a single item within an Outline structure.
 ///
 /// An item consists of a key, a level (indentation), and a list of associated values.
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+///
+/// When deserialized, `level` is required and must be positive; `key` and `value` default to
+/// empty.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub struct OutlineItem {
     /// The main key or title of the outline item.
+    #[serde(default)]
     pub key: String,
     /// The indentation level of the item, starting from 1.
+    #[serde(deserialize_with = "deserialize_level")]
     pub level: u32,
     /// A list of additional values associated with the item.
-    pub value: Vec<String>,
+    #[serde(default)]
+    pub value: Vec<Value>,
+}
+
+fn deserialize_level<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    match u32::deserialize(deserializer)? {
+        0 => Err(de::Error::invalid_value(
+            de::Unexpected::Unsigned(0),
+            &"a level of 1 or more",
+        )),
+        level => Ok(level),
+    }
 }
 
 impl OutlineItem {
@@ -35,12 +51,12 @@ impl OutlineItem {
     ///
     /// * `key` - The main key of the item.
     /// * `level` - The indentation level.
-    /// * `value` - A vector of associated values.
+    /// * `value` - A vector of associated values, stored as `Value::Text`.
     pub fn new(key: &str, level: u32, value: Vec<String>) -> Self {
         OutlineItem {
             key: key.to_string(),
             level,
-            value,
+            value: value.into_iter().map(Value::Text).collect(),
         }
     }
 
@@ -73,7 +89,8 @@ impl OutlineItem {
 /// Represents an entire Outline structure.
 ///
 /// An outline consists of optional key and value headers, and a list of `OutlineItem`s.
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Outline {
     /// Header for the keys, typically representing column names for different levels.
     pub key_header: Vec<String>,
@@ -95,34 +112,20 @@ impl Outline {
     ///
     /// * `key` - The main key of the item.
     /// * `level` - The indentation level.
-    /// * `value` - A vector of associated values.
+    /// * `value` - A vector of associated values, stored as `Value::Text`.
     pub fn add_item(&mut self, key: &str, level: u32, value: Vec<String>) {
         self.item.push(OutlineItem::new(key, level, value));
     }
 
     /// Validates the entire `Outline` structure.
     ///
-    /// Checks if `key_header` and `value_header` elements are valid strings,
-    /// and validates each `OutlineItem` within the outline.
+    /// Validates each `OutlineItem` within the outline. Structural issues such as level
+    /// jumps are reported by [`crate::transform::validate`].
     ///
     /// # Returns
     ///
     /// `Ok(())` if the outline is valid, otherwise an `OutlineError`.
     pub fn validate(&self) -> Result<(), OutlineError> {
-        if !self.key_header.iter().all(|v| v.is_empty() || v.is_ascii()) {
-            return Err(OutlineError::ValidationError(
-                "key_header elements must be strings.".to_string(),
-            ));
-        }
-        if !self
-            .value_header
-            .iter()
-            .all(|v| v.is_empty() || v.is_ascii())
-        {
-            return Err(OutlineError::ValidationError(
-                "value_header elements must be strings.".to_string(),
-            ));
-        }
         for item in &self.item {
             item.validate()?;
         }
@@ -158,6 +161,24 @@ impl Outline {
             .unwrap_or(0) // The `chain` ensures the iterator is never empty, guaranteeing a `Some` value from `max()`
     }
 
+    /// Returns a copy of the key header and value header, without the items.
+    pub fn header(&self) -> Outline {
+        Outline {
+            key_header: self.key_header.clone(),
+            value_header: self.value_header.clone(),
+            item: Vec::new(),
+        }
+    }
+
+    /// Returns the maximum level, maximum number of values and number of items of the outline.
+    pub fn summary(&self) -> OutlineSummary {
+        let mut summary = OutlineSummary::new(self);
+        for item in &self.item {
+            summary.add_item(item);
+        }
+        summary
+    }
+
     /// Converts the `Outline` into a hierarchical `OutlineTree` structure.
     ///
     /// This method builds a tree where each node represents an `OutlineItem`
@@ -165,220 +186,285 @@ impl Outline {
     ///
     /// # Returns
     ///
-    /// An `Rc<RefCell<OutlineTree>>` representing the root of the constructed tree.
-    pub fn to_tree(&self) -> Rc<RefCell<OutlineTree>> {
-        let root = OutlineTree::new_root();
-        let mut last_node_rc = Rc::clone(&root);
+    /// An `OutlineTree` whose root node has no item.
+    pub fn to_tree(&self) -> OutlineTree {
+        let mut tree = OutlineTree::new();
+        let mut last_node = tree.root().id();
 
         for item in &self.item {
-            let mut parent_node_rc = Rc::clone(&root);
+            let mut parent_node = tree.root().id();
 
             if item.level > 1 {
-                let last_node_borrow = last_node_rc.borrow();
-                if let Some(last_node_item) = last_node_borrow.item() {
-                    if item.level > last_node_item.level {
-                        parent_node_rc = Rc::clone(&last_node_rc);
-                    } else {
-                        let mut current_search_node_rc = Rc::clone(&last_node_rc);
-                        loop {
-                            let parent_option = {
-                                let current_search_node_borrow = current_search_node_rc.borrow();
-                                if current_search_node_borrow.is_root() {
-                                    break;
-                                }
-                                if let Some(current_search_node_item) =
-                                    current_search_node_borrow.item()
-                                {
-                                    if current_search_node_item.level < item.level {
-                                        break;
-                                    }
-                                }
-                                current_search_node_borrow.parent.upgrade()
-                            }; // current_search_node_borrow is dropped here
-
-                            if let Some(p) = parent_option {
-                                current_search_node_rc = p;
-                            } else {
-                                break; // Should not happen for non-root nodes
-                            }
-                        }
-                        parent_node_rc = current_search_node_rc;
+                // The parent is the nearest preceding node with a lower level
+                let mut search_node = tree.node(last_node);
+                while let (Some(search_item), Some(parent)) =
+                    (search_node.item(), search_node.parent())
+                {
+                    if search_item.level < item.level {
+                        break;
                     }
+                    search_node = parent;
                 }
+                parent_node = search_node.id();
             }
-            let new_node_rc = OutlineTree::add_child(&parent_node_rc, item.clone());
-            last_node_rc = new_node_rc;
+            last_node = tree.add_child(parent_node, item.clone());
         }
-        root
+        tree
     }
 }
 
-/// Represents a node in the hierarchical `OutlineTree`.
-///
-/// Each node contains an `OutlineItem` (if not the root), a weak reference to its parent,
-/// and a list of its children.
-#[derive(Debug)]
-pub struct OutlineTree {
+/// The size of an outline, gathered item by item when the items are not held in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OutlineSummary {
+    /// Same as `Outline::max_level`.
+    pub max_level: u32,
+    /// Same as `Outline::max_value_length`.
+    pub max_value_length: usize,
+    /// The number of items.
+    pub item_count: usize,
+}
+
+impl OutlineSummary {
+    /// Creates a summary of the headers of `header`, ignoring its items.
+    pub fn new(header: &Outline) -> Self {
+        let mut summary = Self::default();
+        summary.add_header(header);
+        summary
+    }
+
+    /// Adds the headers of `header` to the summary, ignoring its items.
+    pub fn add_header(&mut self, header: &Outline) {
+        self.max_level = self.max_level.max(header.key_header.len() as u32);
+        self.max_value_length = self.max_value_length.max(header.value_header.len());
+    }
+
+    /// Adds `item` to the summary.
+    pub fn add_item(&mut self, item: &OutlineItem) {
+        self.max_level = self.max_level.max(item.level);
+        self.max_value_length = self.max_value_length.max(item.value.len());
+        self.item_count += 1;
+    }
+}
+
+/// Identifies a node of an `OutlineTree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone)]
+struct OutlineNode {
     /// The `OutlineItem` associated with this node. `None` for the root node.
     item: Option<OutlineItem>,
-    /// A weak reference to the parent node. Used to prevent reference cycles.
-    parent: Weak<RefCell<OutlineTree>>,
-    /// A list of child nodes.
-    children: Vec<Rc<RefCell<OutlineTree>>>,
+    /// The parent node. `None` for the root node.
+    parent: Option<NodeId>,
+    /// The child nodes, in order.
+    children: Vec<NodeId>,
+    /// The position of this node in the children of its parent.
+    sibling_index: usize,
+}
+
+/// A hierarchical tree of `OutlineItem`s.
+///
+/// The nodes are stored in an arena and refer to each other by `NodeId`, so that parent and
+/// sibling access is O(1), cloning is cheap and the tree is `Send + Sync`. Nodes are read
+/// through `NodeRef`s obtained from [`OutlineTree::root`] or [`OutlineTree::node`].
+#[derive(Debug, Clone)]
+pub struct OutlineTree {
+    nodes: Vec<OutlineNode>,
+}
+
+impl Default for OutlineTree {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OutlineTree {
-    /// Creates a new root `OutlineTree` node.
-    pub fn new_root() -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(OutlineTree {
-            item: None,
-            parent: Weak::new(),
-            children: Vec::new(),
-        }))
+    /// Creates a new tree consisting of a root node only.
+    pub fn new() -> Self {
+        OutlineTree {
+            nodes: vec![OutlineNode {
+                item: None,
+                parent: None,
+                children: Vec::new(),
+                sibling_index: 0,
+            }],
+        }
     }
 
-    /// Creates a new `OutlineTree` node with a parent.
-    ///
-    /// # Arguments
-    ///
-    /// * `item` - The `OutlineItem` for this node.
-    /// * `parent_rc` - An `Rc<RefCell<OutlineTree>>` to the parent node.
-    pub fn new_with_parent(
-        item: OutlineItem,
-        parent_rc: &Rc<RefCell<OutlineTree>>,
-    ) -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(OutlineTree {
-            item: Some(item),
-            parent: Rc::downgrade(parent_rc),
-            children: Vec::new(),
-        }))
+    /// Returns the root node.
+    pub fn root(&self) -> NodeRef<'_> {
+        self.node(NodeId(0))
     }
 
-    /// Checks if this node is the root of the tree.
-    pub fn is_root(&self) -> bool {
-        self.parent.upgrade().is_none() && self.item.is_none()
+    /// Returns the node identified by `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not belong to this tree.
+    pub fn node(&self, id: NodeId) -> NodeRef<'_> {
+        assert!(id.0 < self.nodes.len(), "invalid node id {:?}", id);
+        NodeRef { tree: self, id }
     }
 
     /// Adds a new child node to the given parent node.
     ///
     /// # Arguments
     ///
-    /// * `parent_rc` - The `Rc<RefCell<OutlineTree>>` of the parent node to which the child will be added.
+    /// * `parent` - The node to which the child will be added.
     /// * `item` - The `OutlineItem` for the new child node.
     ///
     /// # Returns
     ///
-    /// An `Rc<RefCell<OutlineTree>>` representing the newly added child node.
-    pub fn add_child(
-        parent_rc: &Rc<RefCell<OutlineTree>>,
-        item: OutlineItem,
-    ) -> Rc<RefCell<OutlineTree>> {
-        let child = OutlineTree::new_with_parent(item, parent_rc);
-        parent_rc.borrow_mut().children.push(Rc::clone(&child));
-        child
+    /// The `NodeId` of the newly added child node.
+    pub fn add_child(&mut self, parent: NodeId, item: OutlineItem) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        let sibling_index = self.nodes[parent.0].children.len();
+        self.nodes.push(OutlineNode {
+            item: Some(item),
+            parent: Some(parent),
+            children: Vec::new(),
+            sibling_index,
+        });
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+}
+
+/// A reference to a node of an `OutlineTree`.
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a> {
+    tree: &'a OutlineTree,
+    id: NodeId,
+}
+
+impl<'a> NodeRef<'a> {
+    fn node(&self) -> &'a OutlineNode {
+        &self.tree.nodes[self.id.0]
+    }
+
+    fn with_id(&self, id: NodeId) -> NodeRef<'a> {
+        NodeRef {
+            tree: self.tree,
+            id,
+        }
+    }
+
+    /// Returns the identifier of this node.
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Checks if this node is the root of the tree.
+    pub fn is_root(&self) -> bool {
+        self.node().parent.is_none()
     }
 
     /// Returns the parent node, if it exists.
-    pub fn parent(&self) -> Option<Rc<RefCell<OutlineTree>>> {
-        self.parent.upgrade()
+    pub fn parent(&self) -> Option<NodeRef<'a>> {
+        self.node().parent.map(|id| self.with_id(id))
     }
 
     /// Returns a reference to the `OutlineItem` of this node.
-    pub fn item(&self) -> Option<&OutlineItem> {
-        self.item.as_ref()
+    pub fn item(&self) -> Option<&'a OutlineItem> {
+        self.node().item.as_ref()
     }
 
-    /// Returns a reference to the children vector of this node.
-    pub fn children(&self) -> &Vec<Rc<RefCell<OutlineTree>>> {
-        &self.children
+    /// Returns an iterator over the children of this node.
+    pub fn children(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = NodeRef<'a>> + ExactSizeIterator + 'a {
+        let tree = self.tree;
+        self.node()
+            .children
+            .iter()
+            .map(move |&id| NodeRef { tree, id })
     }
 
     /// Checks if this node is a leaf node (i.e., has no children).
     pub fn is_leaf(&self) -> bool {
-        !self.is_root() && self.children.is_empty()
+        !self.is_root() && self.node().children.is_empty()
     }
 
     /// Returns an iterator over all descendant nodes in pre-order (children left-to-right).
-    pub fn descendants(rc: &Rc<RefCell<OutlineTree>>) -> Descendants {
-        let mut stack = Vec::new();
-        for child in rc.borrow().children().iter().rev() {
-            stack.push(child.clone());
+    pub fn descendants(&self) -> Descendants<'a> {
+        Descendants {
+            tree: self.tree,
+            stack: self.node().children.iter().rev().copied().collect(),
         }
-        Descendants { stack }
     }
 
     /// Returns an iterator over ancestors from parent to root (excluding self).
-    pub fn ancestors(rc: &Rc<RefCell<OutlineTree>>) -> Ancestors {
+    pub fn ancestors(&self) -> Ancestors<'a> {
         Ancestors {
-            current: rc.borrow().parent(), // Start from the parent
+            current: self.parent(), // Start from the parent
         }
     }
 
     /// Returns previous sibling if exists.
-    pub fn prev(rc: &Rc<RefCell<OutlineTree>>) -> Option<Rc<RefCell<OutlineTree>>> {
-        let parent = rc.borrow().parent()?;
-        let siblings = parent.borrow();
-        let ptr = Rc::as_ptr(rc);
-        let idx = siblings
-            .children()
-            .iter()
-            .position(|s| Rc::as_ptr(s) == ptr)?;
-        if idx == 0 {
-            None
-        } else {
-            Some(Rc::clone(&siblings.children()[idx - 1]))
-        }
+    pub fn prev(&self) -> Option<NodeRef<'a>> {
+        let siblings = &self.parent()?.node().children;
+        let index = self.node().sibling_index.checked_sub(1)?;
+        Some(self.with_id(siblings[index]))
     }
 
     /// Returns next sibling if exists.
-    pub fn next(rc: &Rc<RefCell<OutlineTree>>) -> Option<Rc<RefCell<OutlineTree>>> {
-        let parent = rc.borrow().parent()?;
-        let siblings = parent.borrow();
-        let ptr = Rc::as_ptr(rc);
-        let idx = siblings
-            .children()
-            .iter()
-            .position(|s| Rc::as_ptr(s) == ptr)?;
-        if idx + 1 >= siblings.children().len() {
-            None
-        } else {
-            Some(Rc::clone(&siblings.children()[idx + 1]))
-        }
+    pub fn next(&self) -> Option<NodeRef<'a>> {
+        let siblings = &self.parent()?.node().children;
+        siblings
+            .get(self.node().sibling_index + 1)
+            .map(|&id| self.with_id(id))
+    }
+}
+
+impl PartialEq for NodeRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.tree, other.tree) && self.id == other.id
+    }
+}
+
+impl Eq for NodeRef<'_> {}
+
+impl std::fmt::Debug for NodeRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeRef")
+            .field("id", &self.id)
+            .field("item", &self.item())
+            .finish()
     }
 }
 
 /// An iterator over the ancestors of an `OutlineTree` node.
-pub struct Ancestors {
-    current: Option<Rc<RefCell<OutlineTree>>>,
+pub struct Ancestors<'a> {
+    current: Option<NodeRef<'a>>,
 }
 
-impl Iterator for Ancestors {
-    type Item = Rc<RefCell<OutlineTree>>;
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = NodeRef<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let next_node = self.current.take()?; // Take the current node (which is the next ancestor)
-        self.current = next_node.borrow().parent(); // Set the next ancestor to its parent
+        self.current = next_node.parent(); // Set the next ancestor to its parent
         Some(next_node)
     }
 }
 
 /// An iterator over the descendants of an `OutlineTree` node.
-pub struct Descendants {
-    stack: Vec<Rc<RefCell<OutlineTree>>>,
+pub struct Descendants<'a> {
+    tree: &'a OutlineTree,
+    stack: Vec<NodeId>,
 }
 
-impl Iterator for Descendants {
-    type Item = Rc<RefCell<OutlineTree>>;
+impl<'a> Iterator for Descendants<'a> {
+    type Item = NodeRef<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let next_node = self.stack.pop()?; // Get the next node from the stack
+        let next_id = self.stack.pop()?; // Get the next node from the stack
 
         // Add children to the stack in reverse order to process left-to-right
-        for child in next_node.borrow().children().iter().rev() {
-            self.stack.push(child.clone());
-        }
+        self.stack
+            .extend(self.tree.nodes[next_id.0].children.iter().rev().copied());
 
-        Some(next_node)
+        Some(self.tree.node(next_id))
     }
 }
 
@@ -457,6 +543,12 @@ mod tests {
         invalid_outline.value_header = vec!["V1".to_string(), "V2".to_string()];
         invalid_outline.add_item("key", 0, vec!["val1".to_string()]); // Invalid item
         assert!(invalid_outline.validate().is_err());
+
+        let mut non_ascii_outline = Outline::new();
+        non_ascii_outline.key_header = vec!["見出し".to_string()];
+        non_ascii_outline.value_header = vec!["Größe".to_string()];
+        non_ascii_outline.add_item("key", 1, vec![]);
+        assert!(non_ascii_outline.validate().is_ok());
     }
 
     #[test]
@@ -509,12 +601,30 @@ mod tests {
         assert_eq!(outline.max_value_length(), 3);
     }
 
+    #[test]
+    fn test_outline_summary() {
+        let mut outline = Outline::new();
+        outline.key_header = vec!["H1".to_string(), "H2".to_string()];
+        outline.add_item("A", 1, vec!["a".to_string()]);
+        outline.add_item("B", 3, vec![]);
+        assert_eq!(
+            outline.summary(),
+            OutlineSummary {
+                max_level: outline.max_level(),
+                max_value_length: outline.max_value_length(),
+                item_count: 2,
+            }
+        );
+        assert_eq!(outline.header().key_header, outline.key_header);
+        assert!(outline.header().item.is_empty());
+    }
+
     #[test]
     fn test_outline_to_tree_empty() {
         let outline = Outline::new();
         let tree = outline.to_tree();
-        assert!(tree.borrow().is_root());
-        assert!(tree.borrow().children().is_empty());
+        assert!(tree.root().is_root());
+        assert_eq!(tree.root().children().len(), 0);
     }
 
     #[test]
@@ -523,16 +633,15 @@ mod tests {
         outline.add_item("Item 1", 1, vec![]);
         let tree = outline.to_tree();
 
-        let tree_borrow = tree.borrow();
-        assert!(tree_borrow.is_root());
-        assert_eq!(tree_borrow.children().len(), 1);
+        let root = tree.root();
+        assert!(root.is_root());
+        assert_eq!(root.children().len(), 1);
 
-        let child1_rc = &tree_borrow.children()[0];
-        let child1 = child1_rc.borrow();
+        let child1 = root.children().next().unwrap();
         assert_eq!(child1.item().unwrap().key, "Item 1");
         assert_eq!(child1.item().unwrap().level, 1);
         assert!(child1.is_leaf());
-        assert_eq!(child1.parent().unwrap().borrow().item(), None);
+        assert_eq!(child1.parent().unwrap().item(), None);
     }
 
     #[test]
@@ -547,58 +656,44 @@ mod tests {
         let tree = outline.to_tree();
 
         // Root
-        let tree_borrow = tree.borrow();
-        assert!(tree_borrow.is_root());
-        assert_eq!(tree_borrow.children().len(), 2);
+        let root = tree.root();
+        assert!(root.is_root());
+        assert_eq!(root.children().len(), 2);
 
         // Item 1
-        let item1_rc = Rc::clone(&tree_borrow.children()[0]);
-        let item1 = item1_rc.borrow();
+        let item1 = root.children().next().unwrap();
         assert_eq!(item1.item().unwrap().key, "Item 1");
         assert_eq!(item1.item().unwrap().level, 1);
         assert_eq!(item1.children().len(), 2);
-        assert_eq!(item1.parent().unwrap().borrow().item(), None);
+        assert_eq!(item1.parent().unwrap().item(), None);
 
         // Item 1.1
-        let item1_1_rc = Rc::clone(&item1.children()[0]);
-        let item1_1 = item1_1_rc.borrow();
+        let item1_1 = item1.children().next().unwrap();
         assert_eq!(item1_1.item().unwrap().key, "Item 1.1");
         assert_eq!(item1_1.item().unwrap().level, 2);
         assert!(item1_1.is_leaf());
-        assert_eq!(
-            item1_1.parent().unwrap().borrow().item().unwrap().key,
-            "Item 1"
-        );
+        assert_eq!(item1_1.parent().unwrap().item().unwrap().key, "Item 1");
 
         // Item 1.2
-        let item1_2_rc = Rc::clone(&item1.children()[1]);
-        let item1_2 = item1_2_rc.borrow();
+        let item1_2 = item1.children().nth(1).unwrap();
         assert_eq!(item1_2.item().unwrap().key, "Item 1.2");
         assert_eq!(item1_2.item().unwrap().level, 2);
         assert_eq!(item1_2.children().len(), 1);
-        assert_eq!(
-            item1_2.parent().unwrap().borrow().item().unwrap().key,
-            "Item 1"
-        );
+        assert_eq!(item1_2.parent().unwrap().item().unwrap().key, "Item 1");
 
         // Item 1.2.1
-        let item1_2_1_rc = Rc::clone(&item1_2.children()[0]);
-        let item1_2_1 = item1_2_1_rc.borrow();
+        let item1_2_1 = item1_2.children().next().unwrap();
         assert_eq!(item1_2_1.item().unwrap().key, "Item 1.2.1");
         assert_eq!(item1_2_1.item().unwrap().level, 3);
         assert!(item1_2_1.is_leaf());
-        assert_eq!(
-            item1_2_1.parent().unwrap().borrow().item().unwrap().key,
-            "Item 1.2"
-        );
+        assert_eq!(item1_2_1.parent().unwrap().item().unwrap().key, "Item 1.2");
 
         // Item 2
-        let item2_rc = Rc::clone(&tree_borrow.children()[1]);
-        let item2 = item2_rc.borrow();
+        let item2 = root.children().nth(1).unwrap();
         assert_eq!(item2.item().unwrap().key, "Item 2");
         assert_eq!(item2.item().unwrap().level, 1);
         assert!(item2.is_leaf());
-        assert_eq!(item2.parent().unwrap().borrow().item(), None);
+        assert_eq!(item2.parent().unwrap().item(), None);
     }
 
     #[test]
@@ -612,88 +707,113 @@ mod tests {
         outline.add_item("F", 2, vec![]);
 
         let tree = outline.to_tree();
-        let tree_borrow = tree.borrow();
+        let root = tree.root();
 
         // A
-        let a_node = Rc::clone(&tree_borrow.children()[0]);
-        let a_node_borrow = a_node.borrow();
-        assert_eq!(a_node_borrow.item().unwrap().key, "A");
-        assert_eq!(a_node_borrow.children().len(), 2);
+        let a_node = root.children().next().unwrap();
+        assert_eq!(a_node.item().unwrap().key, "A");
+        assert_eq!(a_node.children().len(), 2);
 
         // B
-        let b_node = Rc::clone(&a_node_borrow.children()[0]);
-        let b_node_borrow = b_node.borrow();
-        assert_eq!(b_node_borrow.item().unwrap().key, "B");
-        assert_eq!(b_node_borrow.children().len(), 1);
+        let b_node = a_node.children().next().unwrap();
+        assert_eq!(b_node.item().unwrap().key, "B");
+        assert_eq!(b_node.children().len(), 1);
 
         // C
-        let c_node = Rc::clone(&b_node_borrow.children()[0]);
-        let c_node_borrow = c_node.borrow();
-        assert_eq!(c_node_borrow.item().unwrap().key, "C");
-        assert!(c_node_borrow.is_leaf());
+        let c_node = b_node.children().next().unwrap();
+        assert_eq!(c_node.item().unwrap().key, "C");
+        assert!(c_node.is_leaf());
 
         // D
-        let d_node = Rc::clone(&a_node_borrow.children()[1]);
-        let d_node_borrow = d_node.borrow();
-        assert_eq!(d_node_borrow.item().unwrap().key, "D");
-        assert!(d_node_borrow.is_leaf());
+        let d_node = a_node.children().nth(1).unwrap();
+        assert_eq!(d_node.item().unwrap().key, "D");
+        assert!(d_node.is_leaf());
 
         // E
-        let e_node = Rc::clone(&tree_borrow.children()[1]);
-        let e_node_borrow = e_node.borrow();
-        assert_eq!(e_node_borrow.item().unwrap().key, "E");
-        assert_eq!(e_node_borrow.children().len(), 1);
+        let e_node = root.children().nth(1).unwrap();
+        assert_eq!(e_node.item().unwrap().key, "E");
+        assert_eq!(e_node.children().len(), 1);
 
         // F
-        let f_node = Rc::clone(&e_node_borrow.children()[0]);
-        let f_node_borrow = f_node.borrow();
-        assert_eq!(f_node_borrow.item().unwrap().key, "F");
-        assert!(f_node_borrow.is_leaf());
+        let f_node = e_node.children().next().unwrap();
+        assert_eq!(f_node.item().unwrap().key, "F");
+        assert!(f_node.is_leaf());
+    }
+
+    #[test]
+    fn test_outline_to_tree_level_jump() {
+        // A level jump makes the item a child of the nearest preceding lower-level item
+        let mut outline = Outline::new();
+        outline.add_item("A", 1, vec![]);
+        outline.add_item("B", 3, vec![]);
+        outline.add_item("C", 2, vec![]);
+
+        let tree = outline.to_tree();
+        let a_node = tree.root().children().next().unwrap();
+        let keys: Vec<_> = a_node
+            .children()
+            .map(|child| child.item().unwrap().key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["B", "C"]);
     }
 
     #[test]
     fn test_outline_tree_is_root() {
-        let root = OutlineTree::new_root();
-        assert!(root.borrow().is_root());
+        let mut tree = OutlineTree::new();
+        assert!(tree.root().is_root());
 
-        let child = OutlineTree::new_with_parent(OutlineItem::new("child", 1, vec![]), &root);
-        assert!(!child.borrow().is_root());
+        let child = tree.add_child(tree.root().id(), OutlineItem::new("child", 1, vec![]));
+        assert!(!tree.node(child).is_root());
     }
 
     #[test]
     fn test_outline_tree_parent() {
-        let root = OutlineTree::new_root();
-        let child = OutlineTree::new_with_parent(OutlineItem::new("child", 1, vec![]), &root);
+        let mut tree = OutlineTree::new();
+        let child = tree.add_child(tree.root().id(), OutlineItem::new("child", 1, vec![]));
 
-        assert!(root.borrow().parent().is_none());
-        assert_eq!(child.borrow().parent().unwrap().borrow().item(), None);
+        assert!(tree.root().parent().is_none());
+        assert_eq!(tree.node(child).parent().unwrap(), tree.root());
     }
 
     #[test]
     fn test_outline_tree_add_child() {
-        let root = OutlineTree::new_root();
-        let child = OutlineTree::add_child(&root, OutlineItem::new("child", 1, vec![]));
+        let mut tree = OutlineTree::new();
+        let child = tree.add_child(tree.root().id(), OutlineItem::new("child", 1, vec![]));
 
-        assert_eq!(root.borrow().children().len(), 1);
+        assert_eq!(tree.root().children().len(), 1);
         assert_eq!(
-            root.borrow().children()[0].borrow().item().unwrap().key,
+            tree.root().children().next().unwrap().item().unwrap().key,
             "child"
         );
-        assert_eq!(child.borrow().parent().unwrap().borrow().item(), None);
-        assert!(child.borrow().is_leaf());
+        assert_eq!(tree.node(child).parent().unwrap().item(), None);
+        assert!(tree.node(child).is_leaf());
     }
 
     #[test]
     fn test_outline_tree_is_leaf() {
-        let root = OutlineTree::new_root();
-        assert!(!root.borrow().is_leaf()); // Root is not a leaf initially
+        let mut tree = OutlineTree::new();
+        assert!(!tree.root().is_leaf()); // Root is not a leaf initially
+
+        let child = tree.add_child(tree.root().id(), OutlineItem::new("child", 1, vec![]));
+        assert!(tree.node(child).is_leaf()); // Child is a leaf
 
-        let child = OutlineTree::add_child(&root, OutlineItem::new("child", 1, vec![]));
-        assert!(child.borrow().is_leaf()); // Child is a leaf
+        let grand_child = tree.add_child(child, OutlineItem::new("grandchild", 2, vec![]));
+        assert!(!tree.node(child).is_leaf()); // Child is no longer a leaf
+        assert!(tree.node(grand_child).is_leaf()); // Grandchild is a leaf
+    }
+
+    #[test]
+    fn test_outline_tree_send_sync_clone() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<OutlineTree>();
 
-        let grand_child = OutlineTree::add_child(&child, OutlineItem::new("grandchild", 2, vec![]));
-        assert!(!child.borrow().is_leaf()); // Child is no longer a leaf
-        assert!(grand_child.borrow().is_leaf()); // Grandchild is a leaf
+        let mut outline = Outline::new();
+        outline.add_item("A", 1, vec![]);
+        let tree = outline.to_tree();
+        let cloned = tree.clone();
+        let handle = std::thread::spawn(move || cloned.root().descendants().count());
+        assert_eq!(handle.join().unwrap(), 1);
+        assert_eq!(tree.root().descendants().count(), 1);
     }
 
     #[test]
@@ -708,49 +828,41 @@ mod tests {
         outline.add_item("F", 2, vec![]);
 
         let tree = outline.to_tree();
-        let a = tree.borrow().children()[0].clone();
-        let e = tree.borrow().children()[1].clone();
+        let a = tree.root().children().next().unwrap();
+        let e = tree.root().children().nth(1).unwrap();
 
-        let b = a.borrow().children()[0].clone();
-        let d = a.borrow().children()[1].clone();
-        let c = b.borrow().children()[0].clone();
+        let b = a.children().next().unwrap();
+        let d = a.children().nth(1).unwrap();
+        let c = b.children().next().unwrap();
 
         // descendants of A: [B, C, D]
-        let desc_a: Vec<_> = OutlineTree::descendants(&a).collect();
+        let desc_a: Vec<_> = a.descendants().collect();
         assert_eq!(desc_a.len(), 3);
-        assert_eq!(desc_a[0].borrow().item().unwrap().key, "B");
-        assert_eq!(desc_a[1].borrow().item().unwrap().key, "C");
-        assert_eq!(desc_a[2].borrow().item().unwrap().key, "D");
+        assert_eq!(desc_a[0].item().unwrap().key, "B");
+        assert_eq!(desc_a[1].item().unwrap().key, "C");
+        assert_eq!(desc_a[2].item().unwrap().key, "D");
 
         // ancestors of C: [B, A, root]
-        let anc_c: Vec<_> = OutlineTree::ancestors(&c).collect();
+        let anc_c: Vec<_> = c.ancestors().collect();
         assert_eq!(anc_c.len(), 3);
-        assert_eq!(anc_c[0].borrow().item().unwrap().key, "B");
-        assert_eq!(anc_c[1].borrow().item().unwrap().key, "A");
-        assert!(anc_c[2].borrow().is_root());
+        assert_eq!(anc_c[0].item().unwrap().key, "B");
+        assert_eq!(anc_c[1].item().unwrap().key, "A");
+        assert!(anc_c[2].is_root());
 
         // prev/next among siblings under A
-        assert!(OutlineTree::prev(&b).is_none());
-        assert_eq!(
-            OutlineTree::next(&b).unwrap().borrow().item().unwrap().key,
-            "D"
-        );
-        assert_eq!(
-            OutlineTree::prev(&d).unwrap().borrow().item().unwrap().key,
-            "B"
-        );
-        assert!(OutlineTree::next(&d).is_none());
+        assert!(b.prev().is_none());
+        assert_eq!(b.next().unwrap().item().unwrap().key, "D");
+        assert_eq!(d.prev().unwrap().item().unwrap().key, "B");
+        assert!(d.next().is_none());
 
         // prev/next across top-level siblings: A <-> E
-        assert!(OutlineTree::prev(&a).is_none());
-        assert_eq!(
-            OutlineTree::next(&a).unwrap().borrow().item().unwrap().key,
-            "E"
-        );
-        assert_eq!(
-            OutlineTree::prev(&e).unwrap().borrow().item().unwrap().key,
-            "A"
-        );
-        assert!(OutlineTree::next(&e).is_none());
+        assert!(a.prev().is_none());
+        assert_eq!(a.next().unwrap().item().unwrap().key, "E");
+        assert_eq!(e.prev().unwrap().item().unwrap().key, "A");
+        assert!(e.next().is_none());
+
+        // The root has no siblings
+        assert!(tree.root().prev().is_none());
+        assert!(tree.root().next().is_none());
     }
 }
@@ -0,0 +1,263 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::ParseError;
+use crate::parser::{parse_input_with, Parser};
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct CsvAdjacencyParserOptions {
+    /// The header of the column identifying each row.
+    pub id_column: String,
+    /// The header of the column holding the id of the parent row, empty for top-level rows.
+    pub parent_column: String,
+    /// The header of the column holding the key of each item.
+    pub key_column: String,
+    /// The field delimiter.
+    pub delimiter: u8,
+    pub key_header: Vec<String>,
+    /// Defaults to the headers of the columns other than the id, parent and key columns.
+    pub value_header: Vec<String>,
+}
+
+impl Default for CsvAdjacencyParserOptions {
+    fn default() -> Self {
+        CsvAdjacencyParserOptions {
+            id_column: "id".to_string(),
+            parent_column: "parent_id".to_string(),
+            key_column: "name".to_string(),
+            delimiter: b',',
+            key_header: Vec::new(),
+            value_header: Vec::new(),
+        }
+    }
+}
+
+/// A parser for CSV adjacency lists, where each row refers to its parent row by id.
+///
+/// The first row is a header row. Items are output depth-first, siblings in the order of
+/// their rows. The columns other than the id, parent and key columns are read as values.
+/// Parents missing from the input, duplicate ids and cycles are reported as a `ParseError`.
+pub struct CsvAdjacencyParser {
+    options: CsvAdjacencyParserOptions,
+}
+
+/// A data row of the input.
+struct Row {
+    id: String,
+    parent: String,
+    key: String,
+    value: Vec<String>,
+    /// The byte offset of the row in the input.
+    offset: usize,
+    /// The 1-based line number of the row.
+    line: u64,
+}
+
+impl CsvAdjacencyParser {
+    pub fn new(options: CsvAdjacencyParserOptions) -> Self {
+        CsvAdjacencyParser { options }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Outline> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.options.delimiter)
+            .flexible(true)
+            .from_reader(input.as_bytes());
+        let csv_error = |e: csv::Error| -> anyhow::Error {
+            let offset = e.position().map_or(input.len(), |p| p.byte() as usize);
+            ParseError::at_offset(format!("invalid CSV: {}", e), input, offset).into()
+        };
+
+        let headers = reader.headers().map_err(csv_error)?.clone();
+        let column = |name: &str| -> Result<usize> {
+            match headers.iter().position(|h| h.trim() == name) {
+                Some(index) => Ok(index),
+                None => {
+                    Err(ParseError::at_line(format!("no column '{}'", name), input, 1, 1).into())
+                }
+            }
+        };
+        let id_index = column(&self.options.id_column)?;
+        let parent_index = column(&self.options.parent_column)?;
+        let key_index = column(&self.options.key_column)?;
+        let value_indices: Vec<usize> = (0..headers.len())
+            .filter(|i| ![id_index, parent_index, key_index].contains(i))
+            .collect();
+
+        let mut outline = Outline::new();
+        outline.key_header = self.options.key_header.clone();
+        outline.value_header = self.options.value_header.clone();
+        if outline.value_header.is_empty() {
+            outline.value_header = value_indices
+                .iter()
+                .map(|&i| headers[i].trim().to_string())
+                .collect();
+        }
+
+        let mut rows: Vec<Row> = Vec::new();
+        let mut row_by_id: HashMap<String, usize> = HashMap::new();
+        for record in reader.records() {
+            let record = record.map_err(csv_error)?;
+            let (offset, line) = record
+                .position()
+                .map_or((0, 0), |p| (p.byte() as usize, p.line()));
+            let field = |i: usize| record.get(i).unwrap_or("").trim().to_string();
+            let row = Row {
+                id: field(id_index),
+                parent: field(parent_index),
+                key: field(key_index),
+                value: value_indices.iter().map(|&i| field(i)).collect(),
+                offset,
+                line,
+            };
+            if row.id.is_empty() {
+                bail!(ParseError::at_offset("empty id", input, offset));
+            }
+            if let Some(&first) = row_by_id.get(&row.id) {
+                bail!(ParseError::at_offset(
+                    format!(
+                        "duplicate id '{}' (first defined on line {})",
+                        row.id, rows[first].line
+                    ),
+                    input,
+                    offset
+                ));
+            }
+            row_by_id.insert(row.id.clone(), rows.len());
+            rows.push(row);
+        }
+
+        // The children of each row, and the top-level rows under `None`
+        let mut children: HashMap<Option<usize>, Vec<usize>> = HashMap::new();
+        for (index, row) in rows.iter().enumerate() {
+            let parent = if row.parent.is_empty() {
+                None
+            } else {
+                match row_by_id.get(&row.parent) {
+                    Some(&parent) => Some(parent),
+                    None => bail!(ParseError::at_offset(
+                        format!("parent id '{}' of '{}' not found", row.parent, row.id),
+                        input,
+                        row.offset
+                    )),
+                }
+            };
+            children.entry(parent).or_default().push(index);
+        }
+
+        let mut visited = vec![false; rows.len()];
+        let mut stack: Vec<(usize, u32)> = Vec::new();
+        let push_children = |stack: &mut Vec<(usize, u32)>, parent, level| {
+            if let Some(indices) = children.get(&parent) {
+                stack.extend(indices.iter().rev().map(|&index| (index, level)));
+            }
+        };
+        push_children(&mut stack, None, 1);
+        while let Some((index, level)) = stack.pop() {
+            visited[index] = true;
+            let row = &rows[index];
+            outline.item.push(OutlineItem {
+                key: row.key.clone(),
+                level,
+                value: row.value.iter().map(|v| v.as_str().into()).collect(),
+            });
+            push_children(&mut stack, Some(index), level + 1);
+        }
+
+        // Rows not reached from a top-level row have an ancestor in a cycle
+        if let Some(start) = visited.iter().position(|v| !v) {
+            let mut path = vec![start];
+            let mut index = start;
+            let cycle_start = loop {
+                index = row_by_id[&rows[index].parent];
+                if let Some(position) = path.iter().position(|&i| i == index) {
+                    break position;
+                }
+                path.push(index);
+            };
+            let mut ids: Vec<&str> = path[cycle_start..]
+                .iter()
+                .map(|&i| rows[i].id.as_str())
+                .collect();
+            ids.push(ids[0]);
+            bail!(ParseError::at_offset(
+                format!("cycle in parent ids: {}", ids.join(" -> ")),
+                input,
+                rows[path[cycle_start]].offset
+            ));
+        }
+        Ok(outline)
+    }
+}
+
+impl Parser for CsvAdjacencyParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Outline> {
+        CsvAdjacencyParser::new(CsvAdjacencyParserOptions::default()).parse(input)
+    }
+
+    fn parse_error(input: &str) -> ParseError {
+        parse(input).unwrap_err().downcast().unwrap()
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let input = "\
+name,id,title,parent_id
+Carol,3,VP Sales,1
+Alice,1,President,
+Dave,4,Rep,3
+Bob,2,\"VP, R&D\",1
+Erin,5,Advisor,
+";
+        let outline = parse(input)?;
+        let mut expected = Outline::new();
+        expected.value_header = vec!["title".to_string()];
+        expected.add_item("Alice", 1, vec!["President".to_string()]);
+        expected.add_item("Carol", 2, vec!["VP Sales".to_string()]);
+        expected.add_item("Dave", 3, vec!["Rep".to_string()]);
+        expected.add_item("Bob", 2, vec!["VP, R&D".to_string()]);
+        expected.add_item("Erin", 1, vec!["Advisor".to_string()]);
+        assert_eq!(outline, expected);
+
+        let parser = CsvAdjacencyParser::new(CsvAdjacencyParserOptions {
+            id_column: "ci".to_string(),
+            parent_column: "parent".to_string(),
+            key_column: "host".to_string(),
+            delimiter: b'\t',
+            ..Default::default()
+        });
+        let outline = parser.parse("ci\tparent\thost\nb\ta\tweb\na\t\tdc\n")?;
+        assert!(outline.value_header.is_empty());
+        assert_eq!(outline.item[0].key, "dc");
+        assert_eq!(outline.item[1].level, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_error("id,parent_id,name\n1,,A\n2,9,B\n");
+        assert_eq!(err.message, "parent id '9' of '2' not found");
+        assert_eq!(err.location.line, 3);
+
+        let err = parse_error("id,parent_id,name\n1,,A\n2,1,B\n1,,C\n");
+        assert_eq!(err.message, "duplicate id '1' (first defined on line 2)");
+        assert_eq!(err.location.line, 4);
+
+        let err = parse_error("id,parent_id,name\n1,,A\n2,4,B\n3,2,C\n4,3,D\n5,2,E\n");
+        assert_eq!(err.message, "cycle in parent ids: 2 -> 4 -> 3 -> 2");
+        assert_eq!(err.location.line, 3);
+
+        let err = parse_error("id,parent,name\n");
+        assert_eq!(err.message, "no column 'parent_id'");
+    }
+}
@@ -0,0 +1,344 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::ParseError;
+use crate::parser::{parse_input_with, Parser};
+use anyhow::{bail, Result};
+use csv::StringRecord;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Where the hierarchy of a CSV table comes from.
+///
+/// Columns are given by header, or by 1-based index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvHierarchy {
+    /// A column holding the level of each item, as written by `xlsx_type0`.
+    Level { column: String },
+    /// A column holding the keys of each item and its ancestors joined by `separator`,
+    /// e.g. `a/b/c`.
+    Path { column: String, separator: String },
+}
+
+impl Default for CsvHierarchy {
+    fn default() -> Self {
+        CsvHierarchy::Level {
+            column: "2".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvTableParserOptions {
+    pub hierarchy: CsvHierarchy,
+    /// The column holding the key of each item with `CsvHierarchy::Level`. Defaults to the
+    /// first column.
+    pub key_column: Option<String>,
+    /// If true, the first row holds the headers of the columns.
+    pub has_header: bool,
+    /// The field delimiter.
+    pub delimiter: u8,
+    /// Defaults to the header of the key or path column.
+    pub key_header: Vec<String>,
+    /// Defaults to the headers of the other columns.
+    pub value_header: Vec<String>,
+}
+
+impl Default for CsvTableParserOptions {
+    fn default() -> Self {
+        CsvTableParserOptions {
+            hierarchy: CsvHierarchy::default(),
+            key_column: None,
+            has_header: true,
+            delimiter: b',',
+            key_header: Vec::new(),
+            value_header: Vec::new(),
+        }
+    }
+}
+
+/// A parser for delimited text with one item per row, the inverse of `xlsx_type0`.
+///
+/// The columns other than the key, level and path columns are read as values.
+pub struct CsvTableParser {
+    options: CsvTableParserOptions,
+}
+
+/// An item of a table with `CsvHierarchy::Path`.
+struct PathNode {
+    key: String,
+    /// The values of the row of the item, or `None` for an ancestor without a row.
+    value: Option<Vec<String>>,
+    children: Vec<usize>,
+}
+
+impl CsvTableParser {
+    pub fn new(options: CsvTableParserOptions) -> Self {
+        CsvTableParser { options }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Outline> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(self.options.delimiter)
+            .has_headers(self.options.has_header)
+            .flexible(true)
+            .from_reader(input.as_bytes());
+        let csv_error = |e: csv::Error| -> anyhow::Error {
+            let offset = e.position().map_or(input.len(), |p| p.byte() as usize);
+            ParseError::at_offset(format!("invalid CSV: {}", e), input, offset).into()
+        };
+        let headers = if self.options.has_header {
+            Some(reader.headers().map_err(csv_error)?.clone())
+        } else {
+            None
+        };
+        let column = |spec: &str| -> Result<usize> {
+            let by_header = headers
+                .as_ref()
+                .and_then(|headers| headers.iter().position(|h| h.trim() == spec));
+            match by_header.or_else(|| spec.parse::<usize>().ok().filter(|&i| i > 0).map(|i| i - 1))
+            {
+                Some(index) => Ok(index),
+                None => {
+                    Err(ParseError::at_line(format!("no column '{}'", spec), input, 1, 1).into())
+                }
+            }
+        };
+        let hierarchy_index = match &self.options.hierarchy {
+            CsvHierarchy::Level { column: spec } | CsvHierarchy::Path { column: spec, .. } => {
+                column(spec)?
+            }
+        };
+        let key_index = match (&self.options.hierarchy, &self.options.key_column) {
+            (CsvHierarchy::Path { .. }, _) => hierarchy_index,
+            (CsvHierarchy::Level { .. }, Some(spec)) => column(spec)?,
+            (CsvHierarchy::Level { .. }, None) => 0,
+        };
+
+        let records = reader
+            .records()
+            .collect::<Result<Vec<StringRecord>, _>>()
+            .map_err(csv_error)?;
+        let width = headers
+            .iter()
+            .chain(&records)
+            .map(StringRecord::len)
+            .max()
+            .unwrap_or(0);
+        let value_indices: Vec<usize> = (0..width)
+            .filter(|&i| i != key_index && i != hierarchy_index)
+            .collect();
+
+        let mut outline = Outline::new();
+        outline.key_header = self.options.key_header.clone();
+        outline.value_header = self.options.value_header.clone();
+        if let Some(headers) = &headers {
+            let header = |i: usize| headers.get(i).unwrap_or("").trim().to_string();
+            if outline.key_header.is_empty() {
+                outline.key_header = vec![header(key_index)];
+            }
+            if outline.value_header.is_empty() {
+                outline.value_header = value_indices.iter().map(|&i| header(i)).collect();
+            }
+        }
+
+        let offset = |record: &StringRecord| record.position().map_or(0, |p| p.byte() as usize);
+        let field =
+            |record: &StringRecord, i: usize| record.get(i).unwrap_or("").trim().to_string();
+        let values = |record: &StringRecord| -> Vec<String> {
+            value_indices.iter().map(|&i| field(record, i)).collect()
+        };
+        match &self.options.hierarchy {
+            CsvHierarchy::Level { .. } => {
+                for record in &records {
+                    let level = field(record, hierarchy_index);
+                    let Some(level) = level.parse::<u32>().ok().filter(|&l| l > 0) else {
+                        bail!(ParseError::at_offset(
+                            format!("invalid level '{}'", level),
+                            input,
+                            offset(record)
+                        ));
+                    };
+                    outline.item.push(OutlineItem {
+                        key: field(record, key_index),
+                        level,
+                        value: values(record).into_iter().map(Into::into).collect(),
+                    });
+                }
+            }
+            CsvHierarchy::Path { separator, .. } => {
+                let mut nodes: Vec<PathNode> = Vec::new();
+                let mut roots: Vec<usize> = Vec::new();
+                let mut node_by_key: HashMap<(Option<usize>, String), usize> = HashMap::new();
+                for record in &records {
+                    let path = field(record, hierarchy_index);
+                    let keys: Vec<&str> = path
+                        .split(separator.as_str())
+                        .map(str::trim)
+                        .filter(|key| !key.is_empty())
+                        .collect();
+                    if keys.is_empty() {
+                        bail!(ParseError::at_offset("empty path", input, offset(record)));
+                    }
+                    // Ancestors without a row of their own are added with empty values
+                    let mut parent = None;
+                    for key in keys {
+                        let index =
+                            *node_by_key
+                                .entry((parent, key.to_string()))
+                                .or_insert_with(|| {
+                                    nodes.push(PathNode {
+                                        key: key.to_string(),
+                                        value: None,
+                                        children: Vec::new(),
+                                    });
+                                    nodes.len() - 1
+                                });
+                        match parent {
+                            Some(parent) if !nodes[parent].children.contains(&index) => {
+                                nodes[parent].children.push(index)
+                            }
+                            None if !roots.contains(&index) => roots.push(index),
+                            _ => {}
+                        }
+                        parent = Some(index);
+                    }
+                    let node = &mut nodes[parent.expect("paths are not empty")];
+                    if node.value.is_some() {
+                        bail!(ParseError::at_offset(
+                            format!("duplicate path '{}'", path),
+                            input,
+                            offset(record)
+                        ));
+                    }
+                    node.value = Some(values(record));
+                }
+
+                let mut stack: Vec<(usize, u32)> = roots.iter().rev().map(|&i| (i, 1)).collect();
+                while let Some((index, level)) = stack.pop() {
+                    let node = &nodes[index];
+                    outline.item.push(OutlineItem {
+                        key: node.key.clone(),
+                        level,
+                        value: node
+                            .value
+                            .iter()
+                            .flatten()
+                            .map(|v| v.as_str().into())
+                            .collect(),
+                    });
+                    stack.extend(node.children.iter().rev().map(|&i| (i, level + 1)));
+                }
+            }
+        }
+        Ok(outline)
+    }
+}
+
+impl Parser for CsvTableParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level_column() -> Result<()> {
+        let input = "\
+H1,Outline Level,H(1),H(2)
+1,1,1(1),1(2)
+1.1,2,1.1(1),
+1.2,2,\"1.2(1), more\",1.2(2)
+";
+        let outline = CsvTableParser::new(CsvTableParserOptions::default()).parse(input)?;
+        let mut expected = Outline::new();
+        expected.key_header = vec!["H1".to_string()];
+        expected.value_header = vec!["H(1)".to_string(), "H(2)".to_string()];
+        expected.add_item("1", 1, vec!["1(1)".to_string(), "1(2)".to_string()]);
+        expected.add_item("1.1", 2, vec!["1.1(1)".to_string(), "".to_string()]);
+        expected.add_item(
+            "1.2",
+            2,
+            vec!["1.2(1), more".to_string(), "1.2(2)".to_string()],
+        );
+        assert_eq!(outline, expected);
+
+        let parser = CsvTableParser::new(CsvTableParserOptions {
+            hierarchy: CsvHierarchy::Level {
+                column: "1".to_string(),
+            },
+            key_column: Some("3".to_string()),
+            has_header: false,
+            delimiter: b'\t',
+            ..Default::default()
+        });
+        let outline = parser.parse("1\tx\tA\n2\ty\tB\n")?;
+        assert!(outline.key_header.is_empty());
+        assert_eq!(outline.item[1].key, "B");
+        assert_eq!(outline.item[1].level, 2);
+        assert_eq!(outline.item[1].value, vec!["y"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_path_column() -> Result<()> {
+        let input = "\
+Owner,Path
+Ann,Sales
+Bob,/R&D/Lab/
+Dan,Sales/EMEA
+Eve,R&D
+";
+        let parser = CsvTableParser::new(CsvTableParserOptions {
+            hierarchy: CsvHierarchy::Path {
+                column: "Path".to_string(),
+                separator: "/".to_string(),
+            },
+            ..Default::default()
+        });
+        let outline = parser.parse(input)?;
+        let mut expected = Outline::new();
+        expected.key_header = vec!["Path".to_string()];
+        expected.value_header = vec!["Owner".to_string()];
+        expected.add_item("Sales", 1, vec!["Ann".to_string()]);
+        expected.add_item("EMEA", 2, vec!["Dan".to_string()]);
+        expected.add_item("R&D", 1, vec!["Eve".to_string()]);
+        expected.add_item("Lab", 2, vec!["Bob".to_string()]);
+        assert_eq!(outline, expected);
+
+        let err = parser
+            .parse("Owner,Path\nAnn,a/b\nBob,a/b\n")
+            .unwrap_err()
+            .downcast::<ParseError>()?;
+        assert_eq!(err.message, "duplicate path 'a/b'");
+        assert_eq!(err.location.line, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |input| {
+            CsvTableParser::new(CsvTableParserOptions::default())
+                .parse(input)
+                .unwrap_err()
+                .downcast::<ParseError>()
+                .unwrap()
+        };
+        let err = parse("Key,Level\nA,1\nB,x\n");
+        assert_eq!(err.message, "invalid level 'x'");
+        assert_eq!(err.location.line, 3);
+        let err = parse("Key\nA\n");
+        assert_eq!(err.message, "invalid level ''");
+
+        let parser = CsvTableParser::new(CsvTableParserOptions {
+            key_column: Some("Name".to_string()),
+            ..Default::default()
+        });
+        let err = parser.parse("Key,Level\n").unwrap_err();
+        assert_eq!(
+            err.downcast::<ParseError>().unwrap().message,
+            "no column 'Name'"
+        );
+    }
+}
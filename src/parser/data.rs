@@ -0,0 +1,473 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::ParseError;
+use crate::parser::json::json_error;
+use crate::parser::{parse_input_with, Parser};
+use crate::value::Value;
+use anyhow::Result;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fmt;
+use std::path::Path;
+
+/// The value header of the column holding scalar members.
+const SCALAR_HEADER: &str = "Value";
+
+/// The key under which the `toml` crate exposes date-times to deserializers.
+const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+/// The syntax of a structured-data document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataFormat {
+    #[default]
+    Json,
+    Yaml,
+    Toml,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DataParserOptions {
+    pub format: DataFormat,
+    /// In arrays of objects, the member whose value becomes the key of each element instead
+    /// of its index.
+    pub key_field: Option<String>,
+    /// In arrays of objects, the members whose values become value columns of each element
+    /// instead of child items.
+    pub value_fields: Vec<String>,
+    /// The deepest level of items. Objects and arrays at this level are kept as a single
+    /// JSON text value.
+    pub max_depth: Option<u32>,
+    pub key_header: Vec<String>,
+    /// Defaults to `Value` followed by `value_fields`.
+    pub value_header: Vec<String>,
+}
+
+/// A parser for JSON, YAML and TOML documents.
+///
+/// Object members and array elements become items, keyed by member name and by 0-based
+/// index respectively. Scalars are the value of their item, in the first value column;
+/// `null` is an empty value. The document itself must be an object or an array.
+pub struct DataParser {
+    options: DataParserOptions,
+}
+
+/// The kind of a scalar, kept so that collapsed objects and arrays are written back as JSON
+/// with the types of their scalars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarKind {
+    /// A string, or a TOML date-time.
+    String,
+    Number,
+    Bool,
+    Null,
+}
+
+/// A node of a document, with the members of objects in document order.
+#[derive(Debug, Clone, PartialEq)]
+enum DataNode {
+    /// A scalar as text, `null` being empty.
+    Scalar(String, ScalarKind),
+    Array(Vec<DataNode>),
+    Object(Vec<(String, DataNode)>),
+}
+
+impl DataNode {
+    fn as_scalar(&self) -> Option<&str> {
+        match self {
+            DataNode::Scalar(text, _) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns the node as a value: scalars as they are, objects and arrays as compact JSON.
+    fn to_text(&self) -> String {
+        match self {
+            DataNode::Scalar(text, _) => text.clone(),
+            _ => serde_json::to_string(self).unwrap_or_default(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DataNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DataNodeVisitor)
+    }
+}
+
+struct DataNodeVisitor;
+
+impl<'de> Visitor<'de> for DataNodeVisitor {
+    type Value = DataNode;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a scalar, an array or an object")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<DataNode, E> {
+        Ok(DataNode::Scalar(v.to_string(), ScalarKind::Bool))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<DataNode, E> {
+        Ok(DataNode::Scalar(v.to_string(), ScalarKind::Number))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<DataNode, E> {
+        Ok(DataNode::Scalar(v.to_string(), ScalarKind::Number))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<DataNode, E> {
+        // JSON has no infinity nor NaN, e.g. `.inf` of YAML
+        let kind = if v.is_finite() {
+            ScalarKind::Number
+        } else {
+            ScalarKind::String
+        };
+        Ok(DataNode::Scalar(v.to_string(), kind))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<DataNode, E> {
+        Ok(DataNode::Scalar(v.to_string(), ScalarKind::String))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<DataNode, E> {
+        Ok(DataNode::Scalar(v, ScalarKind::String))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<DataNode, E> {
+        Ok(DataNode::Scalar(String::new(), ScalarKind::Null))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<DataNode, E> {
+        self.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<DataNode, D::Error> {
+        DataNode::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<DataNode, A::Error> {
+        let mut elements = Vec::new();
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+        Ok(DataNode::Array(elements))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DataNode, A::Error> {
+        let mut members = Vec::new();
+        while let Some((key, value)) = map.next_entry::<DataNode, DataNode>()? {
+            // YAML allows numbers and booleans as keys, but not collections
+            let DataNode::Scalar(key, _) = key else {
+                return Err(de::Error::custom("object keys must be scalars"));
+            };
+            members.push((key, value));
+        }
+        match members.as_slice() {
+            [(key, DataNode::Scalar(datetime, _))] if key == TOML_DATETIME_KEY => {
+                Ok(DataNode::Scalar(datetime.clone(), ScalarKind::String))
+            }
+            _ => Ok(DataNode::Object(members)),
+        }
+    }
+}
+
+impl Serialize for DataNode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DataNode::Scalar(_, ScalarKind::Null) => serializer.serialize_unit(),
+            DataNode::Scalar(text, ScalarKind::Bool) => serializer.serialize_bool(text == "true"),
+            DataNode::Scalar(text, ScalarKind::Number) => match text.parse::<serde_json::Number>() {
+                Ok(number) => number.serialize(serializer),
+                Err(_) => serializer.serialize_str(text),
+            },
+            DataNode::Scalar(text, ScalarKind::String) => serializer.serialize_str(text),
+            DataNode::Array(elements) => serializer.collect_seq(elements),
+            DataNode::Object(members) => {
+                let mut map = serializer.serialize_map(Some(members.len()))?;
+                for (key, value) in members {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl DataParser {
+    pub fn new(options: DataParserOptions) -> Self {
+        DataParser { options }
+    }
+
+    /// Parses a document into an `Outline`.
+    ///
+    /// Syntax errors are reported as a `ParseError`.
+    pub fn parse(&self, input: &str) -> Result<Outline> {
+        let root = self.read(input)?;
+        let mut outline = Outline::new();
+        outline.key_header = self.options.key_header.clone();
+        outline.value_header = self.options.value_header.clone();
+        if outline.value_header.is_empty() {
+            outline.value_header.push(SCALAR_HEADER.to_string());
+            outline
+                .value_header
+                .extend(self.options.value_fields.iter().cloned());
+        }
+        match &root {
+            DataNode::Scalar(..) => {
+                return Err(ParseError::at_offset(
+                    "expected an object or an array at the top level",
+                    input,
+                    input.len() - input.trim_start().len(),
+                )
+                .into())
+            }
+            _ => self.add_children(&root, 1, &mut outline),
+        }
+        Ok(outline)
+    }
+
+    fn read(&self, input: &str) -> Result<DataNode> {
+        let node = match self.options.format {
+            DataFormat::Json => serde_json::from_str(input)
+                .map_err(|e| json_error("invalid JSON document", input, &e))?,
+            DataFormat::Yaml => serde_yaml_ng::from_str(input).map_err(|e| {
+                let offset = e.location().map_or(input.len(), |l| l.index());
+                let message = e.to_string();
+                let message = match e.location() {
+                    Some(l) => message
+                        .strip_suffix(&format!(" at line {} column {}", l.line(), l.column()))
+                        .unwrap_or(&message)
+                        .to_string(),
+                    None => message,
+                };
+                ParseError::at_offset(format!("invalid YAML document: {}", message), input, offset)
+            })?,
+            DataFormat::Toml => toml::from_str(input).map_err(|e| {
+                let offset = e.span().map_or(input.len(), |span| span.start);
+                ParseError::at_offset(
+                    format!("invalid TOML document: {}", e.message()),
+                    input,
+                    offset,
+                )
+            })?,
+        };
+        Ok(node)
+    }
+
+    /// Adds the members or elements of `node` as items at `level`, with their descendants.
+    fn add_children(&self, node: &DataNode, level: u32, outline: &mut Outline) {
+        match node {
+            DataNode::Scalar(..) => {}
+            DataNode::Object(members) => {
+                for (key, child) in members {
+                    self.add_item(key.clone(), child, Vec::new(), level, outline);
+                }
+            }
+            DataNode::Array(elements) => {
+                for (index, element) in elements.iter().enumerate() {
+                    let DataNode::Object(members) = element else {
+                        self.add_item(index.to_string(), element, Vec::new(), level, outline);
+                        continue;
+                    };
+                    let key_field = self.options.key_field.as_deref();
+                    let field = |name: &str| members.iter().find(|(key, _)| key == name);
+                    let key = key_field
+                        .and_then(field)
+                        .and_then(|(_, value)| value.as_scalar())
+                        .map_or_else(|| index.to_string(), str::to_string);
+                    let values = self
+                        .options
+                        .value_fields
+                        .iter()
+                        .map(|name| field(name).map_or(String::new(), |(_, value)| value.to_text()))
+                        .collect();
+                    let rest = members
+                        .iter()
+                        .filter(|(name, _)| {
+                            Some(name.as_str()) != key_field
+                                && !self.options.value_fields.contains(name)
+                        })
+                        .cloned()
+                        .collect();
+                    self.add_item(key, &DataNode::Object(rest), values, level, outline);
+                }
+            }
+        }
+    }
+
+    /// Adds an item for `node` keyed `key`, with `fields` after its scalar value column.
+    fn add_item(
+        &self,
+        key: String,
+        node: &DataNode,
+        fields: Vec<String>,
+        level: u32,
+        outline: &mut Outline,
+    ) {
+        let at_max_depth = self.options.max_depth.is_some_and(|depth| level >= depth);
+        let scalar = match node {
+            DataNode::Scalar(text, _) => text.clone(),
+            _ if at_max_depth => node.to_text(),
+            _ => String::new(),
+        };
+        let mut value: Vec<Value> = vec![scalar.into()];
+        value.extend(fields.into_iter().map(Value::from));
+        if value.iter().all(Value::is_empty) {
+            value.clear();
+        }
+        outline.item.push(OutlineItem { key, level, value });
+        if !at_max_depth {
+            self.add_children(node, level + 1, outline);
+        }
+    }
+}
+
+impl Parser for DataParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(outline: &Outline) -> Vec<(String, u32, Vec<String>)> {
+        outline
+            .item
+            .iter()
+            .map(|item| {
+                (
+                    item.key.clone(),
+                    item.level,
+                    item.value.iter().map(|v| v.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn row(key: &str, level: u32, value: &[&str]) -> (String, u32, Vec<String>) {
+        (
+            key.to_string(),
+            level,
+            value.iter().map(|v| v.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_parse_json() -> Result<()> {
+        let input = r#"{"name": "app", "version": 2, "debug": null,
+            "servers": [{"host": "a", "port": 80}, "b"], "empty": {}}"#;
+        let outline = DataParser::new(DataParserOptions::default()).parse(input)?;
+        assert_eq!(outline.value_header, vec!["Value"]);
+        assert_eq!(
+            rows(&outline),
+            vec![
+                row("name", 1, &["app"]),
+                row("version", 1, &["2"]),
+                row("debug", 1, &[]),
+                row("servers", 1, &[]),
+                row("0", 2, &[]),
+                row("host", 3, &["a"]),
+                row("port", 3, &["80"]),
+                row("1", 2, &["b"]),
+                row("empty", 1, &[]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_yaml_fields_and_depth() -> Result<()> {
+        let input = "\
+teams:
+  - name: Sales
+    lead: Ann
+    size: 3
+    members: [Bob, Carl]
+  - name: R&D
+    lead: Dan
+    tags: {remote: true, floor: 2, code: '007', desk: ~}
+";
+        let outline = DataParser::new(DataParserOptions {
+            format: DataFormat::Yaml,
+            key_field: Some("name".to_string()),
+            value_fields: vec!["lead".to_string(), "size".to_string()],
+            max_depth: Some(3),
+            ..Default::default()
+        })
+        .parse(input)?;
+        assert_eq!(outline.value_header, vec!["Value", "lead", "size"]);
+        assert_eq!(
+            rows(&outline),
+            vec![
+                row("teams", 1, &[]),
+                row("Sales", 2, &["", "Ann", "3"]),
+                row("members", 3, &[r#"["Bob","Carl"]"#]),
+                row("R&D", 2, &["", "Dan", ""]),
+                row(
+                    "tags",
+                    3,
+                    &[r#"{"remote":true,"floor":2,"code":"007","desk":null}"#]
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_toml() -> Result<()> {
+        let input = "\
+title = \"Plan\"
+
+[owner]
+name = \"Ann\"
+since = 2024-01-31
+
+[[phase]]
+name = \"Design\"
+";
+        let outline = DataParser::new(DataParserOptions {
+            format: DataFormat::Toml,
+            ..Default::default()
+        })
+        .parse(input)?;
+        assert_eq!(
+            rows(&outline),
+            vec![
+                row("title", 1, &["Plan"]),
+                row("owner", 1, &[]),
+                row("name", 2, &["Ann"]),
+                row("since", 2, &["2024-01-31"]),
+                row("phase", 1, &[]),
+                row("0", 2, &[]),
+                row("name", 3, &["Design"]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let parse = |format, input| {
+            DataParser::new(DataParserOptions {
+                format,
+                ..Default::default()
+            })
+            .parse(input)
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap()
+        };
+        let err = parse(DataFormat::Json, "\"text\"");
+        assert_eq!(
+            err.message,
+            "expected an object or an array at the top level"
+        );
+        let err = parse(DataFormat::Yaml, "a: 1\nb: [1\n");
+        assert_eq!(err.location.line, 3);
+        assert!(err.message.starts_with("invalid YAML document: "));
+        let err = parse(DataFormat::Toml, "a = 1\nb = \n");
+        assert_eq!((err.location.line, err.location.column), (2, 5));
+        assert!(err.message.starts_with("invalid TOML document: "));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::outline::Outline;
+use crate::parser::Parser;
 use anyhow::{Context, Result};
 use std::{
     collections::HashSet,
@@ -92,6 +93,22 @@ impl DirTreeParser {
     }
 }
 
+impl Parser for DirTreeParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        let path = match input_path {
+            Some(p) => p,
+            None => anyhow::bail!("Input path is required for dir_tree parser."),
+        };
+        if !path.is_dir() {
+            anyhow::bail!(
+                "Input path '{}' is not a valid directory for dir_tree parser.",
+                path.display()
+            );
+        }
+        self.parse(path)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,323 @@
+//! Structured errors and warnings reported by the parsers.
+//! 
+//! A `ParseError` points at the offending position of the input (file name, line
+//! and column) and keeps the line as a snippet, so that it can be rendered as a
+//! compiler-style diagnostic. A `ParseWarning` points at a line that was read in a
+//! way its author may not have intended.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// A position in an input file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SourceLocation {
+    /// The input file, or `None` for stdin.
+    pub file: Option<PathBuf>,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number, counted in characters.
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}", file.display())?,
+            None => write!(f, "<stdin>")?,
+        }
+        write!(f, ":{}:{}", self.line, self.column)
+    }
+}
+
+/// Represents an error found at a specific position of the input.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{location}: {message}")]
+pub struct ParseError {
+    /// A description of the error.
+    pub message: String,
+    /// Where the error was found.
+    pub location: SourceLocation,
+    /// The input line containing the error.
+    pub snippet: String,
+}
+
+impl ParseError {
+    /// Creates an error at 1-based `line` and `column` of `input`.
+    pub fn at_line(message: impl Into<String>, input: &str, line: usize, column: usize) -> Self {
+        let snippet = input
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or("")
+            .to_string();
+        ParseError {
+            message: message.into(),
+            location: SourceLocation {
+                file: None,
+                line,
+                column,
+            },
+            snippet,
+        }
+    }
+
+    /// Creates an error at the byte `offset` of `input`.
+    ///
+    /// An offset beyond the end of `input` points just after its last character.
+    pub fn at_offset(message: impl Into<String>, input: &str, offset: usize) -> Self {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        Self::at_line(message, input, line, column)
+    }
+
+    /// Creates an error at the first non-whitespace character at or after the byte `offset`
+    /// of the file at `path`.
+    ///
+    /// The file is scanned line by line, so that the whole input is never held in memory.
+    pub(crate) fn at_token_in_file(
+        message: impl Into<String>,
+        path: &Path,
+        mut offset: usize,
+    ) -> std::io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut line = Vec::new();
+        let mut line_start = 0;
+        let mut line_number = 0;
+        let (line_number, column, snippet) = loop {
+            line.clear();
+            let len = reader.read_until(b'\n', &mut line)?;
+            if len == 0 {
+                // Past the end of a file ending with a newline
+                break (line_number + 1, 1, String::new());
+            }
+            line_number += 1;
+            let snippet = || {
+                String::from_utf8_lossy(&line)
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string()
+            };
+            if offset < line_start + len {
+                let start = offset - line_start;
+                if let Some(i) = line[start..].iter().position(|b| !b.is_ascii_whitespace()) {
+                    let column = String::from_utf8_lossy(&line[..start + i]).chars().count() + 1;
+                    break (line_number, column, snippet());
+                }
+                // Only whitespace follows in this line
+                offset = line_start + len;
+            }
+            if !line.ends_with(b"\n") {
+                // Past the end of a file not ending with a newline
+                break (line_number, snippet().chars().count() + 1, snippet());
+            }
+            line_start += len;
+        };
+        Ok(ParseError {
+            message: message.into(),
+            location: SourceLocation {
+                file: Some(path.to_path_buf()),
+                line: line_number,
+                column,
+            },
+            snippet,
+        })
+    }
+
+    /// Sets the file the error was found in.
+    pub fn with_file(mut self, file: Option<&Path>) -> Self {
+        self.location.file = file.map(Path::to_path_buf);
+        self
+    }
+
+    /// Renders the error as a compiler-style diagnostic, e.g.:
+    ///
+    /// ```text
+    /// error: unexpected end of file
+    ///  --> outline.opml:3:5
+    ///   |
+    /// 3 |     <outline text="b">
+    ///   |     ^
+    /// ```
+    pub fn render(&self) -> String {
+        let line_number = self.location.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let snippet = self.snippet.trim_end_matches('\r');
+        // Keep tabs so that the caret lines up with the snippet
+        let caret_indent: String = snippet
+            .chars()
+            .take(self.location.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "error: {}\n{}--> {}\n{} |\n{} | {}\n{} | {}^",
+            self.message, gutter, self.location, gutter, line_number, snippet, gutter, caret_indent
+        )
+    }
+}
+
+/// Represents a line of the input that was read, but possibly not as the author intended.
+///
+/// Warnings are returned to the caller alongside the parsed outline, e.g. by
+/// [`crate::parser::Parser::parse_input_with_warnings`], and do not stop the parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// A description of the warning.
+    pub message: String,
+    /// Where the warning was found.
+    pub location: SourceLocation,
+    /// The input line the warning is about.
+    pub snippet: String,
+}
+
+impl ParseWarning {
+    /// Creates a warning at the 1-based `column` of `line`, the 1-based `line_number`-th
+    /// line of the input.
+    pub fn at_line(
+        message: impl Into<String>,
+        line: &str,
+        line_number: usize,
+        column: usize,
+    ) -> Self {
+        ParseWarning {
+            message: message.into(),
+            location: SourceLocation {
+                file: None,
+                line: line_number,
+                column,
+            },
+            snippet: line.to_string(),
+        }
+    }
+
+    /// Sets the file the warning was found in.
+    pub fn with_file(mut self, file: Option<&Path>) -> Self {
+        self.location.file = file.map(Path::to_path_buf);
+        self
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// An error at a byte offset of the input, reported by parsers that can read either a
+/// string or a file and converted to a `ParseError` by [`locate_in_str`] or [`locate_in_file`].
+///
+/// The error is located at the first non-whitespace character at or after `offset`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} (at byte {offset})")]
+pub(crate) struct OffsetError {
+    pub message: String,
+    pub offset: usize,
+}
+
+/// Converts an `OffsetError` in `error` to a `ParseError` in `input`, and keeps other errors.
+pub(crate) fn locate_in_str(error: anyhow::Error, input: &str) -> anyhow::Error {
+    match error.downcast::<OffsetError>() {
+        Ok(e) => ParseError::at_offset(e.message, input, skip_whitespace(input, e.offset)).into(),
+        Err(e) => e,
+    }
+}
+
+/// Converts an `OffsetError` in `error` to a `ParseError` in the file at `path`, and keeps
+/// other errors.
+pub(crate) fn locate_in_file(error: anyhow::Error, path: &Path) -> anyhow::Error {
+    match error.downcast::<OffsetError>() {
+        Ok(e) => match ParseError::at_token_in_file(e.message, path, e.offset) {
+            Ok(parse_error) => parse_error.into(),
+            Err(io_error) => io_error.into(),
+        },
+        Err(e) => e,
+    }
+}
+
+/// Returns the offset of the first non-whitespace character at or after `offset`.
+pub(crate) fn skip_whitespace(input: &str, offset: usize) -> usize {
+    let offset = offset.min(input.len());
+    input[offset..]
+        .find(|c: char| !c.is_whitespace())
+        .map_or(input.len(), |i| offset + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_at_offset() {
+        let input = "a\n\tbé c\nd";
+        let err = ParseError::at_offset("oops", input, input.find('c').unwrap());
+        assert_eq!(err.location.line, 2);
+        assert_eq!(err.location.column, 5);
+        assert_eq!(err.snippet, "\tbé c");
+        assert_eq!(err.to_string(), "<stdin>:2:5: oops");
+
+        let err = ParseError::at_offset("eof", input, 100);
+        assert_eq!((err.location.line, err.location.column), (3, 2));
+    }
+
+    #[test]
+    fn test_parse_error_render() {
+        let input = "a\n\tbé c\n";
+        let err = ParseError::at_offset("oops", input, input.find('c').unwrap())
+            .with_file(Some(Path::new("in.txt")));
+        assert_eq!(err.to_string(), "in.txt:2:5: oops");
+        assert_eq!(
+            err.render(),
+            "error: oops\n --> in.txt:2:5\n  |\n2 | \tbé c\n  | \t   ^"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_at_token_in_file() -> std::io::Result<()> {
+        use std::io::Write;
+
+        let input = "a\r\n\tbé c\nd";
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(input.as_bytes())?;
+
+        for offset in [input.find('c').unwrap(), input.find('\r').unwrap()] {
+            let err = ParseError::at_token_in_file("oops", file.path(), offset)?;
+            assert_eq!(
+                err,
+                ParseError::at_offset("oops", input, skip_whitespace(input, offset))
+                    .with_file(Some(file.path()))
+            );
+        }
+
+        let err = ParseError::at_token_in_file("eof", file.path(), 100)?;
+        assert_eq!((err.location.line, err.location.column), (3, 2));
+        assert_eq!(err.snippet, "d");
+
+        file.write_all(b"\n")?;
+        let err = ParseError::at_token_in_file("eof", file.path(), 100)?;
+        assert_eq!(
+            err,
+            ParseError::at_offset("eof", &format!("{}\n", input), 100).with_file(Some(file.path()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_warning_at_line() {
+        let warning =
+            ParseWarning::at_line("odd", "   b", 7, 4).with_file(Some(Path::new("in.txt")));
+        assert_eq!(warning.snippet, "   b");
+        assert_eq!(warning.to_string(), "in.txt:7:4: odd");
+    }
+
+    #[test]
+    fn test_skip_whitespace() {
+        assert_eq!(skip_whitespace("a \n b", 1), 4);
+        assert_eq!(skip_whitespace("a  ", 1), 3);
+    }
+}
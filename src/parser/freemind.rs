@@ -0,0 +1,320 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::{locate_in_str, OffsetError};
+use crate::parser::{parse_input_with, Parser};
+use anyhow::Result;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::path::Path;
+
+/// The HTML elements of rich content that end a line of text.
+const BLOCK_ELEMENTS: [&[u8]; 11] = [
+    b"p", b"div", b"li", b"br", b"tr", b"h1", b"h2", b"h3", b"h4", b"h5", b"h6",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct FreemindParserOptions {
+    /// If true, add a last value column `Note` with the text of the note of each node.
+    pub note_column: bool,
+    pub key_header: Vec<String>,
+    /// The value columns to keep: names of `<attribute>` elements, or of attributes of the
+    /// `<node>` element such as `LINK`. Defaults to the names of the `<attribute>` elements
+    /// in order of first appearance.
+    pub value_header: Vec<String>,
+}
+
+/// A parser for FreeMind and Freeplane mind maps (`.mm`), where each `<node>` is an item
+/// at its nesting depth.
+pub struct FreemindParser {
+    options: FreemindParserOptions,
+}
+
+/// A `<node>` element of the map.
+#[derive(Default)]
+struct Node {
+    level: u32,
+    key: String,
+    /// The attributes of the `<node>` element other than `TEXT`.
+    node_attributes: Vec<(String, String)>,
+    /// The `NAME` and `VALUE` of the `<attribute>` children.
+    attributes: Vec<(String, String)>,
+    note: String,
+}
+
+impl Node {
+    fn value(&self, name: &str) -> &str {
+        self.attributes
+            .iter()
+            .chain(&self.node_attributes)
+            .find(|(key, _)| key == name)
+            .map_or("", |(_, value)| value.as_str())
+    }
+}
+
+/// A `<richcontent>` element being read.
+struct RichContent {
+    /// The `TYPE` attribute: `NODE` for the text of the node, `NOTE` for its note.
+    kind: String,
+    text: String,
+}
+
+/// Returns the name and unescaped value of each attribute of `element`.
+fn attributes(element: &BytesStart) -> Result<Vec<(String, String)>> {
+    let mut attributes = Vec::new();
+    for attr in element.attributes() {
+        let attr = attr?;
+        attributes.push((
+            String::from_utf8_lossy(attr.key.into_inner()).into_owned(),
+            attr.unescape_value()?.into_owned(),
+        ));
+    }
+    Ok(attributes)
+}
+
+/// Collapses the whitespace of each line of `text` and drops empty lines.
+fn normalize_text(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl FreemindParser {
+    pub fn new(options: FreemindParserOptions) -> Self {
+        FreemindParser { options }
+    }
+
+    /// Parses a mind map into an `Outline`.
+    ///
+    /// The key of a node is its `TEXT` attribute, or the text of its `NODE` rich content.
+    /// Rich content (HTML) is converted to plain text, one line per paragraph.
+    pub fn parse(&self, input: &str) -> Result<Outline> {
+        let nodes =
+            Self::read_nodes(Reader::from_str(input)).map_err(|e| locate_in_str(e, input))?;
+
+        let mut outline = Outline::new();
+        outline.key_header = self.options.key_header.clone();
+        outline.value_header = self.options.value_header.clone();
+        if outline.value_header.is_empty() {
+            for node in &nodes {
+                for (name, _) in &node.attributes {
+                    if !outline.value_header.contains(name) {
+                        outline.value_header.push(name.clone());
+                    }
+                }
+            }
+        }
+        let value_header = outline.value_header.clone();
+        if self.options.note_column {
+            outline.value_header.push("Note".to_string());
+        }
+
+        for node in &nodes {
+            let mut value: Vec<String> = value_header
+                .iter()
+                .map(|name| node.value(name).to_string())
+                .collect();
+            if self.options.note_column {
+                value.push(node.note.clone());
+            }
+            outline
+                .item
+                .push(OutlineItem::new(&node.key, node.level, value));
+        }
+        Ok(outline)
+    }
+
+    /// Reads the `<node>` elements with `reader`, in document order.
+    ///
+    /// Malformed XML is reported as an `OffsetError`.
+    fn read_nodes(mut reader: Reader<&[u8]>) -> Result<Vec<Node>> {
+        let mut nodes: Vec<Node> = Vec::new();
+        // The indices of the open `<node>` elements
+        let mut stack: Vec<usize> = Vec::new();
+        let mut rich_content: Option<RichContent> = None;
+        loop {
+            let event_start = reader.buffer_position() as usize;
+            let offset_error = |e: &dyn std::fmt::Display| OffsetError {
+                message: e.to_string(),
+                offset: event_start,
+            };
+            let event = reader.read_event().map_err(|e| OffsetError {
+                message: e.to_string(),
+                offset: reader.error_position() as usize,
+            })?;
+            if let Some(rich) = &mut rich_content {
+                match event {
+                    Event::End(e) if e.local_name().as_ref() == b"richcontent" => {
+                        let text = normalize_text(&rich.text);
+                        if let Some(node) = stack.last().map(|&i| &mut nodes[i]) {
+                            match rich.kind.as_str() {
+                                "NODE" if node.key.is_empty() => node.key = text,
+                                "NOTE" => node.note = text,
+                                _ => {}
+                            }
+                        }
+                        rich_content = None;
+                    }
+                    Event::End(e) if BLOCK_ELEMENTS.contains(&e.local_name().as_ref()) => {
+                        rich.text.push('\n')
+                    }
+                    Event::Empty(e) if e.local_name().as_ref() == b"br" => rich.text.push('\n'),
+                    Event::Text(e) => rich
+                        .text
+                        .push_str(&e.decode().map_err(|e| offset_error(&e))?),
+                    Event::CData(e) => rich
+                        .text
+                        .push_str(&e.decode().map_err(|e| offset_error(&e))?),
+                    Event::GeneralRef(e) => {
+                        let name = e.decode().map_err(|e| offset_error(&e))?;
+                        match e.resolve_char_ref().map_err(|e| offset_error(&e))? {
+                            Some(c) => rich.text.push(c),
+                            None => match resolve_predefined_entity(&name) {
+                                Some(text) => rich.text.push_str(text),
+                                None => rich.text.push_str(&format!("&{};", name)),
+                            },
+                        }
+                    }
+                    Event::Eof => break,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match event {
+                Event::Eof => break,
+                Event::Start(ref e) | Event::Empty(ref e) if e.local_name().as_ref() == b"node" => {
+                    let mut node = Node {
+                        level: stack.len() as u32 + 1,
+                        ..Default::default()
+                    };
+                    for (name, value) in attributes(e).map_err(|e| offset_error(&e))? {
+                        match name.as_str() {
+                            "TEXT" => node.key = value.trim().to_string(),
+                            _ => node.node_attributes.push((name, value)),
+                        }
+                    }
+                    nodes.push(node);
+                    if matches!(event, Event::Start(_)) {
+                        stack.push(nodes.len() - 1);
+                    }
+                }
+                Event::End(e) if e.local_name().as_ref() == b"node" => {
+                    stack.pop();
+                }
+                Event::Start(ref e) | Event::Empty(ref e)
+                    if e.local_name().as_ref() == b"attribute" =>
+                {
+                    let attributes = attributes(e).map_err(|e| offset_error(&e))?;
+                    let get = |key: &str| {
+                        attributes
+                            .iter()
+                            .find(|(name, _)| name == key)
+                            .map(|(_, value)| value.clone())
+                    };
+                    if let (Some(node), Some(name)) = (stack.last(), get("NAME")) {
+                        nodes[*node]
+                            .attributes
+                            .push((name, get("VALUE").unwrap_or_default()));
+                    }
+                }
+                Event::Start(e) if e.local_name().as_ref() == b"richcontent" => {
+                    let kind = attributes(&e)
+                        .map_err(|e| offset_error(&e))?
+                        .into_iter()
+                        .find(|(name, _)| name == "TYPE")
+                        .map(|(_, value)| value)
+                        .unwrap_or_default();
+                    rich_content = Some(RichContent {
+                        kind,
+                        text: String::new(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(nodes)
+    }
+}
+
+impl Parser for FreemindParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::error::ParseError;
+
+    const INPUT: &str = r#"<map version="freeplane 1.9.0">
+<!--To view this file, download free mind mapping software Freeplane from https://www.freeplane.org -->
+<node TEXT="Launch" ID="ID_1" LINK="https://example.com/launch">
+<hook NAME="MapStyle"/>
+<node TEXT="Marketing &amp; PR" POSITION="right">
+<attribute NAME="Owner" VALUE="Ann"/>
+<attribute NAME="Cost" VALUE="1200"/>
+<richcontent TYPE="NOTE">
+<html>
+  <head></head>
+  <body>
+    <p>Press kit &#8211; <b>draft</b></p>
+    <ul><li>Blog post</li><li>Newsletter</li></ul>
+  </body>
+</html>
+</richcontent>
+<node TEXT="Ads"/>
+</node>
+<node ID="ID_3" POSITION="left">
+<richcontent TYPE="NODE"><html><body><p>Rich <i>title</i></p></body></html></richcontent>
+<attribute NAME="Owner" VALUE="Bob"/>
+</node>
+</node>
+</map>
+"#;
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let outline = FreemindParser::new(FreemindParserOptions::default()).parse(INPUT)?;
+        let mut expected = Outline::new();
+        expected.value_header = vec!["Owner".to_string(), "Cost".to_string()];
+        expected.add_item("Launch", 1, vec!["".to_string(), "".to_string()]);
+        expected.add_item(
+            "Marketing & PR",
+            2,
+            vec!["Ann".to_string(), "1200".to_string()],
+        );
+        expected.add_item("Ads", 3, vec!["".to_string(), "".to_string()]);
+        expected.add_item("Rich title", 2, vec!["Bob".to_string(), "".to_string()]);
+        assert_eq!(outline, expected);
+
+        let parser = FreemindParser::new(FreemindParserOptions {
+            note_column: true,
+            key_header: Vec::new(),
+            value_header: vec!["LINK".to_string(), "Owner".to_string()],
+        });
+        let outline = parser.parse(INPUT)?;
+        assert_eq!(outline.value_header, vec!["LINK", "Owner", "Note"]);
+        assert_eq!(
+            outline.item[0].value,
+            vec!["https://example.com/launch", "", ""]
+        );
+        assert_eq!(
+            outline.item[1].value,
+            vec!["", "Ann", "Press kit \u{2013} draft\nBlog post\nNewsletter"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        let err = FreemindParser::new(FreemindParserOptions::default())
+            .parse("<map>\n<node TEXT=\"A\">\n</map>\n")
+            .unwrap_err()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!(err.location.line, 3);
+    }
+}
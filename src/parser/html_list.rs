@@ -1,6 +1,9 @@
 use html5ever::parse_document;
 use html5ever::tendril::TendrilSink;
 use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use std::path::Path;
+
+use crate::parser::{parse_input_with, Parser};
 
 #[derive(Debug, Clone)]
 pub struct HtmlListParserOptions {
@@ -99,6 +102,12 @@ impl HtmlListParser {
     }
 }
 
+impl Parser for HtmlListParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> anyhow::Result<crate::outline::Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
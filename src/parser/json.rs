@@ -0,0 +1,132 @@
+use crate::outline::Outline;
+use crate::parser::error::ParseError;
+use crate::parser::{parse_input_with, Parser};
+use anyhow::Result;
+use std::path::Path;
+
+/// A parser for the JSON interchange format written by `JsonGenerator`.
+///
+/// The input is a serialized `Outline`:
+///
+/// ```json
+/// {
+///   "key_header": ["H1"],
+///   "value_header": ["H(1)"],
+///   "item": [{ "key": "1", "level": 1, "value": ["1(1)"] }]
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct JsonParser;
+
+impl JsonParser {
+    /// Creates a new `JsonParser`.
+    pub fn new() -> Self {
+        JsonParser
+    }
+
+    /// Parses the JSON input string into an `Outline` structure.
+    ///
+    /// Missing `key_header`, `value_header`, `key` or `value` members are treated as empty.
+    /// Malformed input, including an item without a positive `level`, is reported as a
+    /// `ParseError`.
+    pub fn parse(&self, input: &str) -> Result<Outline> {
+        let outline: Outline = serde_json::from_str(input)
+            .map_err(|e| json_error("invalid JSON outline", input, &e))?;
+        Ok(outline)
+    }
+}
+
+/// Converts a `serde_json` error into a `ParseError` prefixed with `context`.
+pub(crate) fn json_error(context: &str, input: &str, e: &serde_json::Error) -> ParseError {
+    // serde_json reports 1-based lines and byte columns
+    let line_start: usize = input
+        .split_inclusive('\n')
+        .take(e.line().saturating_sub(1))
+        .map(str::len)
+        .sum();
+    let message = e.to_string();
+    let message = message
+        .strip_suffix(&format!(" at line {} column {}", e.line(), e.column()))
+        .unwrap_or(&message);
+    ParseError::at_offset(
+        format!("{}: {}", context, message),
+        input,
+        line_start + e.column().saturating_sub(1),
+    )
+}
+
+impl Parser for JsonParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_parser_parse() -> Result<()> {
+        let input = r#"{
+            "key_header": ["H1", "H2"],
+            "value_header": ["H(1)"],
+            "item": [
+                { "key": "1", "level": 1, "value": ["1(1)"] },
+                { "key": "1.1", "level": 2, "value": [] }
+            ]
+        }"#;
+        let outline = JsonParser::new().parse(input)?;
+
+        let mut expected = Outline::new();
+        expected.key_header = vec!["H1".to_string(), "H2".to_string()];
+        expected.value_header = vec!["H(1)".to_string()];
+        expected.add_item("1", 1, vec!["1(1)".to_string()]);
+        expected.add_item("1.1", 2, vec![]);
+        assert_eq!(outline, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_parser_missing_members() -> Result<()> {
+        let outline = JsonParser::new().parse(r#"{ "item": [{ "key": "A", "level": 1 }] }"#)?;
+        assert!(outline.key_header.is_empty());
+        assert!(outline.value_header.is_empty());
+        assert_eq!(outline.item[0].key, "A");
+        assert!(outline.item[0].value.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_parser_invalid() {
+        assert!(JsonParser::new().parse("[1, 2, 3]").is_err());
+        assert!(JsonParser::new().parse("{").is_err());
+
+        let err = JsonParser::new()
+            .parse("{\n  \"item\": [{ \"key\": \"A\", \"level\": x }]\n}")
+            .unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!((err.location.line, err.location.column), (2, 35));
+        assert_eq!(
+            err.snippet,
+            "  \"item\": [{ \"key\": \"A\", \"level\": x }]"
+        );
+        assert_eq!(err.message, "invalid JSON outline: expected value");
+
+        let err = JsonParser::new()
+            .parse("{\"item\": [\n{\"key\": \"a\"},\n{\"key\": \"b\", \"level\": 2}]}")
+            .unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err.location.line, 2);
+        assert_eq!(err.message, "invalid JSON outline: missing field `level`");
+
+        let err = JsonParser::new()
+            .parse("{\"item\": [\n{\"key\": \"a\", \"level\": 0}]}")
+            .unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err.location.line, 2);
+        assert_eq!(
+            err.message,
+            "invalid JSON outline: invalid value: integer `0`, expected a level of 1 or more"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::{parse_input_with, Parser};
+use anyhow::Result;
+use clap::ValueEnum;
+use pulldown_cmark::{Event, Options, Tag, TagEnd};
+use std::path::Path;
+
+/// Which elements of a Markdown document form the outline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum MarkdownStructure {
+    /// ATX (`#`) and Setext (`===` / `---`) headings, at their heading level.
+    #[default]
+    Headings,
+    /// Items of nested `-`, `*`, `+` and `1.` lists, at their nesting depth.
+    Lists,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownParserOptions {
+    pub structure: MarkdownStructure,
+    /// If true, add a value column with the state of task-list items: `true` for `[x]`,
+    /// `false` for `[ ]` and empty for other items.
+    pub task_column: bool,
+    /// If true, add a value column with the URL of the first inline link of each item.
+    pub link_column: bool,
+    pub key_header: Vec<String>,
+    /// The headers of the task and link columns. Defaults to `Done` and `URL`.
+    pub value_header: Vec<String>,
+}
+
+/// A parser for Markdown documents, based on headings or on nested lists.
+pub struct MarkdownParser {
+    options: MarkdownParserOptions,
+}
+
+/// An item whose text is being read.
+struct PendingItem {
+    level: u32,
+    key: String,
+    task: Option<bool>,
+    url: Option<String>,
+}
+
+impl MarkdownParser {
+    pub fn new(options: MarkdownParserOptions) -> Self {
+        MarkdownParser { options }
+    }
+
+    /// Parses a Markdown document into an `Outline`.
+    ///
+    /// The key of an item is the plain text of its heading, or of its list item up to any
+    /// nested list, with inline markup removed.
+    pub fn parse(&self, input: &str) -> Result<Outline> {
+        let mut outline = Outline::new();
+        outline.key_header = self.options.key_header.clone();
+        outline.value_header = self.options.value_header.clone();
+        if outline.value_header.is_empty() {
+            if self.options.task_column {
+                outline.value_header.push("Done".to_string());
+            }
+            if self.options.link_column {
+                outline.value_header.push("URL".to_string());
+            }
+        }
+
+        let mut pending: Option<PendingItem> = None;
+        let mut list_depth = 0;
+        let mut in_code_block = false;
+        let new_item = |level| PendingItem {
+            level,
+            key: String::new(),
+            task: None,
+            url: None,
+        };
+        for event in pulldown_cmark::Parser::new_ext(input, Options::ENABLE_TASKLISTS) {
+            match (self.options.structure, event) {
+                (MarkdownStructure::Headings, Event::Start(Tag::Heading { level, .. })) => {
+                    pending = Some(new_item(level as u32));
+                }
+                (MarkdownStructure::Headings, Event::End(TagEnd::Heading(_))) => {
+                    self.flush(&mut pending, &mut outline);
+                }
+                (MarkdownStructure::Lists, Event::Start(Tag::List(_))) => {
+                    // The text of the parent item ends where a nested list starts
+                    self.flush(&mut pending, &mut outline);
+                    list_depth += 1;
+                }
+                (MarkdownStructure::Lists, Event::End(TagEnd::List(_))) => {
+                    list_depth -= 1;
+                }
+                (MarkdownStructure::Lists, Event::Start(Tag::Item)) => {
+                    pending = Some(new_item(list_depth));
+                }
+                (MarkdownStructure::Lists, Event::End(TagEnd::Item)) => {
+                    self.flush(&mut pending, &mut outline);
+                }
+                (_, Event::Start(Tag::CodeBlock(_))) => in_code_block = true,
+                (_, Event::End(TagEnd::CodeBlock)) => in_code_block = false,
+                (_, event) => {
+                    let Some(item) = pending.as_mut() else {
+                        continue;
+                    };
+                    match event {
+                        Event::Text(text) | Event::Code(text) if !in_code_block => {
+                            item.key.push_str(&text);
+                        }
+                        Event::SoftBreak | Event::HardBreak | Event::Start(Tag::Paragraph)
+                            if !item.key.is_empty() && !item.key.ends_with(' ') =>
+                        {
+                            item.key.push(' ');
+                        }
+                        Event::TaskListMarker(checked) => item.task = Some(checked),
+                        Event::Start(Tag::Link { dest_url, .. }) if item.url.is_none() => {
+                            item.url = Some(dest_url.to_string());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(outline)
+    }
+
+    /// Adds the pending item, if any, to `outline`.
+    fn flush(&self, pending: &mut Option<PendingItem>, outline: &mut Outline) {
+        let Some(item) = pending.take() else {
+            return;
+        };
+        let mut value = Vec::new();
+        if self.options.task_column {
+            value.push(
+                item.task
+                    .map_or(String::new(), |done| done.to_string())
+                    .into(),
+            );
+        }
+        if self.options.link_column {
+            value.push(item.url.unwrap_or_default().into());
+        }
+        outline.item.push(OutlineItem {
+            key: item.key.trim().to_string(),
+            level: item.level,
+            value,
+        });
+    }
+}
+
+impl Parser for MarkdownParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(outline: &Outline) -> Vec<(&str, u32)> {
+        outline
+            .item
+            .iter()
+            .map(|item| (item.key.as_str(), item.level))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_headings() -> Result<()> {
+        let input = "\
+# Title
+
+Some *text*.
+
+## ATX *emphasis* `code` ##
+
+Setext 1
+========
+
+Setext
+2
+--------
+
+```
+# not a heading
+```
+
+### [Link](https://example.com) and [other](https://example.org)
+";
+        let parser = MarkdownParser::new(MarkdownParserOptions {
+            link_column: true,
+            ..Default::default()
+        });
+        let outline = parser.parse(input)?;
+        assert_eq!(
+            keys(&outline),
+            vec![
+                ("Title", 1),
+                ("ATX emphasis code", 2),
+                ("Setext 1", 1),
+                ("Setext 2", 2),
+                ("Link and other", 3),
+            ]
+        );
+        assert_eq!(outline.value_header, vec!["URL"]);
+        assert_eq!(outline.item[0].value, vec![""]);
+        assert_eq!(outline.item[4].value, vec!["https://example.com"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_lists() -> Result<()> {
+        let input = "\
+# Ignored heading
+
+- [ ] Plan
+  - [x] Scope
+  - Budget, see [sheet](budget.xlsx)
+    1. Q1
+    2. Q2
+* Build
+  continued
+
+  second paragraph
+";
+        let parser = MarkdownParser::new(MarkdownParserOptions {
+            structure: MarkdownStructure::Lists,
+            task_column: true,
+            link_column: true,
+            ..Default::default()
+        });
+        let outline = parser.parse(input)?;
+        assert_eq!(
+            keys(&outline),
+            vec![
+                ("Plan", 1),
+                ("Scope", 2),
+                ("Budget, see sheet", 2),
+                ("Q1", 3),
+                ("Q2", 3),
+                ("Build continued second paragraph", 1),
+            ]
+        );
+        assert_eq!(outline.value_header, vec!["Done", "URL"]);
+        assert_eq!(outline.item[0].value, vec!["false", ""]);
+        assert_eq!(outline.item[1].value, vec!["true", ""]);
+        assert_eq!(outline.item[2].value, vec!["", "budget.xlsx"]);
+        Ok(())
+    }
+}
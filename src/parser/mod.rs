@@ -3,16 +3,112 @@
 //! This module contains parsers that can convert various input formats
 //! into the internal outline structure.
 
+pub mod csv_adjacency;
+pub mod csv_table;
+pub mod data;
 pub mod dir_tree;
+pub mod error;
+pub mod freemind;
 pub mod html_list;
+pub mod json;
+pub mod markdown;
 pub mod mspdi;
 pub mod opml;
+pub mod org;
 pub mod simple_text;
+pub mod text_tree;
+pub mod xlsx;
+pub mod xmind;
 
-pub enum ParserOptions {
-    SimpleText(simple_text::SimpleTextParserOptions),
-    DirTree(dir_tree::DirTreeParserOptions),
-    HtmlList(html_list::HtmlListParserOptions),
-    Mspdi(mspdi::MspdiParserOptions),
-    Opml(opml::OpmlParserOptions),
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::{ParseError, ParseWarning};
+use anyhow::Result;
+use std::io::Read;
+use std::path::Path;
+
+/// A parser that converts an input into an `Outline`.
+///
+/// Implementors are registered by name in a [`crate::registry::Registry`] so that
+/// the CLI and library callers can select them with `--from-type`.
+pub trait Parser {
+    /// Parses the input located at `input_path` into an `Outline`.
+    ///
+    /// `None` means the input is read from stdin.
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline>;
+
+    /// Parses the input like [`Parser::parse_input`], also returning the warnings about
+    /// ambiguous lines of the input.
+    ///
+    /// The default implementation reports no warning.
+    fn parse_input_with_warnings(
+        &self,
+        input_path: Option<&Path>,
+    ) -> Result<(Outline, Vec<ParseWarning>)> {
+        Ok((self.parse_input(input_path)?, Vec::new()))
+    }
+}
+
+/// A parser that reads an input file incrementally, without holding the input or the
+/// resulting `Outline` in memory.
+///
+/// Used by the streaming mode, which reads the input twice: once to measure the outline
+/// and once to write it.
+pub trait StreamParser {
+    /// Parses the input file at `input_path`, passing each item to `on_item` in order.
+    ///
+    /// # Returns
+    ///
+    /// The key header and value header, as an `Outline` without items.
+    fn parse_stream(
+        &self,
+        input_path: &Path,
+        on_item: &mut dyn FnMut(OutlineItem) -> Result<()>,
+    ) -> Result<Outline>;
+
+    /// Parses the input file like [`StreamParser::parse_stream`], also returning the
+    /// warnings about ambiguous lines of the input.
+    ///
+    /// The default implementation reports no warning.
+    fn parse_stream_with_warnings(
+        &self,
+        input_path: &Path,
+        on_item: &mut dyn FnMut(OutlineItem) -> Result<()>,
+    ) -> Result<(Outline, Vec<ParseWarning>)> {
+        Ok((self.parse_stream(input_path, on_item)?, Vec::new()))
+    }
+}
+
+/// Reads the whole input at `input_path` into a string, or stdin if `input_path` is `None`.
+///
+/// Input that is not valid UTF-8 is reported as a `ParseError` pointing at the first invalid byte.
+pub fn read_input_to_string(input_path: Option<&Path>) -> Result<String> {
+    let bytes = match input_path {
+        Some(path) => std::fs::read(path)?,
+        None => {
+            let mut buf = Vec::new();
+            std::io::stdin().read_to_end(&mut buf)?;
+            buf
+        }
+    };
+    String::from_utf8(bytes).map_err(|e| {
+        let offset = e.utf8_error().valid_up_to();
+        let input = String::from_utf8_lossy(e.as_bytes());
+        ParseError::at_offset("input is not valid UTF-8", &input, offset)
+            .with_file(input_path)
+            .into()
+    })
+}
+
+/// Reads the input at `input_path` and parses it with `parse`.
+///
+/// A `ParseError` returned by `parse` is completed with the name of the input file.
+pub fn parse_input_with<T>(
+    input_path: Option<&Path>,
+    parse: impl FnOnce(&str) -> Result<T>,
+) -> Result<T> {
+    let input = read_input_to_string(input_path)?;
+    parse(&input).map_err(|e| match e.downcast::<ParseError>() {
+        Ok(parse_error) => parse_error.with_file(input_path).into(),
+        Err(e) => e,
+    })
 }
@@ -1,10 +1,14 @@
-use anyhow::anyhow;
 use anyhow::Result;
 use quick_xml::events::Event;
 use quick_xml::Reader;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
 
-use crate::outline::Outline;
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::{locate_in_file, locate_in_str, OffsetError};
+use crate::parser::{parse_input_with, Parser, StreamParser};
 
 #[derive(Debug, Clone)]
 pub struct MspdiParserOptions {
@@ -24,11 +28,30 @@ impl MspdiParser {
     }
 
     pub fn parse(&self, input: &str) -> Result<Outline> {
+        let mut outline = self.header();
+        self.read_items(Reader::from_str(input), &mut |item| {
+            outline.item.push(item);
+            Ok(())
+        })
+        .map_err(|e| locate_in_str(e, input))?;
+        Ok(outline)
+    }
+
+    fn header(&self) -> Outline {
         let mut outline = Outline::new();
         outline.key_header = self.options.key_header.clone();
         outline.value_header = self.options.value_header.clone();
+        outline
+    }
 
-        let mut reader = Reader::from_str(input);
+    /// Reads the `Task` elements with `reader`, passing each item to `on_item`.
+    ///
+    /// Malformed XML is reported as an `OffsetError`.
+    fn read_items<R: BufRead>(
+        &self,
+        mut reader: Reader<R>,
+        on_item: &mut dyn FnMut(OutlineItem) -> Result<()>,
+    ) -> Result<()> {
         reader.config_mut().trim_text(true);
 
         let mut buf = Vec::new();
@@ -36,13 +59,14 @@ impl MspdiParser {
         let mut current_task_values: HashMap<String, String> = HashMap::new();
 
         loop {
+            let event_start = reader.buffer_position() as usize;
             match reader.read_event_into(&mut buf) {
                 Err(e) => {
-                    return Err(anyhow!(
-                        "Error at position {}: {:?}",
-                        reader.buffer_position(),
-                        e
-                    ))
+                    return Err(OffsetError {
+                        message: e.to_string(),
+                        offset: reader.error_position() as usize,
+                    }
+                    .into())
                 }
                 Ok(Event::Eof) => break,
                 Ok(Event::Start(e)) => {
@@ -56,18 +80,22 @@ impl MspdiParser {
                     let tag_name = String::from_utf8_lossy(e.name().into_inner()).into_owned();
                     breadcrumb.pop();
                     if tag_name == "Task" {
-                        self.generate_outline_item(&mut outline, &current_task_values);
+                        on_item(self.generate_outline_item(&current_task_values))?;
                     }
                 }
-                Ok(Event::Text(e)) => {
-                    if breadcrumb.contains(&"Task".to_string()) {
-                        let text = e.decode()?.into_owned();
-                        if let Some(last_tag) = breadcrumb.last() {
-                            current_task_values
-                                .entry(last_tag.clone())
-                                .or_default()
-                                .push_str(&text);
-                        }
+                Ok(Event::Text(e)) if breadcrumb.contains(&"Task".to_string()) => {
+                    let text = e
+                        .decode()
+                        .map_err(|e| OffsetError {
+                            message: e.to_string(),
+                            offset: event_start,
+                        })?
+                        .into_owned();
+                    if let Some(last_tag) = breadcrumb.last() {
+                        current_task_values
+                            .entry(last_tag.clone())
+                            .or_default()
+                            .push_str(&text);
                     }
                 }
                 _ => (),
@@ -75,30 +103,51 @@ impl MspdiParser {
             buf.clear();
         }
 
-        Ok(outline)
+        Ok(())
     }
 
-    fn generate_outline_item(&self, outline: &mut Outline, values: &HashMap<String, String>) {
+    fn generate_outline_item(&self, values: &HashMap<String, String>) -> OutlineItem {
+        let value_header = &self.options.value_header;
         let mut text = String::new();
         let mut level = 1;
-        let mut item_values: Vec<String> = vec!["".to_string(); outline.value_header.len()];
+        let mut item_values: Vec<String> = vec!["".to_string(); value_header.len()];
 
         for (key, val) in values.iter() {
             if key == "Name" {
                 text = val.clone();
             } else if key == "OutlineLevel" {
                 level = val.parse::<u32>().unwrap_or(1);
-            } else if let Some(index) = outline.value_header.iter().position(|h| h == key) {
+            } else if let Some(index) = value_header.iter().position(|h| h == key) {
                 item_values[index] = val.clone();
             }
         }
-        outline.add_item(&text, level, item_values);
+        OutlineItem::new(&text, level, item_values)
+    }
+}
+
+impl Parser for MspdiParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+impl StreamParser for MspdiParser {
+    fn parse_stream(
+        &self,
+        input_path: &Path,
+        on_item: &mut dyn FnMut(OutlineItem) -> Result<()>,
+    ) -> Result<Outline> {
+        let reader = Reader::from_reader(BufReader::new(File::open(input_path)?));
+        self.read_items(reader, on_item)
+            .map_err(|e| locate_in_file(e, input_path))?;
+        Ok(self.header())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::error::ParseError;
 
     #[test]
     fn test_simple_mspdi() {
@@ -178,4 +227,49 @@ mod tests {
         assert_eq!(outline.item[1].value[0], "2025-01-06");
         assert_eq!(outline.item[1].value[1], "2025-01-10");
     }
+
+    #[test]
+    fn test_mspdi_error_position() {
+        let xml_input = "<Project>\n  <Tasks>\n    <Task><Name>A</Task>\n  </Tasks>\n</Project>\n";
+        let parser = MspdiParser::new(MspdiParserOptions {
+            key_header: Vec::new(),
+            value_header: Vec::new(),
+        });
+        let err = parser.parse(xml_input).unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err.location.line, 3);
+        assert_eq!(err.snippet, "    <Task><Name>A</Task>");
+    }
+
+    #[test]
+    fn test_mspdi_parse_stream() -> Result<()> {
+        use std::io::Write;
+
+        let xml_input = "<Project><Tasks>\n<Task><Name>A</Name><OutlineLevel>1</OutlineLevel><Start>s</Start></Task>\n<Task><Name>B</Name><OutlineLevel>2</OutlineLevel></Task>\n</Tasks></Project>\n";
+        let parser = MspdiParser::new(MspdiParserOptions {
+            key_header: Vec::new(),
+            value_header: vec!["Start".to_string()],
+        });
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(xml_input.as_bytes())?;
+
+        let mut items = Vec::new();
+        let mut header = parser.parse_stream(file.path(), &mut |item| {
+            items.push(item);
+            Ok(())
+        })?;
+        header.item = items;
+        assert_eq!(header, parser.parse(xml_input)?);
+        assert_eq!(header.item.len(), 2);
+
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(b"<Project>\n  <Tasks>\n    <Task><Name>A</Task>\n")?;
+        let err = parser
+            .parse_stream(file.path(), &mut |_| Ok(()))
+            .unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err.location.file.as_deref(), Some(file.path()));
+        assert_eq!(err.location.line, 3);
+        Ok(())
+    }
 }
@@ -2,8 +2,13 @@ use anyhow::Result;
 use quick_xml::events::attributes::Attributes;
 use quick_xml::events::Event;
 use quick_xml::Reader;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
 
-use crate::outline::Outline;
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::{locate_in_file, locate_in_str, OffsetError};
+use crate::parser::{parse_input_with, Parser, StreamParser};
 
 #[derive(Debug, Clone)]
 pub struct OpmlParserOptions {
@@ -23,33 +28,63 @@ impl OpmlParser {
     }
 
     pub fn parse(&self, input: &str) -> Result<Outline> {
+        let mut outline = self.header();
+        self.read_items(Reader::from_str(input), &mut |item| {
+            outline.item.push(item);
+            Ok(())
+        })
+        .map_err(|e| locate_in_str(e, input))?;
+        Ok(outline)
+    }
+
+    fn header(&self) -> Outline {
         let mut outline = Outline::new();
         outline.key_header = self.options.key_header.clone();
         outline.value_header = self.options.value_header.clone();
+        outline
+    }
 
-        let mut reader = Reader::from_str(input);
+    /// Reads the `outline` elements with `reader`, passing each item to `on_item`.
+    ///
+    /// Malformed XML is reported as an `OffsetError`.
+    fn read_items<R: BufRead>(
+        &self,
+        mut reader: Reader<R>,
+        on_item: &mut dyn FnMut(OutlineItem) -> Result<()>,
+    ) -> Result<()> {
         reader.config_mut().trim_text(true);
 
         let mut buf = Vec::new();
         let mut outline_level = 0;
 
         loop {
+            let event_start = reader.buffer_position() as usize;
+            let attribute_error = |e: anyhow::Error| OffsetError {
+                message: e.to_string(),
+                offset: event_start,
+            };
             match reader.read_event_into(&mut buf) {
                 Err(e) => {
-                    return Err(anyhow::anyhow!(
-                        "Error at position {}: {:?}",
-                        reader.buffer_position(),
-                        e
-                    ))
+                    return Err(OffsetError {
+                        message: e.to_string(),
+                        offset: reader.error_position() as usize,
+                    }
+                    .into())
                 }
                 Ok(Event::Eof) => break,
                 Ok(Event::Start(ref e)) if e.name().as_ref() == b"outline" => {
                     // Determine the current level based on the stack
                     outline_level += 1;
-                    self.generate_outline_item(&mut outline, &e.attributes(), outline_level)?;
+                    on_item(
+                        self.generate_outline_item(&e.attributes(), outline_level)
+                            .map_err(attribute_error)?,
+                    )?;
                 }
                 Ok(Event::Empty(ref e)) if e.name().as_ref() == b"outline" => {
-                    self.generate_outline_item(&mut outline, &e.attributes(), outline_level + 1)?;
+                    on_item(
+                        self.generate_outline_item(&e.attributes(), outline_level + 1)
+                            .map_err(attribute_error)?,
+                    )?;
                 }
                 Ok(Event::End(ref e)) if e.name().as_ref() == b"outline" => {
                     outline_level -= 1;
@@ -58,17 +93,13 @@ impl OpmlParser {
             }
             buf.clear();
         }
-        Ok(outline)
+        Ok(())
     }
 
-    fn generate_outline_item(
-        &self,
-        outline: &mut Outline,
-        attributes: &Attributes,
-        level: u32,
-    ) -> Result<()> {
+    fn generate_outline_item(&self, attributes: &Attributes, level: u32) -> Result<OutlineItem> {
+        let value_header = &self.options.value_header;
         let mut text = String::new();
-        let mut item_values = vec![String::new(); outline.value_header.len()];
+        let mut item_values = vec![String::new(); value_header.len()];
 
         for attr in attributes.clone() {
             let attr = attr?;
@@ -77,20 +108,38 @@ impl OpmlParser {
 
             if key == "text" {
                 text = value.trim().to_string();
-            } else if let Some(value_pos) = outline.value_header.iter().position(|x| x == &key) {
+            } else if let Some(value_pos) = value_header.iter().position(|x| x == &key) {
                 item_values[value_pos] = value.to_string();
             }
         }
 
-        outline.add_item(&text, level, item_values);
+        Ok(OutlineItem::new(&text, level, item_values))
+    }
+}
 
-        Ok(())
+impl Parser for OpmlParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+impl StreamParser for OpmlParser {
+    fn parse_stream(
+        &self,
+        input_path: &Path,
+        on_item: &mut dyn FnMut(OutlineItem) -> Result<()>,
+    ) -> Result<Outline> {
+        let reader = Reader::from_reader(BufReader::new(File::open(input_path)?));
+        self.read_items(reader, on_item)
+            .map_err(|e| locate_in_file(e, input_path))?;
+        Ok(self.header())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::error::ParseError;
 
     #[test]
     fn test_simple_opml() {
@@ -159,4 +208,64 @@ mod tests {
         assert_eq!(outline.value_header[0], "due");
         assert_eq!(outline.value_header[1], "priority");
     }
+
+    #[test]
+    fn test_opml_error_position() {
+        let xml_input = "<opml>\n  <body>\n    <outline text=\"a\">\n  </body>\n</opml>\n";
+        let parser = OpmlParser::new(OpmlParserOptions {
+            key_header: Vec::new(),
+            value_header: Vec::new(),
+        });
+        let err = parser.parse(xml_input).unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!((err.location.line, err.location.column), (4, 3));
+        assert_eq!(err.snippet, "  </body>");
+
+        let xml_input = "<opml>\n  <body>\n    <outline text=\"&bogus;\"/>\n  </body>\n</opml>\n";
+        let err = parser.parse(xml_input).unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!((err.location.line, err.location.column), (3, 5));
+    }
+
+    #[test]
+    fn test_opml_parse_stream() -> Result<()> {
+        use std::io::Write;
+
+        let xml_input = "<opml>\n  <body>\n    <outline text=\"a\" due=\"x\">\n      <outline text=\"b\"/>\n    </outline>\n  </body>\n</opml>\n";
+        let parser = OpmlParser::new(OpmlParserOptions {
+            key_header: vec!["Key".to_string()],
+            value_header: vec!["due".to_string()],
+        });
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(xml_input.as_bytes())?;
+
+        let mut items = Vec::new();
+        let mut header = parser.parse_stream(file.path(), &mut |item| {
+            items.push(item);
+            Ok(())
+        })?;
+        assert!(header.item.is_empty());
+        header.item = items;
+        assert_eq!(header, parser.parse(xml_input)?);
+
+        // Errors point at the same position as when parsing a string
+        let xml_input = "<opml>\n  <body>\n    <outline text=\"&bogus;\"/>\n  </body>\n</opml>\n";
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(xml_input.as_bytes())?;
+        let err = parser
+            .parse_stream(file.path(), &mut |_| Ok(()))
+            .unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(
+            err,
+            &parser
+                .parse(xml_input)
+                .unwrap_err()
+                .downcast_ref::<ParseError>()
+                .unwrap()
+                .clone()
+                .with_file(Some(file.path()))
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,395 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::ParseError;
+use crate::parser::{parse_input_with, Parser};
+use anyhow::{bail, Result};
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// The value columns taken from the heading and planning lines, named after the Org special
+/// properties, in their default order.
+const SPECIAL_COLUMNS: [&str; 6] = [
+    "TODO",
+    "PRIORITY",
+    "TAGS",
+    "SCHEDULED",
+    "DEADLINE",
+    "CLOSED",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct OrgParserOptions {
+    pub key_header: Vec<String>,
+    /// The value columns to keep: the special properties `TODO`, `PRIORITY`, `TAGS`,
+    /// `SCHEDULED`, `DEADLINE` and `CLOSED`, or property drawer names (case-insensitive).
+    /// Defaults to the special properties used in the document, followed by every drawer
+    /// property in order of first appearance.
+    pub value_header: Vec<String>,
+}
+
+/// A parser for Org documents, where the number of `*` of a heading is its level.
+///
+/// Text before the first heading and the body of each heading are ignored, except for the
+/// `#+TODO:` lines defining TODO keywords.
+pub struct OrgParser {
+    options: OrgParserOptions,
+}
+
+/// A heading of the document.
+#[derive(Default)]
+struct Entry {
+    level: u32,
+    key: String,
+    /// The values of the special properties, in the order of `SPECIAL_COLUMNS`.
+    special: [String; 6],
+    properties: Vec<(String, String)>,
+}
+
+impl Entry {
+    fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^(\*+)(?:[ \t]+(.*?))?[ \t]*$").unwrap())
+}
+
+fn tags_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:^|[ \t]+):((?:[\w@#%]+:)+)$").unwrap())
+}
+
+fn planning_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(SCHEDULED|DEADLINE|CLOSED):[ \t]*([<\[][^>\]]*[>\]])").unwrap())
+}
+
+fn timestamp_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^[<\[](\d{4}-\d{2}-\d{2})(?:[ \t]+[^\s\d>\]]+)?(?:[ \t]+(\d{1,2}):(\d{2}))?")
+            .unwrap()
+    })
+}
+
+fn property_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^[ \t]*:([^\s:]+?)(\+)?:(?:[ \t]+(.*?))?[ \t]*$").unwrap())
+}
+
+fn todo_setting_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)^#\+(?:SEQ_|TYP_)?TODO:(.*)$").unwrap())
+}
+
+/// Converts an Org timestamp such as `<2025-01-31 Fri 9:00 +1w>` to ISO 8601
+/// (`2025-01-31T09:00`), dropping the day name, repeater and delay.
+///
+/// Timestamps that cannot be read are kept as they are.
+fn iso_timestamp(timestamp: &str) -> String {
+    match timestamp_regex().captures(timestamp) {
+        Some(captures) => match (captures.get(2), captures.get(3)) {
+            (Some(hour), Some(minute)) => {
+                format!("{}T{:0>2}:{}", &captures[1], hour.as_str(), minute.as_str())
+            }
+            _ => captures[1].to_string(),
+        },
+        None => timestamp.to_string(),
+    }
+}
+
+impl OrgParser {
+    pub fn new(options: OrgParserOptions) -> Self {
+        OrgParser { options }
+    }
+
+    /// Returns the TODO keywords defined by the `#+TODO:` lines of `input`, or `TODO` and
+    /// `DONE` if there are none.
+    fn todo_keywords(input: &str) -> Vec<String> {
+        let mut keywords: Vec<String> = input
+            .lines()
+            .filter_map(|line| todo_setting_regex().captures(line.trim()))
+            .flat_map(|captures| {
+                captures[1]
+                    .split_whitespace()
+                    .filter(|word| *word != "|")
+                    // Drop the fast-access and logging settings, e.g. `WAIT(w@/!)`
+                    .map(|word| word.split('(').next().unwrap_or(word).to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        if keywords.is_empty() {
+            keywords = vec!["TODO".to_string(), "DONE".to_string()];
+        }
+        keywords
+    }
+
+    /// Reads a heading title into the key, TODO keyword, priority and tags of `entry`.
+    fn read_title(entry: &mut Entry, title: &str, keywords: &[String]) {
+        let mut title = title;
+        if let Some((word, rest)) = title
+            .split_once([' ', '\t'])
+            .or(Some((title, "")))
+            .filter(|(word, _)| keywords.iter().any(|k| k == word))
+        {
+            entry.special[0] = word.to_string();
+            title = rest.trim_start();
+        }
+        if let Some(rest) = title.strip_prefix("[#") {
+            if let Some((priority, rest)) = rest.split_once(']') {
+                entry.special[1] = priority.to_string();
+                title = rest.trim_start();
+            }
+        }
+        if let Some(captures) = tags_regex().captures(title) {
+            entry.special[2] = captures[1].trim_end_matches(':').to_string();
+            title = &title[..captures.get(0).expect("the whole match").start()];
+        }
+        entry.key = title.trim().to_string();
+    }
+
+    /// Parses an Org document into an `Outline`.
+    ///
+    /// The key of an item is its heading title without the TODO keyword, priority cookie
+    /// and tags. Tags are joined with `:`, and timestamps are converted to ISO 8601.
+    pub fn parse(&self, input: &str) -> Result<Outline> {
+        let keywords = Self::todo_keywords(input);
+        let mut entries: Vec<Entry> = Vec::new();
+        // The 1-based line of an open `:PROPERTIES:` drawer
+        let mut drawer: Option<usize> = None;
+        // True while the planning line or property drawer of the last heading may follow
+        let mut in_heading = false;
+        for (index, line) in input.lines().enumerate() {
+            if let Some(start) = drawer {
+                if line.trim().eq_ignore_ascii_case(":END:") {
+                    drawer = None;
+                    in_heading = false;
+                } else if heading_regex().is_match(line) {
+                    bail!(ParseError::at_line(
+                        "unterminated property drawer",
+                        input,
+                        start,
+                        1
+                    ));
+                } else if let Some(captures) = property_regex().captures(line) {
+                    let entry = entries.last_mut().expect("drawers follow a heading");
+                    let name = captures[1].to_string();
+                    let value = captures.get(3).map_or("", |m| m.as_str());
+                    match entry
+                        .properties
+                        .iter_mut()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+                    {
+                        // `:NAME+:` appends to the value of `:NAME:`
+                        Some((_, previous)) if captures.get(2).is_some() => {
+                            if !previous.is_empty() && !value.is_empty() {
+                                previous.push(' ');
+                            }
+                            previous.push_str(value);
+                        }
+                        Some((_, previous)) => *previous = value.to_string(),
+                        None => entry.properties.push((name, value.to_string())),
+                    }
+                }
+                continue;
+            }
+
+            if let Some(captures) = heading_regex().captures(line) {
+                let mut entry = Entry {
+                    level: captures[1].len() as u32,
+                    ..Default::default()
+                };
+                Self::read_title(
+                    &mut entry,
+                    captures.get(2).map_or("", |m| m.as_str()),
+                    &keywords,
+                );
+                entries.push(entry);
+                in_heading = true;
+            } else if in_heading && line.trim().eq_ignore_ascii_case(":PROPERTIES:") {
+                drawer = Some(index + 1);
+            } else if in_heading && planning_regex().is_match(line) {
+                let entry = entries.last_mut().expect("planning lines follow a heading");
+                for captures in planning_regex().captures_iter(line) {
+                    let column = SPECIAL_COLUMNS
+                        .iter()
+                        .position(|c| *c == &captures[1])
+                        .expect("planning keywords are special columns");
+                    entry.special[column] = iso_timestamp(&captures[2]);
+                }
+            } else {
+                in_heading = false;
+            }
+        }
+        if let Some(start) = drawer {
+            bail!(ParseError::at_line(
+                "unterminated property drawer",
+                input,
+                start,
+                1
+            ));
+        }
+
+        let mut outline = Outline::new();
+        outline.key_header = self.options.key_header.clone();
+        outline.value_header = self.options.value_header.clone();
+        if outline.value_header.is_empty() {
+            for (column, name) in SPECIAL_COLUMNS.iter().enumerate() {
+                if entries.iter().any(|e| !e.special[column].is_empty()) {
+                    outline.value_header.push(name.to_string());
+                }
+            }
+            for entry in &entries {
+                for (name, _) in &entry.properties {
+                    if !outline
+                        .value_header
+                        .iter()
+                        .any(|h| h.eq_ignore_ascii_case(name))
+                    {
+                        outline.value_header.push(name.clone());
+                    }
+                }
+            }
+        }
+
+        for entry in &entries {
+            let value = outline
+                .value_header
+                .iter()
+                .map(|name| {
+                    match SPECIAL_COLUMNS
+                        .iter()
+                        .position(|c| c.eq_ignore_ascii_case(name.trim()))
+                    {
+                        Some(column) => entry.special[column].clone(),
+                        None => entry.property(name.trim()).unwrap_or("").to_string(),
+                    }
+                })
+                .collect();
+            outline
+                .item
+                .push(OutlineItem::new(&entry.key, entry.level, value));
+        }
+        Ok(outline)
+    }
+}
+
+impl Parser for OrgParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "\
+#+TITLE: Plan
+#+TODO: TODO WAIT(w@) | DONE(d!)
+
+Notes before the first heading.
+* TODO [#A] Launch                                             :work:urgent:
+  DEADLINE: <2025-03-31 Mon> SCHEDULED: <2025-03-01 Sat 9:00 +1w>
+  :PROPERTIES:
+  :OWNER:    Ann
+  :EFFORT:   3d
+  :END:
+Body text, with a :fake: drawer.
+** WAIT Review
+   :PROPERTIES:
+   :owner: Bob
+   :owner+: and Eve
+   :END:
+** Retrospective :meeting:
+*** DONE Book a room
+    CLOSED: [2025-04-02 Wed 17:30]
+* Someday
+";
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let outline = OrgParser::new(OrgParserOptions::default()).parse(INPUT)?;
+        let mut expected = Outline::new();
+        expected.value_header = [
+            "TODO",
+            "PRIORITY",
+            "TAGS",
+            "SCHEDULED",
+            "DEADLINE",
+            "CLOSED",
+        ]
+        .iter()
+        .chain(&["OWNER", "EFFORT"])
+        .map(|s| s.to_string())
+        .collect();
+        let values = |values: [&str; 8]| values.iter().map(|s| s.to_string()).collect();
+        expected.add_item(
+            "Launch",
+            1,
+            values([
+                "TODO",
+                "A",
+                "work:urgent",
+                "2025-03-01T09:00",
+                "2025-03-31",
+                "",
+                "Ann",
+                "3d",
+            ]),
+        );
+        expected.add_item(
+            "Review",
+            2,
+            values(["WAIT", "", "", "", "", "", "Bob and Eve", ""]),
+        );
+        expected.add_item(
+            "Retrospective",
+            2,
+            values(["", "", "meeting", "", "", "", "", ""]),
+        );
+        expected.add_item(
+            "Book a room",
+            3,
+            values(["DONE", "", "", "", "", "2025-04-02T17:30", "", ""]),
+        );
+        expected.add_item("Someday", 1, values([""; 8]));
+        assert_eq!(outline, expected);
+
+        // The value header selects and orders the columns
+        let parser = OrgParser::new(OrgParserOptions {
+            key_header: vec!["Task".to_string()],
+            value_header: vec!["Owner".to_string(), "todo".to_string(), "Cost".to_string()],
+        });
+        let outline = parser.parse(INPUT)?;
+        assert_eq!(outline.key_header, vec!["Task"]);
+        assert_eq!(outline.item[0].value, vec!["Ann", "TODO", ""]);
+        assert_eq!(outline.item[1].value, vec!["Bob and Eve", "WAIT", ""]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_plain_headings() -> Result<()> {
+        let outline = OrgParser::new(OrgParserOptions::default())
+            .parse("* WAIT here\n**bold** is not a heading\n** TODO\n")?;
+        assert_eq!(outline.value_header, vec!["TODO"]);
+        assert_eq!(outline.item.len(), 2);
+        // Without `#+TODO:` lines, only TODO and DONE are keywords
+        assert_eq!(outline.item[0].key, "WAIT here");
+        assert_eq!(outline.item[1].key, "");
+        assert_eq!(outline.item[1].value, vec!["TODO"]);
+
+        let err = OrgParser::new(OrgParserOptions::default())
+            .parse("* A\n:PROPERTIES:\n:X: 1\n* B\n")
+            .unwrap_err()
+            .downcast::<ParseError>()?;
+        assert_eq!(err.message, "unterminated property drawer");
+        assert_eq!(err.location.line, 2);
+        Ok(())
+    }
+}
@@ -1,11 +1,22 @@
-use crate::outline::Outline;
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::error::{ParseError, ParseWarning};
+use crate::parser::{parse_input_with, Parser, StreamParser};
 use anyhow::Result;
 use regex::Regex;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
 
 /// Options for configuring the `SimpleTextParser`.
 #[derive(Debug, Clone)]
 pub struct SimpleTextParserOptions {
     pub indent: String,
+    /// If true, ignore `indent` and nest lines by the width of their leading whitespace, as
+    /// Python does with blocks.
+    pub auto_indent: bool,
+    /// The number of columns between tab stops, used to measure leading tabs with
+    /// `auto_indent`.
+    pub tab_width: usize,
     pub delimiter: Option<String>,
     pub preserve_empty_line: bool,
     pub key_header: Vec<String>,
@@ -17,6 +28,8 @@ impl Default for SimpleTextParserOptions {
     ///
     /// Default values:
     /// - `indent`: "\t" (tab)
+    /// - `auto_indent`: false
+    /// - `tab_width`: 4
     /// - `delimiter`: None
     /// - `preserve_empty_line`: false
     /// - `key_header`: None
@@ -24,6 +37,8 @@ impl Default for SimpleTextParserOptions {
     fn default() -> Self {
         SimpleTextParserOptions {
             indent: "\t".to_string(),
+            auto_indent: false,
+            tab_width: 4,
             delimiter: None,
             preserve_empty_line: false,
             key_header: Vec::new(),
@@ -57,55 +72,240 @@ impl SimpleTextParser {
     ///
     /// A `Result` which is `Ok(Outline)` on successful parsing, or an `anyhow::Error`
     /// if an error occurs (e.g., invalid regex).
+    ///
+    /// With `auto_indent`, lines with ambiguous indentation are read without notice; use
+    /// [`SimpleTextParser::parse_with_warnings`] to get them.
     pub fn parse(&self, input: &str) -> Result<Outline> {
+        Ok(self.parse_with_warnings(input)?.0)
+    }
+
+    /// Parses the input string like [`SimpleTextParser::parse`], also returning a warning
+    /// for each line with ambiguous indentation.
+    pub fn parse_with_warnings(&self, input: &str) -> Result<(Outline, Vec<ParseWarning>)> {
+        let mut line_parser = LineParser::new(&self.option)?;
+        let mut outline = self.header();
+
+        for (index, line) in input.lines().enumerate() {
+            if let Some(item) = line_parser.parse_line(line, index + 1) {
+                outline.item.push(item);
+            }
+        }
+
+        Ok((outline, line_parser.warnings))
+    }
+
+    fn header(&self) -> Outline {
+        Outline {
+            key_header: self.option.key_header.clone(),
+            value_header: self.option.value_header.clone(),
+            ..Outline::default()
+        }
+    }
+}
+
+/// Converts single lines of simple text into `OutlineItem`s.
+struct LineParser<'a> {
+    option: &'a SimpleTextParserOptions,
+    indent_regexp: Regex,
+    delimiter_regexp: Option<Regex>,
+    /// With `auto_indent`, the widths of the indentation of the current line and its
+    /// ancestors.
+    widths: Vec<usize>,
+    /// With `auto_indent`, the first increase of indentation in the input. It is advisory:
+    /// levels follow `widths` only, and the unit is used to warn about other steps.
+    unit: Option<usize>,
+    /// The lines with ambiguous indentation.
+    warnings: Vec<ParseWarning>,
+}
+
+impl<'a> LineParser<'a> {
+    fn new(option: &'a SimpleTextParserOptions) -> Result<Self> {
         let indent_regexp = Regex::new(&format!(
             "^(?P<indents>({})+)",
-            regex::escape(&self.option.indent)
+            regex::escape(&option.indent)
         ))?;
-        let delimiter_regexp = if let Some(d) = &self.option.delimiter {
+        let delimiter_regexp = if let Some(d) = &option.delimiter {
             Some(Regex::new(&regex::escape(d))?)
         } else {
             None
         };
+        Ok(LineParser {
+            option,
+            indent_regexp,
+            delimiter_regexp,
+            widths: Vec::new(),
+            unit: None,
+            warnings: Vec::new(),
+        })
+    }
 
-        let key_header = self.option.key_header.clone();
-        let value_header = self.option.value_header.clone();
+    /// Returns the item for `line`, the `line_number`-th line of the input, or `None` if
+    /// the line is skipped.
+    fn parse_line(&mut self, line: &str, line_number: usize) -> Option<OutlineItem> {
+        let trimmed_line = line.trim();
+        if trimmed_line.is_empty() && !self.option.preserve_empty_line {
+            return None;
+        }
 
-        let mut outline = Outline {
-            key_header,
-            value_header,
-            ..Outline::default()
-        };
+        let mut level = 1;
+        let mut current_line = line.to_string();
 
-        for line in input.lines() {
-            let trimmed_line = line.trim();
-            if trimmed_line.is_empty() && !self.option.preserve_empty_line {
-                continue;
+        if self.option.auto_indent {
+            if !trimmed_line.is_empty() {
+                let content = line.trim_start_matches([' ', '\t']);
+                let indent = &line[..line.len() - content.len()];
+                level = self.auto_level(indent, line, line_number);
+                current_line = content.to_string();
             }
+        } else if !self.option.indent.is_empty() {
+            if let Some(captures) = self.indent_regexp.captures(&current_line) {
+                let indents = captures.name("indents").unwrap().as_str();
+                level = 1 + (indents.len() / self.option.indent.len()) as u32;
+                current_line = self.indent_regexp.replace(&current_line, "").to_string();
+            }
+        }
+
+        let (key, values) = if let Some(d_regexp) = &self.delimiter_regexp {
+            let mut parts = d_regexp.split(&current_line);
+            let key = parts.next().unwrap_or("").trim().to_string();
+            let values = parts.map(|s| s.trim().to_string()).collect();
+            (key, values)
+        } else {
+            (current_line.trim().to_string(), vec![])
+        };
+        Some(OutlineItem::new(&key, level, values))
+    }
 
-            let mut level = 1;
-            let mut current_line = line.to_string();
+    /// Returns the level of a line indented with `indent`, nesting it under the closest
+    /// previous line with a narrower indentation.
+    ///
+    /// The level only depends on the indentation of the enclosing lines, whatever the width
+    /// of the step. A line indented by another step than the advisory indent unit, dedented
+    /// to a width matching no enclosing line, or indented with both tabs and spaces is
+    /// reported as a warning.
+    fn auto_level(&mut self, indent: &str, line: &str, line_number: usize) -> u32 {
+        let tab_width = self.option.tab_width.max(1);
+        let width = indent.chars().fold(0, |width, c| match c {
+            '\t' => (width / tab_width + 1) * tab_width,
+            _ => width + 1,
+        });
+        let mut messages = Vec::new();
+        if indent.contains(' ') && indent.contains('\t') {
+            messages.push(format!(
+                "indentation mixes tabs and spaces, read with a tab width of {}",
+                tab_width
+            ));
+        }
 
-            if !self.option.indent.is_empty() {
-                if let Some(captures) = indent_regexp.captures(&current_line) {
-                    let indents = captures.name("indents").unwrap().as_str();
-                    level = 1 + (indents.len() / self.option.indent.len()) as u32;
-                    current_line = indent_regexp.replace(&current_line, "").to_string();
+        match self.widths.last() {
+            Some(&top) if width > top => {
+                let step = width - top;
+                match self.unit {
+                    None => self.unit = Some(step),
+                    Some(unit) if step != unit => {
+                        messages.push(format!(
+                            "indented by {} columns where the first indented line used {}, still read as level {}",
+                            step,
+                            unit,
+                            self.widths.len() + 1
+                        ));
+                    }
+                    Some(_) => {}
+                }
+                self.widths.push(width);
+            }
+            Some(&top) if width == top => {}
+            None => self.widths.push(width),
+            Some(_) => {
+                while self.widths.last().is_some_and(|&w| w > width) {
+                    self.widths.pop();
                 }
+                if self.widths.last() != Some(&width) {
+                    self.widths.push(width);
+                    messages.push(format!(
+                        "dedented to {} columns, matching no enclosing line, read as level {}",
+                        width,
+                        self.widths.len()
+                    ));
+                }
+            }
+        }
+
+        let column = indent.chars().count() + 1;
+        for message in messages {
+            self.warnings
+                .push(ParseWarning::at_line(message, line, line_number, column));
+        }
+        self.widths.len() as u32
+    }
+}
+
+impl Parser for SimpleTextParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+
+    fn parse_input_with_warnings(
+        &self,
+        input_path: Option<&Path>,
+    ) -> Result<(Outline, Vec<ParseWarning>)> {
+        let (outline, warnings) =
+            parse_input_with(input_path, |input| self.parse_with_warnings(input))?;
+        let warnings = warnings
+            .into_iter()
+            .map(|warning| warning.with_file(input_path))
+            .collect();
+        Ok((outline, warnings))
+    }
+}
+
+impl StreamParser for SimpleTextParser {
+    fn parse_stream(
+        &self,
+        input_path: &Path,
+        on_item: &mut dyn FnMut(OutlineItem) -> Result<()>,
+    ) -> Result<Outline> {
+        Ok(self.parse_stream_with_warnings(input_path, on_item)?.0)
+    }
+
+    fn parse_stream_with_warnings(
+        &self,
+        input_path: &Path,
+        on_item: &mut dyn FnMut(OutlineItem) -> Result<()>,
+    ) -> Result<(Outline, Vec<ParseWarning>)> {
+        let mut line_parser = LineParser::new(&self.option)?;
+        let mut reader = BufReader::new(File::open(input_path)?);
+        let mut buf = Vec::new();
+        let mut line_number = 0;
+
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
             }
+            line_number += 1;
+            let line = std::str::from_utf8(&buf).map_err(|e| {
+                let line = String::from_utf8_lossy(&buf);
+                let mut err =
+                    ParseError::at_offset("input is not valid UTF-8", &line, e.valid_up_to());
+                err.location.line = line_number;
+                err.with_file(Some(input_path))
+            })?;
+            // Same line ending handling as `str::lines`
+            let line = line.strip_suffix('\n').unwrap_or(line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
 
-            let (key, values) = if let Some(d_regexp) = &delimiter_regexp {
-                let mut parts = d_regexp.split(&current_line);
-                let key = parts.next().unwrap_or("").trim().to_string();
-                let values = parts.map(|s| s.trim().to_string()).collect();
-                (key, values)
-            } else {
-                (current_line.trim().to_string(), vec![])
-            };
-            outline.add_item(&key, level, values);
+            if let Some(item) = line_parser.parse_line(line, line_number) {
+                on_item(item)?;
+            }
         }
 
-        Ok(outline)
+        let warnings = line_parser
+            .warnings
+            .into_iter()
+            .map(|warning| warning.with_file(Some(input_path)))
+            .collect();
+        Ok((self.header(), warnings))
     }
 }
 
@@ -133,6 +333,8 @@ mod tests {
     fn test_simple_text_parser_options_default() {
         let options = SimpleTextParserOptions::default();
         assert_eq!(options.indent, "\t");
+        assert!(!options.auto_indent);
+        assert_eq!(options.tab_width, 4);
         assert_eq!(options.delimiter, None);
 
         assert!(!options.preserve_empty_line);
@@ -225,6 +427,92 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_simple_text_parser_auto_indent() -> Result<(), anyhow::Error> {
+        let input = "Root, r\n  a, 1\n    a1\n  b\n\tb1\n      deep\n   odd\n\nOther\n        far\n  \tmix\n";
+        let parser = SimpleTextParser::new(SimpleTextParserOptions {
+            auto_indent: true,
+            delimiter: Some(",".to_string()),
+            ..Default::default()
+        });
+        let (outline, warnings) = parser.parse_with_warnings(input)?;
+        let levels: Vec<(&str, u32)> = outline
+            .item
+            .iter()
+            .map(|item| (item.key.as_str(), item.level))
+            .collect();
+        assert_eq!(
+            levels,
+            vec![
+                ("Root", 1),
+                ("a", 2),
+                ("a1", 3),
+                ("b", 2),
+                ("b1", 3),
+                ("deep", 4),
+                ("odd", 3),
+                ("Other", 1),
+                ("far", 2),
+                ("mix", 2),
+            ]
+        );
+        assert_eq!(outline.item[1].value, vec!["1"]);
+
+        let warnings: Vec<(usize, usize, &str)> = warnings
+            .iter()
+            .map(|w| (w.location.line, w.location.column, w.message.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    7,
+                    4,
+                    "dedented to 3 columns, matching no enclosing line, read as level 3"
+                ),
+                (
+                    10,
+                    9,
+                    "indented by 8 columns where the first indented line used 2, still read as level 2"
+                ),
+                (
+                    11,
+                    4,
+                    "indentation mixes tabs and spaces, read with a tab width of 4"
+                ),
+                (
+                    11,
+                    4,
+                    "dedented to 4 columns, matching no enclosing line, read as level 2"
+                ),
+            ]
+        );
+
+        // Reading a file, each time the same parser is used
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(input.as_bytes())?;
+        for _ in 0..2 {
+            let (_, file_warnings) = parser.parse_input_with_warnings(Some(file.path()))?;
+            assert_eq!(file_warnings.len(), 4);
+            assert_eq!(file_warnings[0].location.file.as_deref(), Some(file.path()));
+            let (_, stream_warnings) =
+                parser.parse_stream_with_warnings(file.path(), &mut |_| Ok(()))?;
+            assert_eq!(stream_warnings, file_warnings);
+        }
+
+        // With a tab width of 2, a tab and two spaces are the same indentation
+        let parser = SimpleTextParser::new(SimpleTextParserOptions {
+            auto_indent: true,
+            tab_width: 2,
+            ..Default::default()
+        });
+        let (outline, _) = parser.parse_with_warnings("b\n\tb1\n  b2\n")?;
+        assert_eq!(outline.item[1].level, 2);
+        assert_eq!(outline.item[2].level, 2);
+        Ok(())
+    }
+
     #[test]
     fn test_simple_text_parser_header_parsing() -> Result<(), anyhow::Error> {
         let options = SimpleTextParserOptions {
@@ -246,4 +534,49 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_simple_text_parser_invalid_utf8() -> Result<(), anyhow::Error> {
+        use crate::parser::error::ParseError;
+        use std::io::Write;
+
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(b"1\n\t1.1 \xff\n")?;
+        let parser = SimpleTextParser::new(SimpleTextParserOptions::default());
+        let err = parser.parse_input(Some(file.path())).unwrap_err();
+        let err = err.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(err.location.file.as_deref(), Some(file.path()));
+        assert_eq!((err.location.line, err.location.column), (2, 6));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_simple_text_parser_parse_stream() -> Result<(), anyhow::Error> {
+        use std::io::Write;
+
+        let input = "1,1(1),1(2)\r\n\t1.1,1.1(1),1.1(2)\n\n\t1.2,1.2(1),1.2(2)\n\t\t1.2.1,1.2.1(1),1.2.1(2)";
+        let mut file = tempfile::NamedTempFile::new()?;
+        file.write_all(input.as_bytes())?;
+
+        let parser = SimpleTextParser::new(SimpleTextParserOptions {
+            delimiter: Some(",".to_string()),
+            key_header: vec!["H1".to_string(), "H2".to_string(), "H3".to_string()],
+            value_header: vec!["H(1)".to_string(), "H(2)".to_string()],
+            ..Default::default()
+        });
+        let mut outline = parser.parse_stream(file.path(), &mut |_| Ok(()))?;
+        assert!(outline.item.is_empty());
+
+        let mut items = Vec::new();
+        parser.parse_stream(file.path(), &mut |item| {
+            items.push(item);
+            Ok(())
+        })?;
+        outline.item = items;
+        assert_eq!(outline, reference_outline());
+        assert_eq!(outline, parser.parse(input)?);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,252 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::{parse_input_with, Parser};
+use anyhow::Result;
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Default)]
+pub struct TextTreeParserOptions {
+    /// If true, move a trailing version (e.g. `v1.0.86`) and trailing parenthesized or
+    /// bracketed annotations (e.g. `(*)`, `(proc-macro)`) from the key to the values.
+    pub split_annotations: bool,
+    pub key_header: Vec<String>,
+    /// The headers of the version and annotation columns. Defaults to `Version` and
+    /// `Annotation`.
+    pub value_header: Vec<String>,
+}
+
+/// A parser for text trees drawn with box-drawing characters, as printed by `tree` and
+/// `cargo tree` (`├──`, `└──`, `│`), or with their ASCII variants (`|--`, `` `-- ``,
+/// `+---`, `\---`).
+///
+/// The level of an item follows from the column of its connector: connectors further right
+/// are children, connectors in the same column are siblings. A line without a prefix, such
+/// as the `.` printed by `tree`, is a level-1 item. Blank lines, spacer lines made only of
+/// tree-drawing characters and the `N directories, M files` report of `tree` are skipped.
+pub struct TextTreeParser {
+    options: TextTreeParserOptions,
+}
+
+/// Matches a line with a connector: the vertical lines and spaces before it, the connector
+/// and the text of the item.
+fn connector_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^((?:[│┃|][ \t\u{a0}]*|[ \t\u{a0}])*?)(?:[├└┣┗╰╟╙][─━═]*|[|`+\\]-+)[ \t\u{a0}]*(\S.*?)\s*$",
+        )
+        .unwrap()
+    })
+}
+
+/// Matches a line without a connector, e.g. the `[dev-dependencies]` lines of `cargo tree`.
+fn plain_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^([│┃| \t\u{a0}]*)(\S.*?)\s*$").unwrap())
+}
+
+/// Matches a line made only of tree-drawing characters, e.g. a spacer line `│`.
+fn spacer_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^[│┃|├└┣┗╰╟╙─━═\s]*$").unwrap())
+}
+
+fn report_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\d+ director(?:y|ies)(?:, \d+ files?)?$").unwrap())
+}
+
+/// Matches the trailing annotations of a key: an optional version followed by any number of
+/// `(...)` and `[...]` groups.
+fn annotation_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^(.+?)(?:\s+(v\d+(?:\.\d+)*(?:[-+][0-9A-Za-z.+-]*)?|\d+\.\d+(?:\.\d+)*(?:[-+][0-9A-Za-z.+-]*)?))?((?:\s+(?:\([^()]*\)|\[[^\[\]]*\]))*)$",
+        )
+        .unwrap()
+    })
+}
+
+impl TextTreeParser {
+    pub fn new(options: TextTreeParserOptions) -> Self {
+        TextTreeParser { options }
+    }
+
+    /// Splits `text` into the key, the version and the annotations.
+    fn split_annotations(text: &str) -> (String, String, String) {
+        match annotation_regex().captures(text) {
+            Some(captures) => (
+                captures[1].to_string(),
+                captures.get(2).map_or("", |m| m.as_str()).to_string(),
+                captures[3].split_whitespace().collect::<Vec<_>>().join(" "),
+            ),
+            None => (text.to_string(), String::new(), String::new()),
+        }
+    }
+
+    pub fn parse(&self, input: &str) -> Result<Outline> {
+        let mut outline = Outline::new();
+        outline.key_header = self.options.key_header.clone();
+        outline.value_header = self.options.value_header.clone();
+        if self.options.split_annotations && outline.value_header.is_empty() {
+            outline.value_header = vec!["Version".to_string(), "Annotation".to_string()];
+        }
+
+        // The columns of the connectors of the ancestors of the next item
+        let mut columns: Vec<usize> = Vec::new();
+        let mut has_root = false;
+        for line in input.lines() {
+            if spacer_regex().is_match(line) {
+                continue;
+            }
+            let (column, text) = match connector_regex().captures(line) {
+                Some(captures) => (Some(captures[1].chars().count()), captures[2].to_string()),
+                None => match plain_regex().captures(line) {
+                    Some(captures) if captures[1].is_empty() => (None, captures[2].to_string()),
+                    Some(captures) => (Some(captures[1].chars().count()), captures[2].to_string()),
+                    None => continue,
+                },
+            };
+            if report_regex().is_match(&text) {
+                continue;
+            }
+
+            let level = match column {
+                Some(column) => {
+                    while columns.last().is_some_and(|&c| c >= column) {
+                        columns.pop();
+                    }
+                    columns.push(column);
+                    columns.len() as u32 + u32::from(has_root)
+                }
+                None => {
+                    columns.clear();
+                    has_root = true;
+                    1
+                }
+            };
+            let item = if self.options.split_annotations {
+                let (key, version, annotation) = Self::split_annotations(&text);
+                OutlineItem::new(&key, level, vec![version, annotation])
+            } else {
+                OutlineItem::new(&text, level, vec![])
+            };
+            outline.item.push(item);
+        }
+        Ok(outline)
+    }
+}
+
+impl Parser for TextTreeParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        parse_input_with(input_path, |input| self.parse(input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(outline: &Outline) -> Vec<(&str, u32)> {
+        outline
+            .item
+            .iter()
+            .map(|item| (item.key.as_str(), item.level))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_tree() -> Result<()> {
+        let parser = TextTreeParser::new(TextTreeParserOptions::default());
+        // `tree` separates the vertical lines with no-break spaces in UTF-8 locales
+        let input = "\
+.
+├── Cargo.toml
+├── src
+│\u{a0}\u{a0} ├── lib.rs
+│\u{a0}\u{a0} └── parser
+│\u{a0}\u{a0}     └── mod.rs
+└── README.md
+
+2 directories, 4 files
+";
+        let expected = vec![
+            (".", 1),
+            ("Cargo.toml", 2),
+            ("src", 2),
+            ("lib.rs", 3),
+            ("parser", 3),
+            ("mod.rs", 4),
+            ("README.md", 2),
+        ];
+        assert_eq!(levels(&parser.parse(input)?), expected);
+
+        let ascii = "\
+.
+|-- Cargo.toml
+|-- src
+|   |-- lib.rs
+|   `-- parser
+|       `-- mod.rs
+`-- README.md
+";
+        assert_eq!(levels(&parser.parse(ascii)?), expected);
+
+        // `tree /A` on Windows, without a root line
+        let windows = "+---src\n|   \\---parser\n\\---docs\n";
+        assert_eq!(
+            levels(&parser.parse(windows)?),
+            vec![("src", 1), ("parser", 2), ("docs", 1)]
+        );
+
+        // Spacer lines between items
+        let spaced = ".\n├── a\n│\n│   └── a1\n└── b\n";
+        assert_eq!(
+            levels(&parser.parse(spaced)?),
+            vec![(".", 1), ("a", 2), ("a1", 3), ("b", 2)]
+        );
+        let spaced = ".\n|-- a\n|\n|   `-- a1\n`-- b\n";
+        assert_eq!(
+            levels(&parser.parse(spaced)?),
+            vec![(".", 1), ("a", 2), ("a1", 3), ("b", 2)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_cargo_tree() -> Result<()> {
+        let input = "\
+htot-conv-rs v0.1.0 (/root/crate)
+├── anyhow v1.0.86
+├── serde v1.0.203
+│   └── serde_derive v1.0.203 (proc-macro)
+│       └── quote v1.0.36 (*)
+[dev-dependencies]
+└── tempfile v3.10.1
+";
+        let parser = TextTreeParser::new(TextTreeParserOptions {
+            split_annotations: true,
+            ..Default::default()
+        });
+        let outline = parser.parse(input)?;
+        let mut expected = Outline::new();
+        expected.value_header = vec!["Version".to_string(), "Annotation".to_string()];
+        let values =
+            |version: &str, annotation: &str| vec![version.to_string(), annotation.to_string()];
+        expected.add_item("htot-conv-rs", 1, values("v0.1.0", "(/root/crate)"));
+        expected.add_item("anyhow", 2, values("v1.0.86", ""));
+        expected.add_item("serde", 2, values("v1.0.203", ""));
+        expected.add_item("serde_derive", 3, values("v1.0.203", "(proc-macro)"));
+        expected.add_item("quote", 4, values("v1.0.36", "(*)"));
+        expected.add_item("[dev-dependencies]", 1, values("", ""));
+        expected.add_item("tempfile", 2, values("v3.10.1", ""));
+        assert_eq!(outline, expected);
+
+        let outline = TextTreeParser::new(TextTreeParserOptions::default()).parse(input)?;
+        assert_eq!(outline.item[4].key, "quote v1.0.36 (*)");
+        assert!(outline.item[4].value.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,699 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::Parser;
+use crate::value::Value;
+use anyhow::{bail, Context, Result};
+use calamine::{Data, Dimensions, Reader, Xlsx};
+use chrono::NaiveTime;
+use clap::ValueEnum;
+use quick_xml::events::Event;
+use rust_xlsxwriter::utility::row_col_to_cell;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// The layout of the worksheet, named after the output type that writes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum XlsxLayout {
+    /// A key column, an `Outline Level` column, then the value columns.
+    #[default]
+    #[value(name = "xlsx_type0")]
+    Type0,
+    /// Rows grouped with Excel row outline levels, as written by `xlsx_type1` with
+    /// `--to-outline-rows`: a key column and value columns.
+    #[value(name = "xlsx_type1")]
+    Type1,
+    /// One key column per level, with the key in the column of its level.
+    #[value(name = "xlsx_type2")]
+    Type2,
+    /// One key column per level, with the first value right after the key.
+    #[value(name = "xlsx_type3")]
+    Type3,
+    /// One row per leaf, with the keys of its ancestors on the first row of their subtree.
+    #[value(name = "xlsx_type4")]
+    Type4,
+    /// One row per leaf, with the keys of its ancestors repeated on every row.
+    #[value(name = "xlsx_type5")]
+    Type5,
+}
+
+#[derive(Debug, Clone)]
+pub struct XlsxParserOptions {
+    pub layout: XlsxLayout,
+    /// The worksheet to read. Defaults to the first worksheet.
+    pub sheet: Option<String>,
+    /// The key column of `XlsxLayout::Type1`, by header or 1-based index. Defaults to the
+    /// first column.
+    pub key_column: Option<String>,
+    /// The number of key columns of the layouts with one key column per level. Inferred
+    /// from the cells if not set.
+    pub levels: Option<usize>,
+    /// If true, the first row holds the key and value headers.
+    pub has_header: bool,
+    /// Defaults to the key headers of the header row.
+    pub key_header: Vec<String>,
+    /// Defaults to the value headers of the header row.
+    pub value_header: Vec<String>,
+}
+
+impl Default for XlsxParserOptions {
+    fn default() -> Self {
+        XlsxParserOptions {
+            layout: XlsxLayout::default(),
+            sheet: None,
+            key_column: None,
+            levels: None,
+            has_header: true,
+            key_header: Vec::new(),
+            value_header: Vec::new(),
+        }
+    }
+}
+
+/// A parser for worksheets written by the `xlsx_type*` output types, possibly edited since.
+///
+/// The first row is the header row, unless `has_header` is false. Values are read as text: numbers as written by
+/// `Value::Number`, dates, date-times and durations in ISO 8601, so that `--value-type`
+/// can restore their type. The value of a merged range is the value of its top-left
+/// cell; the other cells of the range read as empty.
+pub struct XlsxParser {
+    options: XlsxParserOptions,
+}
+
+/// The cells of a worksheet as text, from `A1` to the last used cell.
+struct Grid {
+    rows: Vec<Vec<String>>,
+    /// The merged ranges of the worksheet.
+    merges: Vec<Dimensions>,
+    /// The Excel outline level of the grouped rows, by 0-based row number. Only read for
+    /// `XlsxLayout::Type1`.
+    row_levels: HashMap<usize, u32>,
+    /// If true, the first row is the header row.
+    has_header: bool,
+}
+
+impl Grid {
+    /// Returns the header row, or no cells if the worksheet has none.
+    fn header(&self) -> &[String] {
+        match self.rows.first() {
+            Some(header) if self.has_header => header,
+            _ => &[],
+        }
+    }
+
+    /// Returns the 0-based row number of the first data row.
+    fn first_data_row(&self) -> usize {
+        usize::from(self.has_header)
+    }
+
+    /// Returns the data rows with their 0-based row numbers, skipping empty rows.
+    fn data_rows(&self) -> impl Iterator<Item = (usize, &[String])> {
+        self.rows
+            .iter()
+            .enumerate()
+            .skip(self.first_data_row())
+            .filter(|(_, row)| row.iter().any(|cell| !cell.is_empty()))
+            .map(|(index, row)| (index, row.as_slice()))
+    }
+}
+
+/// Returns the texts of `cells` without the trailing empty ones.
+fn trimmed(cells: &[String]) -> Vec<String> {
+    let len = cells
+        .iter()
+        .rposition(|cell| !cell.is_empty())
+        .map_or(0, |i| i + 1);
+    cells[..len].to_vec()
+}
+
+/// Returns the values `cells`, without the trailing empty ones.
+fn values(cells: &[String]) -> Vec<Value> {
+    trimmed(cells).into_iter().map(Value::Text).collect()
+}
+
+/// Returns the name of a cell, e.g. `B3`.
+fn cell_name(row: usize, col: usize) -> String {
+    row_col_to_cell(row as u32, col as u16)
+}
+
+/// Converts the value of a cell to text.
+fn cell_text(data: &Data) -> String {
+    match data {
+        Data::Empty => String::new(),
+        Data::String(s) | Data::DateTimeIso(s) | Data::DurationIso(s) => s.clone(),
+        Data::Int(i) => i.to_string(),
+        Data::Float(f) => f.to_string(),
+        Data::Bool(b) => b.to_string(),
+        Data::Error(e) => e.to_string(),
+        Data::DateTime(dt) => {
+            if dt.is_duration() {
+                if let Some(duration) = dt.as_duration() {
+                    return Value::Duration(duration).to_string();
+                }
+            } else if let Some(datetime) = dt.as_datetime() {
+                if datetime.time() == NaiveTime::MIN {
+                    return Value::Date(datetime.date()).to_string();
+                }
+                return Value::DateTime(datetime).to_string();
+            }
+            dt.as_f64().to_string()
+        }
+    }
+}
+
+impl XlsxParser {
+    pub fn new(options: XlsxParserOptions) -> Self {
+        XlsxParser { options }
+    }
+
+    /// Parses the XLSX workbook read from `reader` into an `Outline`.
+    pub fn parse<R: Read>(&self, mut reader: R) -> Result<Outline> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let grid = self.read_grid(&data)?;
+        let mut outline = match self.options.layout {
+            XlsxLayout::Type0 => self.parse_type0(&grid)?,
+            XlsxLayout::Type1 => self.parse_type1(&grid)?,
+            XlsxLayout::Type2 | XlsxLayout::Type3 => self.parse_by_level_column(&grid)?,
+            XlsxLayout::Type4 | XlsxLayout::Type5 => self.parse_by_leaf_row(&grid)?,
+        };
+        if !self.options.key_header.is_empty() {
+            outline.key_header = self.options.key_header.clone();
+        }
+        if !self.options.value_header.is_empty() {
+            outline.value_header = self.options.value_header.clone();
+        }
+        Ok(outline)
+    }
+
+    fn read_grid(&self, data: &[u8]) -> Result<Grid> {
+        let mut workbook = Xlsx::new(Cursor::new(data)).context("Failed to open XLSX workbook")?;
+        let sheet = match &self.options.sheet {
+            Some(sheet) => sheet.clone(),
+            None => match workbook.sheet_names().first() {
+                Some(sheet) => sheet.clone(),
+                None => bail!("The workbook has no worksheet"),
+            },
+        };
+        let range = workbook
+            .worksheet_range(&sheet)
+            .with_context(|| format!("Failed to read worksheet '{}'", sheet))?;
+        let merges = match workbook.worksheet_merge_cells(&sheet) {
+            Some(merges) => merges?,
+            None => Vec::new(),
+        };
+        let (height, width) = range
+            .end()
+            .map_or((0, 0), |(row, col)| (row as usize + 1, col as usize + 1));
+        let rows = (0..height)
+            .map(|row| {
+                (0..width)
+                    .map(|col| {
+                        range
+                            .get_value((row as u32, col as u32))
+                            .map_or(String::new(), cell_text)
+                    })
+                    .collect()
+            })
+            .collect();
+        let row_levels = match self.options.layout {
+            XlsxLayout::Type1 => read_row_levels(data, &sheet)?,
+            _ => HashMap::new(),
+        };
+        Ok(Grid {
+            rows,
+            merges,
+            row_levels,
+            has_header: self.options.has_header,
+        })
+    }
+
+    /// Reads the `xlsx_type0` layout.
+    fn parse_type0(&self, grid: &Grid) -> Result<Outline> {
+        let mut outline = Outline::new();
+        let header = grid.header();
+        outline.key_header = trimmed(header.get(..1).unwrap_or_default());
+        outline.value_header = trimmed(header.get(2..).unwrap_or_default());
+        for (row, cells) in grid.data_rows() {
+            let level = cells.get(1).map_or("", String::as_str);
+            let Some(level) = level.parse::<u32>().ok().filter(|&l| l > 0) else {
+                bail!("Invalid level '{}' in cell {}", level, cell_name(row, 1));
+            };
+            outline.item.push(OutlineItem {
+                key: cells[0].clone(),
+                level,
+                value: values(cells.get(2..).unwrap_or_default()),
+            });
+        }
+        Ok(outline)
+    }
+
+    /// Reads the `xlsx_type1` layout, where the level of each item is its row outline level
+    /// plus one.
+    fn parse_type1(&self, grid: &Grid) -> Result<Outline> {
+        let header = grid.header();
+        let key_col = match &self.options.key_column {
+            Some(spec) => match header
+                .iter()
+                .position(|h| h.trim() == spec)
+                .or_else(|| spec.parse::<usize>().ok().filter(|&i| i > 0).map(|i| i - 1))
+            {
+                Some(col) => col,
+                None => bail!("No column '{}'", spec),
+            },
+            None => 0,
+        };
+        let split = |cells: &[String]| -> (String, Vec<String>) {
+            let mut values = cells.to_vec();
+            let key = if key_col < values.len() {
+                values.remove(key_col)
+            } else {
+                String::new()
+            };
+            (key, values)
+        };
+
+        let mut outline = Outline::new();
+        let (key_header, value_header) = split(header);
+        outline.key_header = trimmed(&[key_header]);
+        outline.value_header = trimmed(&value_header);
+        for (row, cells) in grid.data_rows() {
+            let (key, value) = split(cells);
+            outline.item.push(OutlineItem {
+                key,
+                level: grid.row_levels.get(&row).copied().unwrap_or(0) + 1,
+                value: values(&value),
+            });
+        }
+        Ok(outline)
+    }
+
+    /// Reads the `xlsx_type2` and `xlsx_type3` layouts, where the level of each item is
+    /// given by the column of its key, the first non-empty cell of its row.
+    fn parse_by_level_column(&self, grid: &Grid) -> Result<Outline> {
+        let first_cell = |cells: &[String]| cells.iter().position(|cell| !cell.is_empty());
+        let levels = match self.options.levels {
+            Some(levels) => levels,
+            None => grid
+                .data_rows()
+                .filter_map(|(_, cells)| first_cell(cells))
+                .max()
+                .map_or(0, |col| col + 1),
+        };
+        let type3 = self.options.layout == XlsxLayout::Type3;
+        let header = grid.header();
+        let cells_from =
+            |cells: &[String], col: usize| cells.get(col..).unwrap_or_default().to_vec();
+
+        let mut outline = Outline::new();
+        if type3 {
+            outline.key_header = trimmed(header.get(..1).unwrap_or_default());
+            let mut value_header = header.get(1..2).unwrap_or_default().to_vec();
+            value_header.extend(cells_from(header, levels + 1));
+            outline.value_header = trimmed(&value_header);
+        } else {
+            outline.key_header = trimmed(header.get(..levels).unwrap_or(header));
+            outline.value_header = trimmed(&cells_from(header, levels));
+        }
+        for (row, cells) in grid.data_rows() {
+            let col = first_cell(cells).expect("data rows are not empty");
+            if col >= levels {
+                bail!("No key in the first {} columns of row {}", levels, row + 1);
+            }
+            let mut value = Vec::new();
+            if type3 {
+                value.push(cells.get(col + 1).cloned().unwrap_or_default());
+                value.extend(cells_from(cells, levels + 1));
+            } else {
+                value.extend(cells_from(cells, levels));
+            }
+            outline.item.push(OutlineItem {
+                key: cells[col].clone(),
+                level: col as u32 + 1,
+                value: values(&value),
+            });
+        }
+        Ok(outline)
+    }
+
+    /// Returns the number of key columns of the `xlsx_type4` and `xlsx_type5` layouts.
+    ///
+    /// The key of each leaf is followed by empty or merged cells up to the last key column,
+    /// then by its values: the first non-empty cell after a gap, or the column after a
+    /// merged range starting at a key, is a candidate for the first value column.
+    fn leaf_row_levels(&self, grid: &Grid) -> Result<usize> {
+        if let Some(levels) = self.options.levels {
+            return Ok(levels);
+        }
+        let merge_candidates = grid
+            .merges
+            .iter()
+            .filter(|m| m.start.0 as usize >= grid.first_data_row() && m.start.0 == m.end.0 && m.start.1 < m.end.1)
+            .map(|m| m.end.1 as usize + 1);
+        let gap_candidates = grid.data_rows().filter_map(|(_, cells)| {
+            let key = cells.iter().position(|cell| !cell.is_empty())?;
+            let gap = key + cells[key..].iter().position(String::is_empty)?;
+            Some(gap + cells[gap..].iter().position(|cell| !cell.is_empty())?)
+        });
+        match merge_candidates.chain(gap_candidates).min() {
+            Some(levels) => Ok(levels),
+            None => bail!(
+                "Cannot infer the number of key columns of the {} layout; use --from-levels",
+                self.options.layout.to_possible_value().unwrap().get_name()
+            ),
+        }
+    }
+
+    /// Reads the `xlsx_type4` and `xlsx_type5` layouts, with one row per leaf.
+    ///
+    /// An empty key cell left of the leaf belongs to the same ancestor as in the row above.
+    /// With `xlsx_type5`, so does a key equal to the key of the row above.
+    fn parse_by_leaf_row(&self, grid: &Grid) -> Result<Outline> {
+        let levels = self.leaf_row_levels(grid)?;
+        let header = grid.header();
+        let mut outline = Outline::new();
+        outline.key_header = trimmed(header.get(..levels).unwrap_or(header));
+        outline.value_header = trimmed(header.get(levels..).unwrap_or_default());
+
+        // The keys of the ancestors of the current row, by column
+        let mut path: Vec<String> = Vec::new();
+        for (row, cells) in grid.data_rows() {
+            let keys = &cells[..levels.min(cells.len())];
+            let Some(leaf) = keys.iter().rposition(|cell| !cell.is_empty()) else {
+                bail!("No key in the first {} columns of row {}", levels, row + 1);
+            };
+            let mut new_item = false;
+            for (col, key) in keys[..=leaf].iter().enumerate() {
+                new_item = new_item
+                    || col == leaf
+                    || match self.options.layout {
+                        XlsxLayout::Type5 => path.get(col) != Some(key),
+                        _ => !key.is_empty(),
+                    };
+                if !new_item {
+                    if path.len() <= col {
+                        bail!("No key in cell {}", cell_name(row, col));
+                    }
+                    continue;
+                }
+                if key.is_empty() {
+                    bail!("No key in cell {}", cell_name(row, col));
+                }
+                path.truncate(col);
+                path.push(key.clone());
+                outline.item.push(OutlineItem {
+                    key: key.clone(),
+                    level: col as u32 + 1,
+                    value: if col == leaf {
+                        values(cells.get(levels..).unwrap_or_default())
+                    } else {
+                        Vec::new()
+                    },
+                });
+            }
+        }
+        Ok(outline)
+    }
+}
+
+/// Returns the attributes of the elements named `name` in the XML part `path` of `archive`,
+/// keyed by local name.
+fn xml_elements(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    path: &str,
+    name: &[u8],
+) -> Result<Vec<HashMap<String, String>>> {
+    let mut xml = String::new();
+    archive
+        .by_name(path)
+        .with_context(|| format!("Failed to read '{}' in XLSX workbook", path))?
+        .read_to_string(&mut xml)?;
+    let mut reader = quick_xml::Reader::from_str(&xml);
+    let mut elements = Vec::new();
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == name => {
+                let mut attributes = HashMap::new();
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    attributes.insert(
+                        String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned(),
+                        attr.unescape_value()?.into_owned(),
+                    );
+                }
+                elements.push(attributes);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(elements)
+}
+
+/// Reads the outline levels of the rows of the worksheet `sheet`, by 0-based row number.
+///
+/// Rows without an outline level are omitted.
+fn read_row_levels(data: &[u8], sheet: &str) -> Result<HashMap<usize, u32>> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    // The worksheet part is found through the relationship named in the workbook
+    let relationship = xml_elements(&mut archive, "xl/workbook.xml", b"sheet")?
+        .into_iter()
+        .find(|sheet_attributes| sheet_attributes.get("name").map(String::as_str) == Some(sheet))
+        .and_then(|mut sheet_attributes| sheet_attributes.remove("id"))
+        .with_context(|| format!("No worksheet '{}'", sheet))?;
+    let target = xml_elements(&mut archive, "xl/_rels/workbook.xml.rels", b"Relationship")?
+        .into_iter()
+        .find(|rel| rel.get("Id") == Some(&relationship))
+        .and_then(|mut rel| rel.remove("Target"))
+        .with_context(|| format!("No part for worksheet '{}'", sheet))?;
+    let path = match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("xl/{}", target),
+    };
+
+    let mut levels = HashMap::new();
+    for row in xml_elements(&mut archive, &path, b"row")? {
+        let number = row.get("r").and_then(|r| r.parse::<usize>().ok());
+        let level = row.get("outlineLevel").and_then(|l| l.parse::<u32>().ok());
+        if let (Some(number), Some(level)) = (number, level) {
+            levels.insert(number - 1, level);
+        }
+    }
+    Ok(levels)
+}
+
+impl Parser for XlsxParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        match input_path {
+            Some(path) => self.parse(std::fs::File::open(path)?),
+            None => self.parse(std::io::stdin()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::xlsx_type0::{XlsxType0Generator, XlsxType0GeneratorOptions};
+    use crate::generator::xlsx_type1::{XlsxType1Generator, XlsxType1GeneratorOptions};
+    use crate::generator::xlsx_type2::{XlsxType2Generator, XlsxType2GeneratorOptions};
+    use crate::generator::xlsx_type3::{XlsxType3Generator, XlsxType3GeneratorOptions};
+    use crate::generator::xlsx_type4::{XlsxType4Generator, XlsxType4GeneratorOptions};
+    use crate::generator::xlsx_type5::{XlsxType5Generator, XlsxType5GeneratorOptions};
+    use crate::generator::{IntegrateCellsOption, XlsxGenerator};
+    use chrono::{NaiveDate, TimeDelta};
+    use rust_xlsxwriter::Workbook;
+
+    fn sample() -> Outline {
+        let mut outline = Outline::new();
+        outline.key_header = vec!["H1".to_string(), "H2".to_string(), "H3".to_string()];
+        outline.value_header = vec!["H(1)".to_string(), "H(2)".to_string()];
+        outline.add_item("1", 1, vec!["1(1)".to_string(), "1(2)".to_string()]);
+        outline.add_item("1.1", 2, vec!["1.1(1)".to_string(), "1.1(2)".to_string()]);
+        outline.add_item("1.2", 2, vec!["1.2(1)".to_string(), "1.2(2)".to_string()]);
+        outline.add_item("1.2.1", 3, vec!["1.2.1(1)".to_string()]);
+        outline.add_item("1.2.2", 3, vec!["".to_string(), "1.2.2(2)".to_string()]);
+        outline.add_item("2", 1, vec!["2(1)".to_string()]);
+        outline
+    }
+
+    /// Writes `generator` to a workbook and reads it back with `layout`.
+    fn round_trip(generator: &dyn XlsxGenerator, layout: XlsxLayout) -> Result<Outline> {
+        let mut workbook = Workbook::new();
+        generator.output_to_worksheet(workbook.add_worksheet())?;
+        let buf = workbook.save_to_buffer()?;
+        XlsxParser::new(XlsxParserOptions {
+            layout,
+            ..Default::default()
+        })
+        .parse(Cursor::new(buf))
+    }
+
+    #[test]
+    fn test_parse_type0() -> Result<()> {
+        let mut outline = sample();
+        outline.item[0].value = vec![
+            Value::Integer(3),
+            Value::Date(NaiveDate::from_ymd_opt(2025, 1, 31).unwrap()),
+        ];
+        outline.item[1].value = vec![Value::Number(1.5), Value::Duration(TimeDelta::minutes(90))];
+        let generator = XlsxType0Generator::new(outline, XlsxType0GeneratorOptions::default());
+        let read = round_trip(&generator, XlsxLayout::Type0)?;
+
+        let mut expected = sample();
+        expected.key_header.truncate(1);
+        expected.item[0].value = vec!["3".into(), "2025-01-31".into()];
+        expected.item[1].value = vec!["1.5".into(), "PT1H30M0S".into()];
+        assert_eq!(read, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_type1() -> Result<()> {
+        let generator = XlsxType1Generator::new(
+            sample(),
+            XlsxType1GeneratorOptions {
+                outline_rows: true,
+                shironuri: false,
+            },
+        );
+        let mut expected = sample();
+        expected.key_header.truncate(1);
+        assert_eq!(round_trip(&generator, XlsxLayout::Type1)?, expected);
+
+        // A legacy sheet with the key in another column
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.write_row(0, 0, ["Owner", "Task"])?;
+        worksheet.write_row(1, 0, ["Ann", "Plan"])?;
+        worksheet.write_row(2, 0, ["Bob", "Scope"])?;
+        worksheet.write_row(3, 0, ["", "Budget"])?;
+        worksheet.group_rows(2, 3)?;
+        worksheet.group_rows(3, 3)?;
+        let outline = XlsxParser::new(XlsxParserOptions {
+            layout: XlsxLayout::Type1,
+            key_column: Some("Task".to_string()),
+            ..Default::default()
+        })
+        .parse(Cursor::new(workbook.save_to_buffer()?))?;
+        let mut expected = Outline::new();
+        expected.key_header = vec!["Task".to_string()];
+        expected.value_header = vec!["Owner".to_string()];
+        expected.add_item("Plan", 1, vec!["Ann".to_string()]);
+        expected.add_item("Scope", 2, vec!["Bob".to_string()]);
+        expected.add_item("Budget", 3, vec![]);
+        assert_eq!(outline, expected);
+
+        // A legacy sheet without a header row
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.write_row(0, 0, ["Plan", "Ann"])?;
+        worksheet.write_row(1, 0, ["Scope", "Bob"])?;
+        worksheet.write_row(2, 0, ["Budget"])?;
+        worksheet.group_rows(1, 2)?;
+        worksheet.group_rows(2, 2)?;
+        let outline = XlsxParser::new(XlsxParserOptions {
+            layout: XlsxLayout::Type1,
+            has_header: false,
+            ..Default::default()
+        })
+        .parse(Cursor::new(workbook.save_to_buffer()?))?;
+        let mut expected = Outline::new();
+        expected.add_item("Plan", 1, vec!["Ann".to_string()]);
+        expected.add_item("Scope", 2, vec!["Bob".to_string()]);
+        expected.add_item("Budget", 3, vec![]);
+        assert_eq!(outline, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_type2_type3() -> Result<()> {
+        for integrate_cells in [None, Some(IntegrateCellsOption::Colspan)] {
+            let generator = XlsxType2Generator::new(
+                sample(),
+                XlsxType2GeneratorOptions {
+                    outline_rows: false,
+                    integrate_cells,
+                    shironuri: false,
+                },
+            );
+            assert_eq!(round_trip(&generator, XlsxLayout::Type2)?, sample());
+        }
+        for integrate_cells in [None, Some(IntegrateCellsOption::Both)] {
+            let generator = XlsxType3Generator::new(
+                sample(),
+                XlsxType3GeneratorOptions {
+                    outline_rows: false,
+                    integrate_cells,
+                    shironuri: false,
+                },
+            );
+            let mut expected = sample();
+            expected.key_header.truncate(1);
+            assert_eq!(round_trip(&generator, XlsxLayout::Type3)?, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_type4_type5() -> Result<()> {
+        // Only the values of leaves are written
+        let mut expected = sample();
+        for index in [0, 2] {
+            expected.item[index].value.clear();
+        }
+        for integrate_cells in [None, Some(IntegrateCellsOption::Both)] {
+            let generator = XlsxType4Generator::new(
+                sample(),
+                XlsxType4GeneratorOptions {
+                    integrate_cells,
+                    shironuri: false,
+                },
+            );
+            assert_eq!(round_trip(&generator, XlsxLayout::Type4)?, expected);
+        }
+        let generator = XlsxType5Generator::new(
+            sample(),
+            XlsxType5GeneratorOptions {
+                integrate_cells: Some(IntegrateCellsOption::Colspan),
+                shironuri: false,
+            },
+        );
+        assert_eq!(round_trip(&generator, XlsxLayout::Type5)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() -> Result<()> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.write(0, 0, "Key")?;
+        worksheet.write(1, 0, "A")?;
+        worksheet.write(1, 1, "x")?;
+        let buf = workbook.save_to_buffer()?;
+        let err = XlsxParser::new(XlsxParserOptions::default())
+            .parse(Cursor::new(buf.clone()))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid level 'x' in cell B2");
+
+        let err = XlsxParser::new(XlsxParserOptions {
+            layout: XlsxLayout::Type5,
+            ..Default::default()
+        })
+        .parse(Cursor::new(buf.clone()))
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot infer the number of key columns of the xlsx_type5 layout; use --from-levels"
+        );
+
+        let err = XlsxParser::new(XlsxParserOptions {
+            sheet: Some("Missing".to_string()),
+            ..Default::default()
+        })
+        .parse(Cursor::new(buf))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Failed to read worksheet 'Missing'");
+        Ok(())
+    }
+}
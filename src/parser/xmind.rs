@@ -0,0 +1,464 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::parser::Parser;
+use anyhow::{bail, Context, Result};
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use serde::Deserialize;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+#[derive(Debug, Clone, Default)]
+pub struct XmindParserOptions {
+    /// The title of the sheet to read. Defaults to the first sheet.
+    pub sheet: Option<String>,
+    /// If true, add a value column with the labels of each topic, joined with `, `.
+    pub label_column: bool,
+    /// If true, add a value column with the marker ids of each topic (e.g. `priority-1`),
+    /// joined with `, `.
+    pub marker_column: bool,
+    /// If true, add a value column with the plain-text note of each topic.
+    pub note_column: bool,
+    pub key_header: Vec<String>,
+    /// The headers of the label, marker and note columns. Defaults to `Labels`, `Markers`
+    /// and `Note`.
+    pub value_header: Vec<String>,
+}
+
+/// A parser for XMind workbooks, reading the topic tree of one sheet.
+///
+/// Both the `content.json` of XMind 2020 and later and the `content.xml` of XMind 8 and
+/// earlier are read; `content.json` is preferred when a workbook has both. The attached and
+/// detached (floating) subtopics of a topic are its children.
+pub struct XmindParser {
+    options: XmindParserOptions,
+}
+
+/// A sheet of a workbook, in either format.
+struct Sheet {
+    title: String,
+    root: Topic,
+}
+
+/// A topic of a sheet, in either format.
+#[derive(Default)]
+struct Topic {
+    title: String,
+    labels: Vec<String>,
+    markers: Vec<String>,
+    note: String,
+    children: Vec<Topic>,
+}
+
+/// A sheet of `content.json`.
+#[derive(Deserialize)]
+struct JsonSheet {
+    #[serde(default)]
+    title: String,
+    #[serde(rename = "rootTopic")]
+    root_topic: JsonTopic,
+}
+
+/// A topic of `content.json`.
+#[derive(Deserialize)]
+struct JsonTopic {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    children: JsonChildren,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    markers: Vec<JsonMarker>,
+    notes: Option<JsonNotes>,
+}
+
+#[derive(Deserialize, Default)]
+struct JsonChildren {
+    #[serde(default)]
+    attached: Vec<JsonTopic>,
+    #[serde(default)]
+    detached: Vec<JsonTopic>,
+}
+
+#[derive(Deserialize)]
+struct JsonMarker {
+    #[serde(rename = "markerId")]
+    marker_id: String,
+}
+
+#[derive(Deserialize)]
+struct JsonNotes {
+    plain: Option<JsonPlainNote>,
+}
+
+#[derive(Deserialize)]
+struct JsonPlainNote {
+    content: String,
+}
+
+impl From<JsonTopic> for Topic {
+    fn from(topic: JsonTopic) -> Self {
+        Topic {
+            title: topic.title.trim().to_string(),
+            labels: topic.labels,
+            markers: topic.markers.into_iter().map(|m| m.marker_id).collect(),
+            note: topic
+                .notes
+                .and_then(|notes| notes.plain)
+                .map_or(String::new(), |plain| plain.content.trim().to_string()),
+            children: topic
+                .children
+                .attached
+                .into_iter()
+                .chain(topic.children.detached)
+                .map(Topic::from)
+                .collect(),
+        }
+    }
+}
+
+/// An element of `content.xml`.
+#[derive(Default)]
+struct Element {
+    /// The local name of the element.
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    /// The text directly inside the element.
+    text: String,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|e| e.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |e| e.name == name)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the trimmed text of the child element `name`, or an empty string.
+    fn child_text(&self, name: &str) -> String {
+        self.child(name)
+            .map_or(String::new(), |e| e.text.trim().to_string())
+    }
+}
+
+/// Returns an element with the local name and the attributes of `start`.
+fn start_element(start: &BytesStart) -> Result<Element> {
+    let mut element = Element {
+        name: String::from_utf8_lossy(start.local_name().as_ref()).into_owned(),
+        ..Default::default()
+    };
+    for attr in start.attributes() {
+        let attr = attr?;
+        element.attributes.push((
+            String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned(),
+            attr.unescape_value()?.into_owned(),
+        ));
+    }
+    Ok(element)
+}
+
+/// Reads `xml` into a tree of elements, returning the root element.
+fn read_xml_tree(xml: &str) -> Result<Element> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    // The open elements, the document being the first
+    let mut stack: Vec<Element> = vec![Element::default()];
+    loop {
+        let current = stack.last_mut().expect("the document is never closed");
+        match reader.read_event()? {
+            Event::Start(e) => stack.push(start_element(&e)?),
+            Event::Empty(e) => current.children.push(start_element(&e)?),
+            Event::End(_) => {
+                let element = stack.pop().expect("end tags are matched by the reader");
+                stack
+                    .last_mut()
+                    .expect("the document is never closed")
+                    .children
+                    .push(element);
+            }
+            Event::Text(e) => current.text.push_str(&e.decode()?),
+            Event::CData(e) => current.text.push_str(&e.decode()?),
+            Event::GeneralRef(e) => {
+                let name = e.decode()?;
+                match e.resolve_char_ref()? {
+                    Some(c) => current.text.push(c),
+                    None => match resolve_predefined_entity(&name) {
+                        Some(text) => current.text.push_str(text),
+                        None => current.text.push_str(&format!("&{};", name)),
+                    },
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    let mut document = stack.pop().expect("the document is never closed");
+    match document.children.pop() {
+        Some(root) => Ok(root),
+        None => bail!("No root element"),
+    }
+}
+
+/// Converts a `<topic>` element of `content.xml` into a `Topic`.
+fn xml_topic(element: &Element) -> Topic {
+    Topic {
+        title: element.child_text("title"),
+        labels: element
+            .child("labels")
+            .iter()
+            .flat_map(|labels| labels.children_named("label"))
+            .map(|label| label.text.trim().to_string())
+            .collect(),
+        markers: element
+            .child("marker-refs")
+            .iter()
+            .flat_map(|refs| refs.children_named("marker-ref"))
+            .filter_map(|marker| marker.attribute("marker-id"))
+            .map(str::to_string)
+            .collect(),
+        note: element
+            .child("notes")
+            .map_or(String::new(), |notes| notes.child_text("plain")),
+        children: element
+            .child("children")
+            .iter()
+            .flat_map(|children| children.children_named("topics"))
+            .filter(|topics| {
+                matches!(
+                    topics.attribute("type"),
+                    None | Some("attached") | Some("detached")
+                )
+            })
+            .flat_map(|topics| topics.children_named("topic"))
+            .map(xml_topic)
+            .collect(),
+    }
+}
+
+/// Reads the sheets of the workbook in `data`.
+fn read_sheets(data: &[u8]) -> Result<Vec<Sheet>> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).context("Failed to open XMind file")?;
+    let mut content = String::new();
+    if let Ok(mut file) = archive.by_name("content.json") {
+        file.read_to_string(&mut content)?;
+        let sheets: Vec<JsonSheet> =
+            serde_json::from_str(&content).context("Invalid content.json in XMind file")?;
+        return Ok(sheets
+            .into_iter()
+            .map(|sheet| Sheet {
+                title: sheet.title,
+                root: sheet.root_topic.into(),
+            })
+            .collect());
+    }
+    match archive.by_name("content.xml") {
+        Ok(mut file) => file.read_to_string(&mut content)?,
+        Err(_) => bail!("No content.json or content.xml in XMind file"),
+    };
+    let root = read_xml_tree(&content).context("Invalid content.xml in XMind file")?;
+    Ok(root
+        .children_named("sheet")
+        .map(|sheet| Sheet {
+            title: sheet.child_text("title"),
+            root: sheet.child("topic").map(xml_topic).unwrap_or_default(),
+        })
+        .collect())
+}
+
+impl XmindParser {
+    pub fn new(options: XmindParserOptions) -> Self {
+        XmindParser { options }
+    }
+
+    /// Parses the XMind workbook read from `reader` into an `Outline`.
+    pub fn parse<R: Read>(&self, mut reader: R) -> Result<Outline> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut sheets = read_sheets(&data)?;
+        let sheet = match &self.options.sheet {
+            Some(title) => match sheets.iter().position(|s| &s.title == title) {
+                Some(index) => sheets.swap_remove(index),
+                None => bail!("No sheet '{}' in XMind file", title),
+            },
+            None if sheets.is_empty() => bail!("The XMind file has no sheet"),
+            None => sheets.swap_remove(0),
+        };
+
+        let mut outline = Outline::new();
+        outline.key_header = self.options.key_header.clone();
+        outline.value_header = self.options.value_header.clone();
+        if outline.value_header.is_empty() {
+            for (enabled, header) in [
+                (self.options.label_column, "Labels"),
+                (self.options.marker_column, "Markers"),
+                (self.options.note_column, "Note"),
+            ] {
+                if enabled {
+                    outline.value_header.push(header.to_string());
+                }
+            }
+        }
+
+        let mut stack: Vec<(&Topic, u32)> = vec![(&sheet.root, 1)];
+        while let Some((topic, level)) = stack.pop() {
+            let mut value = Vec::new();
+            if self.options.label_column {
+                value.push(topic.labels.join(", "));
+            }
+            if self.options.marker_column {
+                value.push(topic.markers.join(", "));
+            }
+            if self.options.note_column {
+                value.push(topic.note.clone());
+            }
+            outline
+                .item
+                .push(OutlineItem::new(&topic.title, level, value));
+            stack.extend(topic.children.iter().rev().map(|child| (child, level + 1)));
+        }
+        Ok(outline)
+    }
+}
+
+impl Parser for XmindParser {
+    fn parse_input(&self, input_path: Option<&Path>) -> Result<Outline> {
+        match input_path {
+            Some(path) => self.parse(std::fs::File::open(path)?),
+            None => self.parse(std::io::stdin()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn xmind_file(name: &str, content: &str) -> Result<Vec<u8>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file(name, SimpleFileOptions::default())?;
+        writer.write_all(content.as_bytes())?;
+        Ok(writer.finish()?.into_inner())
+    }
+
+    fn all_columns() -> XmindParserOptions {
+        XmindParserOptions {
+            label_column: true,
+            marker_column: true,
+            note_column: true,
+            ..Default::default()
+        }
+    }
+
+    fn expected() -> Outline {
+        let mut expected = Outline::new();
+        expected.value_header = vec![
+            "Labels".to_string(),
+            "Markers".to_string(),
+            "Note".to_string(),
+        ];
+        let values = |values: [&str; 3]| values.iter().map(|s| s.to_string()).collect();
+        expected.add_item("Launch", 1, values(["", "", ""]));
+        expected.add_item(
+            "Marketing",
+            2,
+            values(["web, print", "priority-1, task-half", "Ask Ann & Bob"]),
+        );
+        expected.add_item("Ads", 3, values(["", "", ""]));
+        expected.add_item("Parking lot", 2, values(["", "", ""]));
+        expected
+    }
+
+    #[test]
+    fn test_parse_content_json() -> Result<()> {
+        let content = r#"[
+  {"id": "s1", "class": "sheet", "title": "Draft", "rootTopic": {"id": "t0", "title": "Old"}},
+  {"id": "s2", "class": "sheet", "title": "Plan", "rootTopic": {
+    "id": "t1", "title": "Launch", "structureClass": "org.xmind.ui.map.unbalanced",
+    "children": {
+      "attached": [{
+        "id": "t2", "title": "Marketing",
+        "labels": ["web", "print"],
+        "markers": [{"markerId": "priority-1"}, {"markerId": "task-half"}],
+        "notes": {"plain": {"content": "Ask Ann & Bob\n"}, "realHTML": {"content": "<div>Ask</div>"}},
+        "children": {"attached": [{"id": "t3", "title": "Ads"}]}
+      }],
+      "detached": [{"id": "t4", "title": "Parking lot", "position": {"x": 10, "y": 20}}]
+    }
+  }}
+]"#;
+        let data = xmind_file("content.json", content)?;
+        let parser = XmindParser::new(XmindParserOptions {
+            sheet: Some("Plan".to_string()),
+            ..all_columns()
+        });
+        assert_eq!(parser.parse(Cursor::new(&data))?, expected());
+
+        let outline = XmindParser::new(XmindParserOptions::default()).parse(Cursor::new(&data))?;
+        assert_eq!(outline.item.len(), 1);
+        assert_eq!(outline.item[0].key, "Old");
+        assert!(outline.item[0].value.is_empty());
+
+        let err = XmindParser::new(XmindParserOptions {
+            sheet: Some("Missing".to_string()),
+            ..Default::default()
+        })
+        .parse(Cursor::new(&data))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "No sheet 'Missing' in XMind file");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_content_xml() -> Result<()> {
+        let content = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<xmap-content xmlns="urn:xmind:xmap:xmlns:content:2.0" xmlns:xlink="http://www.w3.org/1999/xlink" version="2.0">
+  <sheet id="s1">
+    <topic id="t1" structure-class="org.xmind.ui.logic.right">
+      <title>Launch</title>
+      <children>
+        <topics type="attached">
+          <topic id="t2">
+            <title>Marketing</title>
+            <labels><label>web</label><label>print</label></labels>
+            <marker-refs><marker-ref marker-id="priority-1"/><marker-ref marker-id="task-half"/></marker-refs>
+            <notes><plain>Ask Ann &amp; Bob</plain><html><xhtml:p xmlns:xhtml="http://www.w3.org/1999/xhtml">Ask</xhtml:p></html></notes>
+            <children><topics type="attached"><topic id="t3"><title>Ads</title></topic></topics></children>
+          </topic>
+        </topics>
+        <topics type="detached"><topic id="t4"><title>Parking lot</title></topic></topics>
+        <topics type="summary"><topic id="t5"><title>Summary</title></topic></topics>
+      </children>
+    </topic>
+    <title>Plan</title>
+  </sheet>
+</xmap-content>
+"#;
+        let data = xmind_file("content.xml", content)?;
+        let parser = XmindParser::new(XmindParserOptions {
+            sheet: Some("Plan".to_string()),
+            ..all_columns()
+        });
+        assert_eq!(parser.parse(Cursor::new(&data))?, expected());
+
+        let err = XmindParser::new(XmindParserOptions::default())
+            .parse(Cursor::new(xmind_file("meta.xml", "<meta/>")?))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No content.json or content.xml in XMind file"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,769 @@
+//! Registry of the available input and output types.
+//! 
+//! The registry maps type names (as given to `--from-type` / `--to-type`) to
+//! factories that build a [`Parser`] or [`Generator`] from an [`OptionMap`].
+//! Additional formats can be registered at runtime without touching the CLI.
+//! 
+//! Types that support the streaming mode are also registered as a
+//! [`StreamParser`] or [`XlsxStreamGenerator`] under the same name.
+
+use crate::generator::json::{JsonGenerator, JsonGeneratorOptions};
+use crate::generator::xlsx_type0::{
+    XlsxType0Generator, XlsxType0GeneratorOptions, XlsxType0StreamGenerator,
+};
+use crate::generator::xlsx_type1::{
+    XlsxType1Generator, XlsxType1GeneratorOptions, XlsxType1StreamGenerator,
+};
+use crate::generator::xlsx_type2::{
+    XlsxType2Generator, XlsxType2GeneratorOptions, XlsxType2StreamGenerator,
+};
+use crate::generator::xlsx_type3::{XlsxType3Generator, XlsxType3GeneratorOptions};
+use crate::generator::xlsx_type4::{XlsxType4Generator, XlsxType4GeneratorOptions};
+use crate::generator::xlsx_type5::{XlsxType5Generator, XlsxType5GeneratorOptions};
+use crate::generator::{Generator, IntegrateCellsOption, XlsxGenerator, XlsxStreamGenerator};
+use crate::outline::{Outline, OutlineSummary};
+use crate::parser::csv_adjacency::{CsvAdjacencyParser, CsvAdjacencyParserOptions};
+use crate::parser::csv_table::{CsvHierarchy, CsvTableParser, CsvTableParserOptions};
+use crate::parser::data::{DataFormat, DataParser, DataParserOptions};
+use crate::parser::dir_tree::{DirTreeParser, DirTreeParserOptions};
+use crate::parser::freemind::{FreemindParser, FreemindParserOptions};
+use crate::parser::html_list::{HtmlListParser, HtmlListParserOptions};
+use crate::parser::json::JsonParser;
+use crate::parser::markdown::{MarkdownParser, MarkdownParserOptions, MarkdownStructure};
+use crate::parser::mspdi::{MspdiParser, MspdiParserOptions};
+use crate::parser::opml::{OpmlParser, OpmlParserOptions};
+use crate::parser::org::{OrgParser, OrgParserOptions};
+use crate::parser::simple_text::{SimpleTextParser, SimpleTextParserOptions};
+use crate::parser::text_tree::{TextTreeParser, TextTreeParserOptions};
+use crate::parser::xlsx::{XlsxLayout, XlsxParser, XlsxParserOptions};
+use crate::parser::xmind::{XmindParser, XmindParserOptions};
+use crate::parser::{Parser, StreamParser};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+
+/// A set of named option values handed to parser and generator factories.
+///
+/// Keys are the CLI option names without their `--from-` / `--to-` prefix
+/// (e.g. `indent`, `key-header`, `integrate-cells`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptionMap {
+    values: BTreeMap<String, String>,
+}
+
+impl OptionMap {
+    /// Creates a new, empty `OptionMap`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of the option `key`, replacing any previous value.
+    pub fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    /// Returns the raw value of the option `key`, if set.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(|s| s.as_str())
+    }
+
+    /// Returns the value of the option `key` as a boolean.
+    ///
+    /// Accepts `true`/`false`, `yes`/`no` and `1`/`0`. An unset option is `false`.
+    pub fn get_bool(&self, key: &str) -> Result<bool> {
+        match self.get(key) {
+            None => Ok(false),
+            Some(v) => match v.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok(true),
+                "false" | "no" | "0" | "" => Ok(false),
+                _ => anyhow::bail!("Invalid boolean value for option '{}': {}", key, v),
+            },
+        }
+    }
+
+    /// Returns the value of the option `key` as a comma-separated list of trimmed strings.
+    ///
+    /// An unset option is an empty list.
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .map(|s| s.split(',').map(|s| s.trim().to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the value of the option `key` parsed as a clap `ValueEnum`, if set.
+    pub fn get_enum<T: ValueEnum>(&self, key: &str) -> Result<Option<T>> {
+        self.get(key)
+            .map(|v| {
+                T::from_str(v, true)
+                    .map_err(|e| anyhow::anyhow!("Invalid value for option '{}': {}", key, e))
+            })
+            .transpose()
+    }
+
+    /// Returns the value of the option `key` parsed with `FromStr`, if set.
+    pub fn get_parsed<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: std::str::FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.get(key)
+            .map(|v| {
+                v.parse::<T>()
+                    .with_context(|| format!("Invalid value for option '{}': {}", key, v))
+            })
+            .transpose()
+    }
+}
+
+/// Builds a parser from the `--from-*` options.
+pub type ParserFactory = Box<dyn Fn(&OptionMap) -> Result<Box<dyn Parser>>>;
+
+/// Builds a generator for the given outline from the `--to-*` options.
+pub type GeneratorFactory = Box<dyn Fn(Outline, &OptionMap) -> Result<Box<dyn Generator>>>;
+
+/// Builds a single-worksheet XLSX generator for the given outline from the `--to-*` options.
+pub type XlsxGeneratorFactory = Box<dyn Fn(Outline, &OptionMap) -> Result<Box<dyn XlsxGenerator>>>;
+
+/// Builds a stream parser from the `--from-*` options.
+pub type StreamParserFactory = Box<dyn Fn(&OptionMap) -> Result<Box<dyn StreamParser>>>;
+
+/// Builds a stream generator for an outline with the given headers and size from the
+/// `--to-*` options.
+pub type StreamGeneratorFactory =
+    Box<dyn Fn(Outline, OutlineSummary, &OptionMap) -> Result<Box<dyn XlsxStreamGenerator>>>;
+
+/// A table of the parsers and generators selectable by type name.
+pub struct Registry {
+    parsers: Vec<(String, ParserFactory)>,
+    generators: Vec<(String, GeneratorFactory)>,
+    xlsx_generators: Vec<(String, XlsxGeneratorFactory)>,
+    stream_parsers: Vec<(String, StreamParserFactory)>,
+    stream_generators: Vec<(String, StreamGeneratorFactory)>,
+}
+
+impl Default for Registry {
+    /// Returns a registry holding all built-in input and output types.
+    fn default() -> Self {
+        let mut registry = Registry::new();
+        registry.register_builtin();
+        registry
+    }
+}
+
+impl Registry {
+    /// Creates a new, empty `Registry`.
+    pub fn new() -> Self {
+        Registry {
+            parsers: Vec::new(),
+            generators: Vec::new(),
+            xlsx_generators: Vec::new(),
+            stream_parsers: Vec::new(),
+            stream_generators: Vec::new(),
+        }
+    }
+
+    /// Registers a parser under `name`, replacing any parser of the same name.
+    pub fn register_parser<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&OptionMap) -> Result<Box<dyn Parser>> + 'static,
+    {
+        self.parsers.retain(|(n, _)| n != name);
+        self.parsers.push((name.to_string(), Box::new(factory)));
+    }
+
+    /// Registers a generator under `name`, replacing any generator of the same name.
+    pub fn register_generator<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(Outline, &OptionMap) -> Result<Box<dyn Generator>> + 'static,
+    {
+        self.generators.retain(|(n, _)| n != name);
+        self.generators.push((name.to_string(), Box::new(factory)));
+        self.xlsx_generators.retain(|(n, _)| n != name);
+    }
+
+    /// Registers a single-worksheet XLSX generator under `name`, replacing any generator of
+    /// the same name.
+    ///
+    /// The generator can also be created with [`Registry::create_generator`], and can be
+    /// given a worksheet of a multi-sheet workbook with [`Registry::create_xlsx_generator`].
+    pub fn register_xlsx_generator<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(Outline, &OptionMap) -> Result<Box<dyn XlsxGenerator>> + Clone + 'static,
+    {
+        let generator_factory = factory.clone();
+        self.register_generator(name, move |outline, o| {
+            Ok(Box::new(generator_factory(outline, o)?))
+        });
+        self.xlsx_generators
+            .push((name.to_string(), Box::new(factory)));
+    }
+
+    /// Registers a stream parser under `name`, replacing any stream parser of the same name.
+    pub fn register_stream_parser<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&OptionMap) -> Result<Box<dyn StreamParser>> + 'static,
+    {
+        self.stream_parsers.retain(|(n, _)| n != name);
+        self.stream_parsers
+            .push((name.to_string(), Box::new(factory)));
+    }
+
+    /// Registers a stream generator under `name`, replacing any stream generator of the
+    /// same name.
+    pub fn register_stream_generator<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(Outline, OutlineSummary, &OptionMap) -> Result<Box<dyn XlsxStreamGenerator>>
+            + 'static,
+    {
+        self.stream_generators.retain(|(n, _)| n != name);
+        self.stream_generators
+            .push((name.to_string(), Box::new(factory)));
+    }
+
+    /// Returns the names of the registered parsers in registration order.
+    pub fn parser_types(&self) -> Vec<String> {
+        self.parsers.iter().map(|(n, _)| n.clone()).collect()
+    }
+
+    /// Returns the names of the registered generators in registration order.
+    pub fn generator_types(&self) -> Vec<String> {
+        self.generators.iter().map(|(n, _)| n.clone()).collect()
+    }
+
+    /// Returns the names of the registered stream parsers in registration order.
+    pub fn stream_parser_types(&self) -> Vec<String> {
+        self.stream_parsers.iter().map(|(n, _)| n.clone()).collect()
+    }
+
+    /// Returns the names of the registered stream generators in registration order.
+    pub fn stream_generator_types(&self) -> Vec<String> {
+        self.stream_generators
+            .iter()
+            .map(|(n, _)| n.clone())
+            .collect()
+    }
+
+    /// Creates the parser registered under `name`.
+    pub fn create_parser(&self, name: &str, options: &OptionMap) -> Result<Box<dyn Parser>> {
+        let (_, factory) = self
+            .parsers
+            .iter()
+            .find(|(n, _)| n == name)
+            .with_context(|| {
+                format!(
+                    "Unsupported from_type: {}. Supported types are: {}",
+                    name,
+                    self.parser_types().join(", ")
+                )
+            })?;
+        factory(options)
+    }
+
+    /// Creates the generator registered under `name` for the given outline.
+    pub fn create_generator(
+        &self,
+        name: &str,
+        outline: Outline,
+        options: &OptionMap,
+    ) -> Result<Box<dyn Generator>> {
+        let (_, factory) = self
+            .generators
+            .iter()
+            .find(|(n, _)| n == name)
+            .with_context(|| {
+                format!(
+                    "Unsupported to_type: {}. Supported types are: {}",
+                    name,
+                    self.generator_types().join(", ")
+                )
+            })?;
+        factory(outline, options)
+    }
+
+    /// Creates the XLSX generator registered under `name` for the given outline.
+    pub fn create_xlsx_generator(
+        &self,
+        name: &str,
+        outline: Outline,
+        options: &OptionMap,
+    ) -> Result<Box<dyn XlsxGenerator>> {
+        let (_, factory) = self
+            .xlsx_generators
+            .iter()
+            .find(|(n, _)| n == name)
+            .with_context(|| {
+                format!(
+                    "to_type {} does not write a worksheet. Supported types are: {}",
+                    name,
+                    self.xlsx_generators
+                        .iter()
+                        .map(|(n, _)| n.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+        factory(outline, options)
+    }
+
+    /// Creates the stream parser registered under `name`.
+    pub fn create_stream_parser(
+        &self,
+        name: &str,
+        options: &OptionMap,
+    ) -> Result<Box<dyn StreamParser>> {
+        let (_, factory) = self
+            .stream_parsers
+            .iter()
+            .find(|(n, _)| n == name)
+            .with_context(|| {
+                format!(
+                    "Streaming is not supported for from_type: {}. Supported types are: {}",
+                    name,
+                    self.stream_parser_types().join(", ")
+                )
+            })?;
+        factory(options)
+    }
+
+    /// Creates the stream generator registered under `name` for an outline with the headers
+    /// of `header` and the size given by `summary`.
+    pub fn create_stream_generator(
+        &self,
+        name: &str,
+        header: Outline,
+        summary: OutlineSummary,
+        options: &OptionMap,
+    ) -> Result<Box<dyn XlsxStreamGenerator>> {
+        let (_, factory) = self
+            .stream_generators
+            .iter()
+            .find(|(n, _)| n == name)
+            .with_context(|| {
+                format!(
+                    "Streaming is not supported for to_type: {}. Supported types are: {}",
+                    name,
+                    self.stream_generator_types().join(", ")
+                )
+            })?;
+        factory(header, summary, options)
+    }
+
+    fn register_builtin(&mut self) {
+        self.register_parser("simple_text", |o| {
+            Ok(Box::new(SimpleTextParser::new(simple_text_options(o)?)))
+        });
+        self.register_parser("dir_tree", |o| {
+            Ok(Box::new(DirTreeParser::new(DirTreeParserOptions {
+                key_header: o.get_list("key-header"),
+                glob_pattern: Some(o.get("glob-pattern").unwrap_or("**/*").to_string()),
+                dir_indicator: o.get("dir-indicator").map(|s| s.to_string()),
+            })))
+        });
+        self.register_parser("html_list", |o| {
+            Ok(Box::new(HtmlListParser::new(HtmlListParserOptions {
+                key_header: o.get_list("key-header"),
+            })))
+        });
+        self.register_parser("mspdi", |o| {
+            Ok(Box::new(MspdiParser::new(mspdi_options(o))))
+        });
+        self.register_parser("opml", |o| Ok(Box::new(OpmlParser::new(opml_options(o)))));
+        self.register_parser("json", |_| Ok(Box::new(JsonParser::new())));
+        self.register_parser("markdown", |o| {
+            Ok(Box::new(MarkdownParser::new(MarkdownParserOptions {
+                structure: o
+                    .get_enum::<MarkdownStructure>("structure")?
+                    .unwrap_or_default(),
+                task_column: o.get_bool("task-column")?,
+                link_column: o.get_bool("link-column")?,
+                key_header: o.get_list("key-header"),
+                value_header: o.get_list("value-header"),
+            })))
+        });
+        for (name, format) in [
+            ("json_data", DataFormat::Json),
+            ("yaml", DataFormat::Yaml),
+            ("toml", DataFormat::Toml),
+        ] {
+            self.register_parser(name, move |o| {
+                Ok(Box::new(DataParser::new(DataParserOptions {
+                    format,
+                    key_field: o.get("key-field").map(str::to_string),
+                    value_fields: o.get_list("value-fields"),
+                    max_depth: o.get_parsed("max-depth")?,
+                    key_header: o.get_list("key-header"),
+                    value_header: o.get_list("value-header"),
+                })))
+            });
+        }
+        self.register_parser("csv_adjacency", |o| {
+            let defaults = CsvAdjacencyParserOptions::default();
+            Ok(Box::new(CsvAdjacencyParser::new(
+                CsvAdjacencyParserOptions {
+                    id_column: o
+                        .get("id-column")
+                        .map_or(defaults.id_column, str::to_string),
+                    parent_column: o
+                        .get("parent-column")
+                        .map_or(defaults.parent_column, str::to_string),
+                    key_column: o
+                        .get("key-column")
+                        .map_or(defaults.key_column, str::to_string),
+                    delimiter: csv_delimiter(o, b',')?,
+                    key_header: o.get_list("key-header"),
+                    value_header: o.get_list("value-header"),
+                },
+            )))
+        });
+        for (name, delimiter) in [("csv", b','), ("tsv", b'\t')] {
+            self.register_parser(name, move |o| {
+                let hierarchy = match o.get("path-column") {
+                    Some(column) => CsvHierarchy::Path {
+                        column: column.to_string(),
+                        separator: match o.get("path-separator") {
+                            None => "/".to_string(),
+                            Some("") => {
+                                bail!("Invalid path separator: expected a non-empty string")
+                            }
+                            Some(separator) => separator.to_string(),
+                        },
+                    },
+                    None => CsvHierarchy::Level {
+                        column: o.get("level-column").unwrap_or("2").to_string(),
+                    },
+                };
+                Ok(Box::new(CsvTableParser::new(CsvTableParserOptions {
+                    hierarchy,
+                    key_column: o.get("key-column").map(str::to_string),
+                    has_header: !o.get_bool("no-header")?,
+                    delimiter: csv_delimiter(o, delimiter)?,
+                    key_header: o.get_list("key-header"),
+                    value_header: o.get_list("value-header"),
+                })))
+            });
+        }
+
+        self.register_parser("xlsx", |o| {
+            Ok(Box::new(XlsxParser::new(XlsxParserOptions {
+                layout: o.get_enum::<XlsxLayout>("layout")?.unwrap_or_default(),
+                sheet: o.get("sheet").map(str::to_string),
+                key_column: o.get("key-column").map(str::to_string),
+                levels: o.get_parsed("levels")?,
+                has_header: !o.get_bool("no-header")?,
+                key_header: o.get_list("key-header"),
+                value_header: o.get_list("value-header"),
+            })))
+        });
+        self.register_parser("org", |o| {
+            Ok(Box::new(OrgParser::new(OrgParserOptions {
+                key_header: o.get_list("key-header"),
+                value_header: o.get_list("value-header"),
+            })))
+        });
+        self.register_parser("freemind", |o| {
+            Ok(Box::new(FreemindParser::new(FreemindParserOptions {
+                note_column: o.get_bool("note-column")?,
+                key_header: o.get_list("key-header"),
+                value_header: o.get_list("value-header"),
+            })))
+        });
+        self.register_parser("xmind", |o| {
+            Ok(Box::new(XmindParser::new(XmindParserOptions {
+                sheet: o.get("sheet").map(str::to_string),
+                label_column: o.get_bool("label-column")?,
+                marker_column: o.get_bool("marker-column")?,
+                note_column: o.get_bool("note-column")?,
+                key_header: o.get_list("key-header"),
+                value_header: o.get_list("value-header"),
+            })))
+        });
+        self.register_parser("text_tree", |o| {
+            Ok(Box::new(TextTreeParser::new(TextTreeParserOptions {
+                split_annotations: o.get_bool("split-annotations")?,
+                key_header: o.get_list("key-header"),
+                value_header: o.get_list("value-header"),
+            })))
+        });
+
+        self.register_xlsx_generator("xlsx_type0", |outline, o| {
+            Ok(Box::new(XlsxType0Generator::new(
+                outline,
+                xlsx_type0_options(o)?,
+            )))
+        });
+        self.register_xlsx_generator("xlsx_type1", |outline, o| {
+            Ok(Box::new(XlsxType1Generator::new(
+                outline,
+                xlsx_type1_options(o)?,
+            )))
+        });
+        self.register_xlsx_generator("xlsx_type2", |outline, o| {
+            Ok(Box::new(XlsxType2Generator::new(
+                outline,
+                xlsx_type2_options(o)?,
+            )))
+        });
+        self.register_xlsx_generator("xlsx_type3", |outline, o| {
+            Ok(Box::new(XlsxType3Generator::new(
+                outline,
+                XlsxType3GeneratorOptions {
+                    outline_rows: o.get_bool("outline-rows")?,
+                    integrate_cells: o.get_enum::<IntegrateCellsOption>("integrate-cells")?,
+                    shironuri: o.get_bool("shironuri")?,
+                },
+            )))
+        });
+        self.register_xlsx_generator("xlsx_type4", |outline, o| {
+            Ok(Box::new(XlsxType4Generator::new(
+                outline,
+                XlsxType4GeneratorOptions {
+                    integrate_cells: o.get_enum::<IntegrateCellsOption>("integrate-cells")?,
+                    shironuri: o.get_bool("shironuri")?,
+                },
+            )))
+        });
+        self.register_xlsx_generator("xlsx_type5", |outline, o| {
+            Ok(Box::new(XlsxType5Generator::new(
+                outline,
+                XlsxType5GeneratorOptions {
+                    integrate_cells: o.get_enum::<IntegrateCellsOption>("integrate-cells")?,
+                    shironuri: o.get_bool("shironuri")?,
+                },
+            )))
+        });
+        self.register_generator("json", |outline, o| {
+            Ok(Box::new(JsonGenerator::new(
+                outline,
+                JsonGeneratorOptions {
+                    pretty: o.get_bool("pretty")?,
+                },
+            )))
+        });
+
+        self.register_stream_parser("simple_text", |o| {
+            Ok(Box::new(SimpleTextParser::new(simple_text_options(o)?)))
+        });
+        self.register_stream_parser("mspdi", |o| {
+            Ok(Box::new(MspdiParser::new(mspdi_options(o))))
+        });
+        self.register_stream_parser("opml", |o| Ok(Box::new(OpmlParser::new(opml_options(o)))));
+
+        self.register_stream_generator("xlsx_type0", |header, summary, o| {
+            Ok(Box::new(XlsxType0StreamGenerator::new(
+                header,
+                summary,
+                xlsx_type0_options(o)?,
+            )))
+        });
+        self.register_stream_generator("xlsx_type1", |header, summary, o| {
+            Ok(Box::new(XlsxType1StreamGenerator::new(
+                header,
+                summary,
+                xlsx_type1_options(o)?,
+            )))
+        });
+        self.register_stream_generator("xlsx_type2", |header, summary, o| {
+            let options = xlsx_type2_options(o)?;
+            if options.integrate_cells == Some(IntegrateCellsOption::Rowspan) {
+                bail!("integrate-cells=rowspan is not supported in streaming mode");
+            }
+            Ok(Box::new(XlsxType2StreamGenerator::new(
+                header, summary, options,
+            )))
+        });
+    }
+}
+
+fn simple_text_options(o: &OptionMap) -> Result<SimpleTextParserOptions> {
+    Ok(SimpleTextParserOptions {
+        indent: o.get("indent").unwrap_or("\t").to_string(),
+        auto_indent: o.get_bool("auto-indent")?,
+        tab_width: o.get_parsed("tab-width")?.unwrap_or(4),
+        delimiter: o.get("delimiter").map(|s| s.to_string()),
+        preserve_empty_line: o.get_bool("preserve-empty-line")?,
+        key_header: o.get_list("key-header"),
+        value_header: o.get_list("value-header"),
+    })
+}
+
+/// Returns the `delimiter` option as a CSV field delimiter: a single ASCII character, or
+/// `\t` for tabs.
+fn csv_delimiter(o: &OptionMap, default: u8) -> Result<u8> {
+    match o.get("delimiter") {
+        None => Ok(default),
+        Some("\\t") => Ok(b'\t'),
+        Some(d) if d.len() == 1 && d.is_ascii() => Ok(d.as_bytes()[0]),
+        Some(d) => bail!(
+            "Invalid CSV delimiter '{}': expected a single ASCII character",
+            d
+        ),
+    }
+}
+
+fn mspdi_options(o: &OptionMap) -> MspdiParserOptions {
+    MspdiParserOptions {
+        key_header: o.get_list("key-header"),
+        value_header: o.get_list("value-header"),
+    }
+}
+
+fn opml_options(o: &OptionMap) -> OpmlParserOptions {
+    OpmlParserOptions {
+        key_header: o.get_list("key-header"),
+        value_header: o.get_list("value-header"),
+    }
+}
+
+fn xlsx_type0_options(o: &OptionMap) -> Result<XlsxType0GeneratorOptions> {
+    Ok(XlsxType0GeneratorOptions {
+        shironuri: o.get_bool("shironuri")?,
+    })
+}
+
+fn xlsx_type1_options(o: &OptionMap) -> Result<XlsxType1GeneratorOptions> {
+    Ok(XlsxType1GeneratorOptions {
+        outline_rows: o.get_bool("outline-rows")?,
+        shironuri: o.get_bool("shironuri")?,
+    })
+}
+
+fn xlsx_type2_options(o: &OptionMap) -> Result<XlsxType2GeneratorOptions> {
+    Ok(XlsxType2GeneratorOptions {
+        outline_rows: o.get_bool("outline-rows")?,
+        integrate_cells: o.get_enum::<IntegrateCellsOption>("integrate-cells")?,
+        shironuri: o.get_bool("shironuri")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    struct UpperCaseParser;
+
+    impl Parser for UpperCaseParser {
+        fn parse_input(&self, _input_path: Option<&Path>) -> Result<Outline> {
+            let mut outline = Outline::new();
+            outline.add_item("A", 1, vec![]);
+            Ok(outline)
+        }
+    }
+
+    #[test]
+    fn test_option_map_getters() -> Result<()> {
+        let mut options = OptionMap::new();
+        options.set("key-header", "H1, H2,H3");
+        options.set("shironuri", "yes");
+        options.set("integrate-cells", "colspan");
+        options.set("tab-width", "4");
+
+        assert_eq!(options.get_list("key-header"), vec!["H1", "H2", "H3"]);
+        assert!(options.get_list("value-header").is_empty());
+        assert!(options.get_bool("shironuri")?);
+        assert!(!options.get_bool("outline-rows")?);
+        assert_eq!(
+            options.get_enum::<IntegrateCellsOption>("integrate-cells")?,
+            Some(IntegrateCellsOption::Colspan)
+        );
+        assert_eq!(options.get_parsed::<usize>("tab-width")?, Some(4));
+
+        options.set("shironuri", "maybe");
+        assert!(options.get_bool("shironuri").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_builtin_types() {
+        let registry = Registry::default();
+        assert_eq!(
+            registry.parser_types(),
+            vec![
+                "simple_text",
+                "dir_tree",
+                "html_list",
+                "mspdi",
+                "opml",
+                "json",
+                "markdown",
+                "json_data",
+                "yaml",
+                "toml",
+                "csv_adjacency",
+                "csv",
+                "tsv",
+                "xlsx",
+                "org",
+                "freemind",
+                "xmind",
+                "text_tree"
+            ]
+        );
+        assert_eq!(
+            registry.generator_types(),
+            vec![
+                "xlsx_type0",
+                "xlsx_type1",
+                "xlsx_type2",
+                "xlsx_type3",
+                "xlsx_type4",
+                "xlsx_type5",
+                "json"
+            ]
+        );
+        assert!(registry
+            .create_parser("unknown", &OptionMap::new())
+            .is_err());
+        assert!(registry
+            .create_generator("unknown", Outline::new(), &OptionMap::new())
+            .is_err());
+
+        assert!(registry
+            .create_xlsx_generator("xlsx_type3", Outline::new(), &OptionMap::new())
+            .is_ok());
+        assert!(registry
+            .create_xlsx_generator("json", Outline::new(), &OptionMap::new())
+            .is_err());
+
+        assert_eq!(
+            registry.stream_parser_types(),
+            vec!["simple_text", "mspdi", "opml"]
+        );
+        assert_eq!(
+            registry.stream_generator_types(),
+            vec!["xlsx_type0", "xlsx_type1", "xlsx_type2"]
+        );
+        assert!(registry
+            .create_stream_parser("dir_tree", &OptionMap::new())
+            .is_err());
+        let mut options = OptionMap::new();
+        options.set("path-column", "1");
+        options.set("path-separator", "");
+        assert!(registry.create_parser("csv", &options).is_err());
+        options.set("path-separator", "::");
+        assert!(registry.create_parser("csv", &options).is_ok());
+
+        let mut options = OptionMap::new();
+        options.set("integrate-cells", "rowspan");
+        assert!(registry
+            .create_stream_generator(
+                "xlsx_type2",
+                Outline::new(),
+                OutlineSummary::default(),
+                &options
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_registry_register_parser() -> Result<()> {
+        let mut registry = Registry::default();
+        registry.register_parser("upper", |_| Ok(Box::new(UpperCaseParser)));
+        assert_eq!(registry.parser_types().last().unwrap(), "upper");
+
+        let parser = registry.create_parser("upper", &OptionMap::new())?;
+        let outline = parser.parse_input(None)?;
+        assert_eq!(outline.item[0].key, "A");
+        Ok(())
+    }
+}
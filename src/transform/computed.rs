@@ -0,0 +1,176 @@
+use crate::outline::{NodeId, NodeRef, Outline};
+use crate::transform::Transform;
+use crate::value::Value;
+use anyhow::Result;
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+/// A value column derived from the position of each item in the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ComputedColumn {
+    /// Hierarchical numbering, e.g. `1.2.1`.
+    Wbs,
+    /// The keys of the item and its ancestors, from level 1 down, joined by a separator.
+    Path,
+    /// The depth of the item in the tree, 1 for top-level items.
+    Depth,
+    /// The number of children.
+    Children,
+    /// The number of descendants without children.
+    Leaves,
+    /// `leaf` for items without children, `branch` for the others.
+    Kind,
+}
+
+impl ComputedColumn {
+    /// Returns the value header of the column.
+    pub fn header(&self) -> &'static str {
+        match self {
+            ComputedColumn::Wbs => "WBS",
+            ComputedColumn::Path => "Path",
+            ComputedColumn::Depth => "Depth",
+            ComputedColumn::Children => "Children",
+            ComputedColumn::Leaves => "Leaves",
+            ComputedColumn::Kind => "Kind",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ComputedColumnsTransformOptions {
+    /// The columns added, in order.
+    pub columns: Vec<ComputedColumn>,
+    /// The separator of the keys of `ComputedColumn::Path`.
+    pub path_separator: String,
+}
+
+impl Default for ComputedColumnsTransformOptions {
+    fn default() -> Self {
+        ComputedColumnsTransformOptions {
+            columns: Vec::new(),
+            path_separator: "/".to_string(),
+        }
+    }
+}
+
+/// Adds value columns computed from the tree after the existing value columns.
+pub struct ComputedColumnsTransform {
+    options: ComputedColumnsTransformOptions,
+}
+
+impl ComputedColumnsTransform {
+    pub fn new(options: ComputedColumnsTransformOptions) -> Self {
+        ComputedColumnsTransform { options }
+    }
+
+    /// Returns the value of `column` for `node`, given `wbs`, its hierarchical number.
+    fn value(&self, column: ComputedColumn, node: NodeRef, wbs: &str) -> Value {
+        // The node and its ancestors, from level 1 down
+        let mut lineage: Vec<NodeRef> = std::iter::once(node)
+            .chain(node.ancestors())
+            .filter(|n| !n.is_root())
+            .collect();
+        lineage.reverse();
+        match column {
+            ComputedColumn::Wbs => Value::from(wbs),
+            ComputedColumn::Path => Value::Text(
+                lineage
+                    .iter()
+                    .filter_map(|n| n.item().map(|item| item.key.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(&self.options.path_separator),
+            ),
+            ComputedColumn::Depth => Value::Integer(lineage.len() as i64),
+            ComputedColumn::Children => Value::Integer(node.children().len() as i64),
+            ComputedColumn::Leaves => {
+                Value::Integer(node.descendants().filter(|n| n.is_leaf()).count() as i64)
+            }
+            ComputedColumn::Kind => Value::from(if node.is_leaf() { "leaf" } else { "branch" }),
+        }
+    }
+}
+
+impl Transform for ComputedColumnsTransform {
+    fn apply(&self, mut outline: Outline) -> Result<Outline> {
+        if self.options.columns.is_empty() {
+            return Ok(outline);
+        }
+        let value_length = outline.max_value_length();
+        let tree = outline.to_tree();
+        // The nodes of the tree are in the order of the items
+        let nodes: Vec<NodeRef> = tree.root().descendants().collect();
+        // Hierarchical numbers are built from the parent's, which comes first in pre-order
+        let mut numbers: HashMap<NodeId, String> = HashMap::new();
+        let mut child_counts: HashMap<NodeId, usize> = HashMap::new();
+        for (item, node) in outline.item.iter_mut().zip(nodes) {
+            let parent = node.parent().expect("items have a parent").id();
+            let count = child_counts.entry(parent).or_default();
+            *count += 1;
+            let wbs = match numbers.get(&parent) {
+                Some(parent_number) => format!("{}.{}", parent_number, count),
+                None => count.to_string(),
+            };
+            item.value.resize(value_length, Value::default());
+            for &column in &self.options.columns {
+                item.value.push(self.value(column, node, &wbs));
+            }
+            numbers.insert(node.id(), wbs);
+        }
+        outline.value_header.resize(value_length, String::new());
+        outline.value_header.extend(
+            self.options
+                .columns
+                .iter()
+                .map(|column| column.header().to_string()),
+        );
+        Ok(outline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_computed_columns_transform() -> Result<()> {
+        let mut outline = Outline::new();
+        outline.value_header = vec!["Owner".to_string()];
+        outline.add_item("A", 1, vec!["x".to_string(), "y".to_string()]);
+        outline.add_item("A1", 2, vec![]);
+        outline.add_item("A2", 2, vec![]);
+        outline.add_item("A2a", 3, vec![]);
+        outline.add_item("B", 1, vec![]);
+
+        let transform = ComputedColumnsTransform::new(ComputedColumnsTransformOptions {
+            columns: vec![
+                ComputedColumn::Wbs,
+                ComputedColumn::Path,
+                ComputedColumn::Depth,
+                ComputedColumn::Children,
+                ComputedColumn::Leaves,
+                ComputedColumn::Kind,
+            ],
+            path_separator: " > ".to_string(),
+        });
+        let outline = transform.apply(outline)?;
+        assert_eq!(
+            outline.value_header,
+            vec!["Owner", "", "WBS", "Path", "Depth", "Children", "Leaves", "Kind"]
+        );
+        let row = |index: usize| -> Vec<String> {
+            outline.item[index]
+                .value
+                .iter()
+                .map(|v| v.to_string())
+                .collect()
+        };
+        assert_eq!(row(0), vec!["x", "y", "1", "A", "1", "2", "2", "branch"]);
+        assert_eq!(
+            row(3),
+            vec!["", "", "1.2.1", "A > A2 > A2a", "3", "0", "0", "leaf"]
+        );
+        assert_eq!(row(4)[2], "2");
+        assert_eq!(outline.item[1].value[4], Value::Integer(2));
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+//! Transformations applied to an outline between parsing and generation.
+//! 
+//! Each transformation takes the parsed `Outline` and returns a modified one,
+//! independently of the input and output types.
+
+pub mod computed;
+pub mod rollup;
+pub mod sort;
+pub mod subtree;
+pub mod validate;
+pub mod value_type;
+
+use crate::outline::{Outline, OutlineItem};
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// A transformation applied to an `Outline` before it is handed to a generator.
+pub trait Transform {
+    /// Applies the transformation to `outline` and returns the result.
+    fn apply(&self, outline: Outline) -> Result<Outline>;
+}
+
+/// A transformation that changes each item independently of the others.
+///
+/// Unlike a [`Transform`], it can also be applied in streaming mode, where the items are
+/// never held in memory together.
+pub trait ItemTransform {
+    /// Applies the transformation to `item` of an outline with the headers of `header`.
+    fn apply_item(&self, header: &Outline, item: OutlineItem) -> Result<OutlineItem>;
+}
+
+/// A value column selected on the command line, by number or by value header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueColumn {
+    /// A value column by 0-based index.
+    Index(usize),
+    /// A value column by value header.
+    Header(String),
+}
+
+impl ValueColumn {
+    /// Returns the 0-based index of the column in `outline`.
+    pub fn index(&self, outline: &Outline) -> Result<usize> {
+        match self {
+            ValueColumn::Index(index) => Ok(*index),
+            ValueColumn::Header(name) => outline
+                .value_header
+                .iter()
+                .position(|header| header == name)
+                .ok_or_else(|| anyhow!("No value column '{}'", name)),
+        }
+    }
+}
+
+impl FromStr for ValueColumn {
+    type Err = String;
+
+    /// Reads a 1-based value column number or a value header.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(0) => Err("value columns are numbered from 1".to_string()),
+            Ok(n) => Ok(ValueColumn::Index(n - 1)),
+            Err(_) => Ok(ValueColumn::Header(s.to_string())),
+        }
+    }
+}
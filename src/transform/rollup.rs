@@ -0,0 +1,226 @@
+use crate::outline::{NodeRef, Outline};
+use crate::transform::{Transform, ValueColumn};
+use crate::value::Value;
+use anyhow::Result;
+use chrono::TimeDelta;
+use clap::ValueEnum;
+
+/// How the values of the descendant leaves of an item are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+    /// The number of leaves with a numeric value.
+    Count,
+    Average,
+}
+
+impl Aggregate {
+    /// Combines `values`, all numeric.
+    ///
+    /// Integers and durations are kept as such when all values are of that type, except for
+    /// averages of integers and sums of integers out of the range of `i64`. Returns `None` if there is no value to combine, except for
+    /// `Count`.
+    fn apply(&self, values: &[Value]) -> Option<Value> {
+        if *self == Aggregate::Count {
+            return Some(Value::Integer(values.len() as i64));
+        }
+        if values.is_empty() {
+            return None;
+        }
+        if values.iter().all(|v| matches!(v, Value::Integer(_))) {
+            let mut integers = values.iter().filter_map(|v| match v {
+                Value::Integer(i) => Some(*i),
+                _ => None,
+            });
+            let integer = match self {
+                // Sums out of the range of i64 are combined as numbers
+                Aggregate::Sum => match integers.try_fold(0i64, i64::checked_add) {
+                    Some(sum) => sum,
+                    None => return Some(Value::Number(self.combine(values))),
+                },
+                Aggregate::Min => integers.min()?,
+                Aggregate::Max => integers.max()?,
+                _ => return Some(Value::Number(self.combine(values))),
+            };
+            return Some(Value::Integer(integer));
+        }
+        let number = self.combine(values);
+        if values.iter().all(|v| matches!(v, Value::Duration(_))) {
+            // Durations are combined in seconds
+            return Some(Value::Duration(TimeDelta::milliseconds(
+                (number * 1000.0).round() as i64,
+            )));
+        }
+        Some(Value::Number(number))
+    }
+
+    fn combine(&self, values: &[Value]) -> f64 {
+        let numbers = values.iter().filter_map(Value::as_f64);
+        match self {
+            Aggregate::Sum => numbers.sum(),
+            Aggregate::Min => numbers.fold(f64::INFINITY, f64::min),
+            Aggregate::Max => numbers.fold(f64::NEG_INFINITY, f64::max),
+            Aggregate::Count => values.len() as f64,
+            Aggregate::Average => numbers.sum::<f64>() / values.len() as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RollupTransformOptions {
+    /// The value columns to fill, with how each is aggregated.
+    pub columns: Vec<(ValueColumn, Aggregate)>,
+}
+
+/// Fills the empty values of items with children with an aggregate of the values of their
+/// descendant leaves.
+///
+/// Only the leaves are aggregated, so that the values of intermediate items are not counted
+/// twice. Text values are read as numbers when their type can be inferred as numeric;
+/// other values are ignored. Values already set on items with children are kept.
+pub struct RollupTransform {
+    options: RollupTransformOptions,
+}
+
+impl RollupTransform {
+    pub fn new(options: RollupTransformOptions) -> Self {
+        RollupTransform { options }
+    }
+}
+
+impl Transform for RollupTransform {
+    fn apply(&self, mut outline: Outline) -> Result<Outline> {
+        let columns = self
+            .options
+            .columns
+            .iter()
+            .map(|(column, aggregate)| Ok((column.index(&outline)?, *aggregate)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let tree = outline.to_tree();
+        // The nodes of the tree are in the order of the items
+        let nodes: Vec<NodeRef> = tree.root().descendants().collect();
+        for (item, node) in outline.item.iter_mut().zip(nodes) {
+            if node.is_leaf() {
+                continue;
+            }
+            for &(index, aggregate) in &columns {
+                if item.value.get(index).is_some_and(|v| !v.is_empty()) {
+                    continue;
+                }
+                let values: Vec<Value> = node
+                    .descendants()
+                    .filter(|n| n.is_leaf())
+                    .filter_map(|n| n.item()?.value.get(index))
+                    .map(Value::inferred)
+                    .filter(|v| v.as_f64().is_some())
+                    .collect();
+                if let Some(value) = aggregate.apply(&values) {
+                    if item.value.len() <= index {
+                        item.value.resize(index + 1, Value::default());
+                    }
+                    item.value[index] = value;
+                }
+            }
+        }
+        Ok(outline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wbs() -> Outline {
+        let mut outline = Outline::new();
+        outline.value_header = vec!["Cost".to_string(), "Effort".to_string()];
+        outline.add_item("Project", 1, vec![]);
+        outline.add_item("Design", 2, vec![]);
+        outline.add_item("Sketch", 3, vec!["10".into(), "PT2H".into()]);
+        outline.add_item("Review", 3, vec!["2.5".into(), "PT30M".into()]);
+        outline.add_item("Build", 2, vec!["7".into(), "n/a".into()]);
+        outline
+    }
+
+    fn rollup(columns: Vec<(&str, Aggregate)>) -> Result<Outline> {
+        let transform = RollupTransform::new(RollupTransformOptions {
+            columns: columns
+                .into_iter()
+                .map(|(column, aggregate)| (column.parse().unwrap(), aggregate))
+                .collect(),
+        });
+        transform.apply(wbs())
+    }
+
+    #[test]
+    fn test_rollup_transform() -> Result<()> {
+        let outline = rollup(vec![("Cost", Aggregate::Sum), ("2", Aggregate::Sum)])?;
+        assert_eq!(
+            outline.item[0].value,
+            vec![
+                Value::Number(19.5),
+                Value::Duration(TimeDelta::minutes(150))
+            ]
+        );
+        assert_eq!(
+            outline.item[1].value,
+            vec![
+                Value::Number(12.5),
+                Value::Duration(TimeDelta::minutes(150))
+            ]
+        );
+        // Leaves are kept as they are
+        assert_eq!(outline.item[2].value, vec!["10", "PT2H"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollup_transform_aggregates() -> Result<()> {
+        let values = |aggregate| -> Result<Value> {
+            Ok(rollup(vec![("Cost", aggregate)])?.item[0].value[0].clone())
+        };
+        assert_eq!(values(Aggregate::Min)?, Value::Number(2.5));
+        assert_eq!(values(Aggregate::Max)?, Value::Number(10.0));
+        assert_eq!(values(Aggregate::Count)?, Value::Integer(3));
+        assert_eq!(values(Aggregate::Average)?, Value::Number(6.5));
+
+        let mut outline = wbs();
+        outline.item[3].value[0] = "3".into();
+        let transform = RollupTransform::new(RollupTransformOptions {
+            columns: vec![("Cost".parse().unwrap(), Aggregate::Max)],
+        });
+        assert_eq!(
+            transform.apply(outline)?.item[0].value[0],
+            Value::Integer(10)
+        );
+
+        let mut outline = wbs();
+        outline.item[2].value[0] = Value::Integer(i64::MAX);
+        outline.item[3].value[0] = Value::Integer(1);
+        outline.item[4].value[0] = Value::Integer(1);
+        let transform = RollupTransform::new(RollupTransformOptions {
+            columns: vec![("Cost".parse().unwrap(), Aggregate::Sum)],
+        });
+        assert_eq!(
+            transform.apply(outline)?.item[0].value[0],
+            Value::Number(i64::MAX as f64 + 2.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollup_transform_keeps_set_values() -> Result<()> {
+        let mut outline = wbs();
+        outline.item[1].value = vec!["100".into()];
+        let transform = RollupTransform::new(RollupTransformOptions {
+            columns: vec![(ValueColumn::Index(0), Aggregate::Sum)],
+        });
+        let outline = transform.apply(outline)?;
+        assert_eq!(outline.item[1].value, vec!["100"]);
+        assert_eq!(outline.item[0].value, vec![Value::Number(19.5)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use crate::outline::{NodeRef, Outline, OutlineItem};
+use crate::transform::{Transform, ValueColumn};
+use crate::value::Value;
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use icu_collator::{Collator, CollatorOptions, Numeric};
+use icu_locid::Locale;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// How the sort keys of sibling items are compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SortOrder {
+    /// Compare the text character by character (by Unicode code point).
+    Text,
+    /// Compare as numbers. Items without a numeric sort key come last, in their original order.
+    Numeric,
+    /// Compare with the collation rules of a locale, and runs of digits by their numeric value
+    /// (`item2` before `item10`).
+    #[default]
+    Natural,
+}
+
+/// The column of an item used as its sort key.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SortBy {
+    /// The key of the item.
+    #[default]
+    Key,
+    /// A value column.
+    Value(ValueColumn),
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    /// Reads `key`, a 1-based value column number or a value header.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "key" => Ok(SortBy::Key),
+            _ => s.parse().map(SortBy::Value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SortTransformOptions {
+    /// The sort key of each item.
+    pub by: SortBy,
+    /// How sort keys are compared.
+    pub order: SortOrder,
+    /// Sorts in descending order.
+    pub descending: bool,
+    /// The locale of `SortOrder::Natural` (e.g. `en`, `ja`, `sv`), or `None` for the root
+    /// collation.
+    pub locale: Option<String>,
+}
+
+/// Reorders the children of each item, keeping their descendants attached.
+///
+/// The sort is stable: siblings with equal sort keys keep their original order.
+pub struct SortTransform {
+    options: SortTransformOptions,
+}
+
+impl SortTransform {
+    pub fn new(options: SortTransformOptions) -> Self {
+        SortTransform { options }
+    }
+
+    /// Returns the index of the sort value column, or `None` to sort by key.
+    fn column(&self, outline: &Outline) -> Result<Option<usize>> {
+        match &self.options.by {
+            SortBy::Key => Ok(None),
+            SortBy::Value(column) => column.index(outline).map(Some),
+        }
+    }
+
+    fn collator(&self) -> Result<Collator> {
+        let locale = match &self.options.locale {
+            Some(locale) => Locale::from_str(locale)
+                .map_err(|e| anyhow!("Invalid locale '{}': {}", locale, e))?,
+            None => Locale::UND,
+        };
+        let mut options = CollatorOptions::new();
+        options.numeric = Some(Numeric::On);
+        Collator::try_new(&(&locale).into(), options)
+            .map_err(|e| anyhow!("Cannot sort for locale '{}': {}", locale, e))
+    }
+}
+
+impl Transform for SortTransform {
+    fn apply(&self, mut outline: Outline) -> Result<Outline> {
+        let column = self.column(&outline)?;
+        let collator = match self.options.order {
+            SortOrder::Natural => Some(self.collator()?),
+            _ => None,
+        };
+        let empty = Value::default();
+        let sort_value = |node: &NodeRef| -> Value {
+            let item = node.item().expect("children are items");
+            match column {
+                None => Value::Text(item.key.clone()),
+                Some(index) => item.value.get(index).unwrap_or(&empty).clone(),
+            }
+        };
+        let compare = |a: &Value, b: &Value| -> Ordering {
+            let ordering = match (self.options.order, &collator) {
+                (SortOrder::Numeric, _) => match (a.inferred().as_f64(), b.inferred().as_f64()) {
+                    (Some(a), Some(b)) => a.total_cmp(&b),
+                    // Non-numeric keys stay last in both directions
+                    (a, b) => return a.is_none().cmp(&b.is_none()),
+                },
+                (_, Some(collator)) => collator.compare(&a.to_string(), &b.to_string()),
+                _ => a.to_string().cmp(&b.to_string()),
+            };
+            if self.options.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+
+        let tree = outline.to_tree();
+        outline.item.clear();
+        let mut stack = vec![tree.root()];
+        let mut sorted: Vec<OutlineItem> = Vec::new();
+        while let Some(node) = stack.pop() {
+            if let Some(item) = node.item() {
+                sorted.push(item.clone());
+            }
+            let mut children: Vec<(Value, NodeRef)> =
+                node.children().map(|c| (sort_value(&c), c)).collect();
+            children.sort_by(|(a, _), (b, _)| compare(a, b));
+            // Pushed in reverse so that the first child is visited next
+            stack.extend(children.into_iter().rev().map(|(_, c)| c));
+        }
+        outline.item = sorted;
+        Ok(outline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(outline: &Outline) -> Vec<&str> {
+        outline.item.iter().map(|item| item.key.as_str()).collect()
+    }
+
+    fn sort(outline: Outline, options: SortTransformOptions) -> Result<Outline> {
+        SortTransform::new(options).apply(outline)
+    }
+
+    #[test]
+    fn test_sort_by_key_keeps_subtrees() -> Result<()> {
+        let mut outline = Outline::new();
+        outline.add_item("b", 1, vec![]);
+        outline.add_item("b2", 2, vec![]);
+        outline.add_item("b1", 2, vec![]);
+        outline.add_item("a", 1, vec![]);
+        outline.add_item("a1", 2, vec![]);
+
+        let sorted = sort(outline.clone(), SortTransformOptions::default())?;
+        assert_eq!(keys(&sorted), vec!["a", "a1", "b", "b1", "b2"]);
+        assert_eq!(sorted.item[1].level, 2);
+
+        let sorted = sort(
+            outline,
+            SortTransformOptions {
+                descending: true,
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(keys(&sorted), vec!["b", "b2", "b1", "a", "a1"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_natural_and_text() -> Result<()> {
+        let mut outline = Outline::new();
+        for key in ["item10", "Item2", "item1", "éclair", "eclipse"] {
+            outline.add_item(key, 1, vec![]);
+        }
+
+        let sorted = sort(outline.clone(), SortTransformOptions::default())?;
+        assert_eq!(
+            keys(&sorted),
+            vec!["éclair", "eclipse", "item1", "Item2", "item10"]
+        );
+
+        let sorted = sort(
+            outline,
+            SortTransformOptions {
+                order: SortOrder::Text,
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(
+            keys(&sorted),
+            vec!["Item2", "eclipse", "item1", "item10", "éclair"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_by_locale() -> Result<()> {
+        let mut outline = Outline::new();
+        for key in ["ö", "z", "a"] {
+            outline.add_item(key, 1, vec![]);
+        }
+        let sorted = sort(
+            outline.clone(),
+            SortTransformOptions {
+                locale: Some("de".to_string()),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(keys(&sorted), vec!["a", "ö", "z"]);
+
+        let sorted = sort(
+            outline,
+            SortTransformOptions {
+                locale: Some("sv".to_string()),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(keys(&sorted), vec!["a", "z", "ö"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_numeric_by_value_column() -> Result<()> {
+        let mut outline = Outline::new();
+        outline.value_header = vec!["Name".to_string(), "Cost".to_string()];
+        outline.add_item("a", 1, vec!["x".into(), "10".into()]);
+        outline.add_item("b", 1, vec!["x".into(), "n/a".into()]);
+        outline.add_item("c", 1, vec!["x".into(), "9.5".into()]);
+        outline.add_item("d", 1, vec!["x".into(), "100".into()]);
+
+        let options = SortTransformOptions {
+            by: "Cost".parse().unwrap(),
+            order: SortOrder::Numeric,
+            ..Default::default()
+        };
+        let sorted = sort(outline.clone(), options.clone())?;
+        assert_eq!(keys(&sorted), vec!["c", "a", "d", "b"]);
+
+        let sorted = sort(
+            outline.clone(),
+            SortTransformOptions {
+                by: "2".parse().unwrap(),
+                descending: true,
+                ..options
+            },
+        )?;
+        assert_eq!(keys(&sorted), vec!["d", "a", "c", "b"]);
+
+        let err = sort(
+            outline,
+            SortTransformOptions {
+                by: "Owner".parse().unwrap(),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "No value column 'Owner'");
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use crate::outline::{NodeRef, Outline, OutlineItem};
+use crate::transform::Transform;
+use anyhow::{bail, Result};
+use regex::Regex;
+
+/// Appends the items of the descendants of `node` to `items` in order, skipping the
+/// subtrees for which `keep` returns false.
+fn collect_items(node: NodeRef, keep: &dyn Fn(&OutlineItem) -> bool, items: &mut Vec<OutlineItem>) {
+    for child in node.children() {
+        if let Some(item) = child.item().filter(|item| keep(item)) {
+            items.push(item.clone());
+            collect_items(child, keep, items);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MaxLevelTransformOptions {
+    /// The deepest level kept.
+    pub max_level: u32,
+}
+
+/// Keeps only the items up to a maximum level.
+pub struct MaxLevelTransform {
+    options: MaxLevelTransformOptions,
+}
+
+impl MaxLevelTransform {
+    pub fn new(options: MaxLevelTransformOptions) -> Self {
+        MaxLevelTransform { options }
+    }
+}
+
+impl Transform for MaxLevelTransform {
+    fn apply(&self, mut outline: Outline) -> Result<Outline> {
+        let max_level = self.options.max_level;
+        let tree = outline.to_tree();
+        outline.item.clear();
+        collect_items(
+            tree.root(),
+            &|item| item.level <= max_level,
+            &mut outline.item,
+        );
+        outline.key_header.truncate(max_level as usize);
+        Ok(outline)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SelectSubtreeTransformOptions {
+    /// The keys of the selected item and its ancestors, from level 1 down.
+    pub path: Vec<String>,
+}
+
+/// Keeps only the subtree of the item at a key path, moved up so that it is at level 1.
+pub struct SelectSubtreeTransform {
+    options: SelectSubtreeTransformOptions,
+}
+
+impl SelectSubtreeTransform {
+    pub fn new(options: SelectSubtreeTransformOptions) -> Self {
+        SelectSubtreeTransform { options }
+    }
+
+    /// Returns the first descendant of `node` reached through children with the keys of
+    /// `path`, in order.
+    fn find<'a>(node: NodeRef<'a>, path: &[String]) -> Option<NodeRef<'a>> {
+        let Some((key, rest)) = path.split_first() else {
+            return Some(node);
+        };
+        node.children()
+            .filter(|child| child.item().is_some_and(|item| &item.key == key))
+            .find_map(|child| Self::find(child, rest))
+    }
+}
+
+impl Transform for SelectSubtreeTransform {
+    fn apply(&self, mut outline: Outline) -> Result<Outline> {
+        let tree = outline.to_tree();
+        let node = match Self::find(tree.root(), &self.options.path) {
+            Some(node) if !node.is_root() => node,
+            _ => bail!("No item at path '{}'", self.options.path.join("/")),
+        };
+
+        // Levels (and the key headers of the levels) above the selected item are removed
+        let offset = node.item().map_or(0, |item| item.level - 1);
+        outline.item = std::iter::once(node)
+            .chain(node.descendants())
+            .filter_map(|n| n.item())
+            .map(|item| OutlineItem {
+                level: item.level - offset,
+                ..item.clone()
+            })
+            .collect();
+        outline
+            .key_header
+            .drain(..(offset as usize).min(outline.key_header.len()));
+        Ok(outline)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DropSubtreeTransformOptions {
+    /// Items whose key matches are dropped with their descendants.
+    pub pattern: Regex,
+}
+
+/// Drops the subtrees of the items whose key matches a regular expression.
+pub struct DropSubtreeTransform {
+    options: DropSubtreeTransformOptions,
+}
+
+impl DropSubtreeTransform {
+    pub fn new(options: DropSubtreeTransformOptions) -> Self {
+        DropSubtreeTransform { options }
+    }
+}
+
+impl Transform for DropSubtreeTransform {
+    fn apply(&self, mut outline: Outline) -> Result<Outline> {
+        let tree = outline.to_tree();
+        outline.item.clear();
+        collect_items(
+            tree.root(),
+            &|item| !self.options.pattern.is_match(&item.key),
+            &mut outline.item,
+        );
+        Ok(outline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn org_chart() -> Outline {
+        let mut outline = Outline::new();
+        outline.key_header = vec!["L1".into(), "L2".into(), "L3".into()];
+        outline.add_item("President", 1, vec![]);
+        outline.add_item("VP Marketing", 2, vec![]);
+        outline.add_item("Manager A", 3, vec![]);
+        outline.add_item("VP Sales", 2, vec![]);
+        outline.add_item("Manager B", 3, vec!["b".into()]);
+        outline.add_item("Manager C", 3, vec![]);
+        outline
+    }
+
+    fn levels(outline: &Outline) -> Vec<(&str, u32)> {
+        outline
+            .item
+            .iter()
+            .map(|item| (item.key.as_str(), item.level))
+            .collect()
+    }
+
+    #[test]
+    fn test_max_level_transform() -> Result<()> {
+        let transform = MaxLevelTransform::new(MaxLevelTransformOptions { max_level: 2 });
+        let outline = transform.apply(org_chart())?;
+        assert_eq!(
+            levels(&outline),
+            vec![("President", 1), ("VP Marketing", 2), ("VP Sales", 2)]
+        );
+        assert_eq!(outline.key_header, vec!["L1", "L2"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_subtree_transform() -> Result<()> {
+        let transform = SelectSubtreeTransform::new(SelectSubtreeTransformOptions {
+            path: vec!["President".into(), "VP Sales".into()],
+        });
+        let outline = transform.apply(org_chart())?;
+        assert_eq!(
+            levels(&outline),
+            vec![("VP Sales", 1), ("Manager B", 2), ("Manager C", 2)]
+        );
+        assert_eq!(outline.item[1].value, vec!["b"]);
+        assert_eq!(outline.key_header, vec!["L2", "L3"]);
+
+        let transform = SelectSubtreeTransform::new(SelectSubtreeTransformOptions {
+            path: vec!["President".into(), "VP Legal".into()],
+        });
+        assert_eq!(
+            transform.apply(org_chart()).unwrap_err().to_string(),
+            "No item at path 'President/VP Legal'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_drop_subtree_transform() -> Result<()> {
+        let transform = DropSubtreeTransform::new(DropSubtreeTransformOptions {
+            pattern: Regex::new("^VP M|C$")?,
+        });
+        let outline = transform.apply(org_chart())?;
+        assert_eq!(
+            levels(&outline),
+            vec![("President", 1), ("VP Sales", 2), ("Manager B", 3)]
+        );
+        Ok(())
+    }
+}
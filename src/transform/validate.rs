@@ -0,0 +1,384 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::transform::Transform;
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// Represents a structural issue found in an `Outline`.
+///
+/// `index` is the position of the offending item in `Outline::item`.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// The level is 0; levels start from 1.
+    #[error("item #{} \"{key}\": level is 0", index + 1)]
+    ZeroLevel { index: usize, key: String },
+    /// The level increases by more than one from the previous item.
+    #[error("item #{} \"{key}\": level jumps from {from} to {to}", index + 1)]
+    LevelJump {
+        index: usize,
+        key: String,
+        from: u32,
+        to: u32,
+    },
+    /// The key is empty or whitespace only.
+    #[error("item #{}: key is empty", index + 1)]
+    EmptyKey { index: usize },
+    /// Another item with the same parent has the same key.
+    #[error("item #{} \"{key}\": duplicate key among siblings", index + 1)]
+    DuplicateSiblingKey { index: usize, key: String },
+    /// The number of values differs from the number of value headers.
+    #[error("item #{} \"{key}\": has {actual} values but {expected} value headers", index + 1)]
+    ValueCountMismatch {
+        index: usize,
+        key: String,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+/// What to do when validation finds issues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ValidationPolicy {
+    /// Fail with the list of issues.
+    Error,
+    /// Report the issues and continue, but fail on items at level 0.
+    Warn,
+    /// Repair level jumps and items at level 0 by clamping levels to one below the parent, and
+    /// warn about the other issues.
+    Clamp,
+    /// Repair items at level 0 by moving them to level 1 and level jumps by inserting
+    /// placeholder parents with an empty key, and warn about the other issues.
+    InsertParents,
+}
+
+/// Returns the structural issues found in `outline`, in item order.
+pub fn find_issues(outline: &Outline) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    // Indices of the current ancestors, and (parent index, key) pairs seen so far
+    let mut ancestors: Vec<usize> = Vec::new();
+    let mut sibling_keys: HashSet<(Option<usize>, &str)> = HashSet::new();
+    let mut previous_level = 0;
+
+    for (index, item) in outline.item.iter().enumerate() {
+        if item.level == 0 {
+            issues.push(ValidationIssue::ZeroLevel {
+                index,
+                key: item.key.clone(),
+            });
+        }
+        if item.level > previous_level + 1 {
+            issues.push(ValidationIssue::LevelJump {
+                index,
+                key: item.key.clone(),
+                from: previous_level,
+                to: item.level,
+            });
+        }
+        previous_level = item.level;
+
+        if item.key.trim().is_empty() {
+            issues.push(ValidationIssue::EmptyKey { index });
+        }
+
+        while let Some(&last) = ancestors.last() {
+            if outline.item[last].level < item.level {
+                break;
+            }
+            ancestors.pop();
+        }
+        if !item.key.trim().is_empty()
+            && !sibling_keys.insert((ancestors.last().copied(), item.key.as_str()))
+        {
+            issues.push(ValidationIssue::DuplicateSiblingKey {
+                index,
+                key: item.key.clone(),
+            });
+        }
+        ancestors.push(index);
+
+        if !outline.value_header.is_empty() && item.value.len() != outline.value_header.len() {
+            issues.push(ValidationIssue::ValueCountMismatch {
+                index,
+                key: item.key.clone(),
+                expected: outline.value_header.len(),
+                actual: item.value.len(),
+            });
+        }
+    }
+    issues
+}
+
+/// Changes the levels so that each item is at most one level below its parent.
+///
+/// The parent of an item is the nearest preceding item with a lower level, as in
+/// `Outline::to_tree`, so the shape of the tree is kept.
+fn clamp_levels(outline: &mut Outline) {
+    // (original level, repaired level) of the current ancestors
+    let mut ancestors: Vec<(u32, u32)> = Vec::new();
+    for item in outline.item.iter_mut() {
+        while ancestors
+            .last()
+            .is_some_and(|&(level, _)| level >= item.level)
+        {
+            ancestors.pop();
+        }
+        let level = ancestors.last().map_or(1, |&(_, repaired)| repaired + 1);
+        ancestors.push((item.level, level));
+        item.level = level;
+    }
+}
+
+/// Inserts items with an empty key and empty values to fill the missing levels before each
+/// level jump, after moving items at level 0 to level 1.
+fn insert_parents(outline: &mut Outline) {
+    let mut items = Vec::with_capacity(outline.item.len());
+    let mut previous_level = 0;
+    let placeholder_value = vec![String::new(); outline.value_header.len()];
+    for mut item in outline.item.drain(..) {
+        item.level = item.level.max(1);
+        for level in (previous_level + 1)..item.level {
+            items.push(OutlineItem::new("", level, placeholder_value.clone()));
+        }
+        previous_level = item.level;
+        items.push(item);
+    }
+    outline.item = items;
+}
+
+#[derive(Debug, Clone)]
+pub struct ValidateTransformOptions {
+    pub policy: ValidationPolicy,
+}
+
+/// Checks the structure of an outline and reports or repairs the issues found.
+pub struct ValidateTransform {
+    options: ValidateTransformOptions,
+    on_issue: Box<dyn Fn(&ValidationIssue)>,
+}
+
+impl ValidateTransform {
+    pub fn new(options: ValidateTransformOptions) -> Self {
+        ValidateTransform {
+            options,
+            on_issue: Box::new(|_| {}),
+        }
+    }
+
+    /// Sets the function the issues are passed to when the policy is not
+    /// [`ValidationPolicy::Error`]. By default, the issues are ignored.
+    pub fn with_on_issue(mut self, on_issue: impl Fn(&ValidationIssue) + 'static) -> Self {
+        self.on_issue = Box::new(on_issue);
+        self
+    }
+}
+
+impl Transform for ValidateTransform {
+    fn apply(&self, mut outline: Outline) -> Result<Outline> {
+        let issues = find_issues(&outline);
+        let policy = self.options.policy;
+        // Items at level 0 cannot be laid out, so they are only let through to be repaired
+        let fatal: Vec<String> = issues
+            .iter()
+            .filter(|i| {
+                policy == ValidationPolicy::Error
+                    || (policy == ValidationPolicy::Warn
+                        && matches!(i, ValidationIssue::ZeroLevel { .. }))
+            })
+            .map(|i| i.to_string())
+            .collect();
+        if !fatal.is_empty() {
+            bail!("Invalid outline:\n  {}", fatal.join("\n  "));
+        }
+        if policy == ValidationPolicy::Error {
+            return Ok(outline);
+        }
+
+        issues.iter().for_each(&self.on_issue);
+        let has_level_issue = issues.iter().any(|i| {
+            matches!(
+                i,
+                ValidationIssue::LevelJump { .. } | ValidationIssue::ZeroLevel { .. }
+            )
+        });
+        if has_level_issue && policy == ValidationPolicy::Clamp {
+            clamp_levels(&mut outline);
+        } else if has_level_issue && policy == ValidationPolicy::InsertParents {
+            insert_parents(&mut outline);
+        }
+        Ok(outline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn jumping_outline() -> Outline {
+        let mut outline = Outline::new();
+        outline.add_item("1", 1, vec![]);
+        outline.add_item("1.1.1", 3, vec![]);
+        outline.add_item("1.1.1.1", 4, vec![]);
+        outline.add_item("1.2", 3, vec![]);
+        outline.add_item("2", 1, vec![]);
+        outline
+    }
+
+    fn levels(outline: &Outline) -> Vec<(&str, u32)> {
+        outline
+            .item
+            .iter()
+            .map(|item| (item.key.as_str(), item.level))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_issues() {
+        let mut outline = Outline::new();
+        outline.value_header = vec!["V1".to_string()];
+        outline.add_item("1", 2, vec!["a".to_string()]);
+        outline.add_item("1.1", 3, vec!["a".to_string()]);
+        outline.add_item("1.1", 3, vec!["a".to_string()]);
+        outline.add_item(" ", 3, vec![]);
+        outline.add_item("1.1", 2, vec!["a".to_string()]);
+
+        assert_eq!(
+            find_issues(&outline),
+            vec![
+                ValidationIssue::LevelJump {
+                    index: 0,
+                    key: "1".to_string(),
+                    from: 0,
+                    to: 2
+                },
+                ValidationIssue::DuplicateSiblingKey {
+                    index: 2,
+                    key: "1.1".to_string()
+                },
+                ValidationIssue::EmptyKey { index: 3 },
+                ValidationIssue::ValueCountMismatch {
+                    index: 3,
+                    key: " ".to_string(),
+                    expected: 1,
+                    actual: 0
+                },
+            ]
+        );
+        assert_eq!(
+            find_issues(&outline)[0].to_string(),
+            "item #1 \"1\": level jumps from 0 to 2"
+        );
+    }
+
+    #[test]
+    fn test_validate_transform_error() {
+        let transform = ValidateTransform::new(ValidateTransformOptions {
+            policy: ValidationPolicy::Error,
+        });
+        let err = transform.apply(jumping_outline()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid outline:\n  item #2 \"1.1.1\": level jumps from 1 to 3"
+        );
+
+        let mut outline = Outline::new();
+        outline.add_item("1", 1, vec![]);
+        outline.add_item("1.1", 2, vec![]);
+        assert_eq!(transform.apply(outline.clone()).unwrap(), outline);
+    }
+
+    #[test]
+    fn test_validate_transform_warn() -> Result<()> {
+        let issues = Rc::new(RefCell::new(Vec::new()));
+        let transform = ValidateTransform::new(ValidateTransformOptions {
+            policy: ValidationPolicy::Warn,
+        })
+        .with_on_issue({
+            let issues = Rc::clone(&issues);
+            move |issue| issues.borrow_mut().push(issue.clone())
+        });
+        assert_eq!(transform.apply(jumping_outline())?, jumping_outline());
+        assert_eq!(*issues.borrow(), find_issues(&jumping_outline()));
+        assert_eq!(issues.borrow().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_transform_clamp() -> Result<()> {
+        let transform = ValidateTransform::new(ValidateTransformOptions {
+            policy: ValidationPolicy::Clamp,
+        });
+        let outline = transform.apply(jumping_outline())?;
+        assert_eq!(
+            levels(&outline),
+            vec![("1", 1), ("1.1.1", 2), ("1.1.1.1", 3), ("1.2", 2), ("2", 1)]
+        );
+        assert!(find_issues(&outline).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_transform_insert_parents() -> Result<()> {
+        let transform = ValidateTransform::new(ValidateTransformOptions {
+            policy: ValidationPolicy::InsertParents,
+        });
+        let outline = transform.apply(jumping_outline())?;
+        assert_eq!(
+            levels(&outline),
+            vec![
+                ("1", 1),
+                ("", 2),
+                ("1.1.1", 3),
+                ("1.1.1.1", 4),
+                ("1.2", 3),
+                ("2", 1)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_transform_zero_level() -> Result<()> {
+        let mut outline = Outline::new();
+        outline.value_header = vec!["V1".to_string()];
+        outline.add_item("a", 0, vec!["1".to_string()]);
+        outline.add_item("b", 2, vec!["2".to_string()]);
+        assert_eq!(
+            find_issues(&outline)[0],
+            ValidationIssue::ZeroLevel {
+                index: 0,
+                key: "a".to_string()
+            }
+        );
+
+        let transform = |policy| ValidateTransform::new(ValidateTransformOptions { policy });
+        let err = transform(ValidationPolicy::Warn)
+            .apply(outline.clone())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid outline:\n  item #1 \"a\": level is 0"
+        );
+
+        let clamped = transform(ValidationPolicy::Clamp).apply(outline.clone())?;
+        assert_eq!(levels(&clamped), vec![("a", 1), ("b", 2)]);
+
+        // Placeholders have as many values as value headers
+        let repaired = transform(ValidationPolicy::InsertParents).apply(outline)?;
+        assert_eq!(levels(&repaired), vec![("a", 1), ("b", 2)]);
+        let mut jumping = jumping_outline();
+        jumping.value_header = vec!["V1".to_string()];
+        for item in &mut jumping.item {
+            item.value = vec!["x".into()];
+        }
+        let repaired = transform(ValidationPolicy::InsertParents).apply(jumping)?;
+        assert_eq!(repaired.item[1].value, vec![Value::default()]);
+        assert!(!find_issues(&repaired)
+            .iter()
+            .any(|i| matches!(i, ValidationIssue::ValueCountMismatch { .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use crate::outline::{Outline, OutlineItem};
+use crate::transform::{ItemTransform, Transform};
+use crate::value::{Value, ValueType};
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone)]
+pub struct ValueTypeTransformOptions {
+    /// The type of each value column, in order.
+    ///
+    /// The last type applies to the remaining columns. An empty list leaves values untouched.
+    pub value_type: Vec<ValueType>,
+}
+
+/// Converts textual values to typed values according to per-column type declarations.
+pub struct ValueTypeTransform {
+    options: ValueTypeTransformOptions,
+}
+
+impl ValueTypeTransform {
+    pub fn new(options: ValueTypeTransformOptions) -> Self {
+        ValueTypeTransform { options }
+    }
+
+    fn column_type(&self, index: usize) -> Option<ValueType> {
+        self.options
+            .value_type
+            .get(index)
+            .or_else(|| self.options.value_type.last())
+            .copied()
+    }
+}
+
+impl ItemTransform for ValueTypeTransform {
+    fn apply_item(&self, header: &Outline, mut item: OutlineItem) -> Result<OutlineItem> {
+        for (index, value) in item.value.iter_mut().enumerate() {
+            let (Some(ty), Value::Text(text)) = (self.column_type(index), &*value) else {
+                continue;
+            };
+            match Value::parse_as(text, ty) {
+                Some(typed) => *value = typed,
+                None => {
+                    let column = header
+                        .value_header
+                        .get(index)
+                        .filter(|header| !header.is_empty())
+                        .cloned()
+                        .unwrap_or_else(|| format!("#{}", index + 1));
+                    bail!(
+                        "Cannot convert value '{}' of item '{}' (column {}) to {:?}",
+                        text,
+                        item.key,
+                        column,
+                        ty
+                    );
+                }
+            }
+        }
+        Ok(item)
+    }
+}
+
+impl Transform for ValueTypeTransform {
+    fn apply(&self, mut outline: Outline) -> Result<Outline> {
+        let header = outline.header();
+        outline.item = outline
+            .item
+            .into_iter()
+            .map(|item| self.apply_item(&header, item))
+            .collect::<Result<_>>()?;
+        Ok(outline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outline::OutlineItem;
+    use chrono::{NaiveDate, TimeDelta};
+
+    fn sample_outline() -> Outline {
+        Outline {
+            key_header: vec![],
+            value_header: vec!["Cost".into(), "Start".into(), "Work".into()],
+            item: vec![
+                OutlineItem::new(
+                    "1",
+                    1,
+                    vec!["100".into(), "2025-01-31".into(), "8:30".into()],
+                ),
+                OutlineItem::new("1.1", 2, vec!["".into(), "".into(), "PT1H0M0S".into()]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_value_type_transform_auto() -> Result<()> {
+        let transform = ValueTypeTransform::new(ValueTypeTransformOptions {
+            value_type: vec![ValueType::Auto],
+        });
+        let outline = transform.apply(sample_outline())?;
+        assert_eq!(
+            outline.item[0].value,
+            vec![
+                Value::Integer(100),
+                Value::Date(NaiveDate::from_ymd_opt(2025, 1, 31).unwrap()),
+                Value::Text("8:30".into()),
+            ]
+        );
+        assert_eq!(outline.item[1].value[0], "");
+        assert_eq!(
+            outline.item[1].value[2],
+            Value::Duration(TimeDelta::hours(1))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_type_transform_per_column() -> Result<()> {
+        let transform = ValueTypeTransform::new(ValueTypeTransformOptions {
+            value_type: vec![ValueType::Number, ValueType::Text, ValueType::Duration],
+        });
+        let outline = transform.apply(sample_outline())?;
+        assert_eq!(outline.item[0].value[0], Value::Number(100.0));
+        assert_eq!(outline.item[0].value[1], "2025-01-31");
+        assert_eq!(
+            outline.item[0].value[2],
+            Value::Duration(TimeDelta::minutes(510))
+        );
+
+        let transform = ValueTypeTransform::new(ValueTypeTransformOptions { value_type: vec![] });
+        assert_eq!(transform.apply(sample_outline())?, sample_outline());
+        Ok(())
+    }
+
+    #[test]
+    fn test_value_type_transform_error() {
+        let transform = ValueTypeTransform::new(ValueTypeTransformOptions {
+            value_type: vec![ValueType::Integer, ValueType::Boolean],
+        });
+        let err = transform.apply(sample_outline()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot convert value '2025-01-31' of item '1' (column Start) to Boolean"
+        );
+    }
+}
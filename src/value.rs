@@ -0,0 +1,492 @@
+//! Typed values of outline items.
+//! 
+//! Parsers produce `Value::Text` for every value. The other variants are
+//! obtained through type inference or per-column type declarations (see
+//! [`crate::transform::value_type`]) and are written to XLSX as native
+//! numbers, booleans and dates.
+
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// A single value associated with an `OutlineItem`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ValueRepr", into = "ValueRepr")]
+pub enum Value {
+    /// Plain text.
+    Text(String),
+    /// A whole number.
+    Integer(i64),
+    /// A floating-point number.
+    Number(f64),
+    /// A boolean (`true` / `false`).
+    Boolean(bool),
+    /// A calendar date without time.
+    Date(NaiveDate),
+    /// A date and time without time zone.
+    DateTime(NaiveDateTime),
+    /// A length of time.
+    Duration(TimeDelta),
+}
+
+/// The type a value column is converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ValueType {
+    /// Infer the type of each value from its text.
+    Auto,
+    Text,
+    Integer,
+    Number,
+    Boolean,
+    Date,
+    #[value(name = "datetime")]
+    DateTime,
+    Duration,
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Text(String::new())
+    }
+}
+
+impl Value {
+    /// Infers the type of `text` and returns the corresponding value.
+    ///
+    /// Recognizes `true`/`false`, integers (without leading zeros), decimal numbers,
+    /// ISO 8601 dates (`2025-01-31`), date-times (`2025-01-31T09:00:00`) and
+    /// durations (`PT8H30M0S`). Anything else is kept as text, including numbers that Excel
+    /// cannot hold exactly: numbers out of the range of `f64`, integers beyond 2^53 and
+    /// numbers with more than 15 significant digits, such as long IDs.
+    pub fn infer(text: &str) -> Value {
+        [
+            ValueType::Boolean,
+            ValueType::Integer,
+            ValueType::Number,
+            ValueType::Date,
+            ValueType::DateTime,
+            ValueType::Duration,
+        ]
+        .iter()
+        .find_map(|ty| Self::parse_strict(text, *ty))
+        .filter(|value| !loses_precision(text, value))
+        .unwrap_or_else(|| Value::Text(text.to_string()))
+    }
+
+    /// Converts `text` to a value of type `ty`.
+    ///
+    /// Empty text is kept as an empty `Value::Text` so that it still renders as an empty cell.
+    ///
+    /// # Returns
+    ///
+    /// `None` if `text` is not empty and cannot be read as `ty`.
+    pub fn parse_as(text: &str, ty: ValueType) -> Option<Value> {
+        match ty {
+            ValueType::Auto => Some(Self::infer(text)),
+            ValueType::Text => Some(Value::Text(text.to_string())),
+            _ if text.trim().is_empty() => Some(Value::Text(text.to_string())),
+            ValueType::Duration => Self::parse_strict(text, ty)
+                .or_else(|| parse_clock_duration(text.trim()).map(Value::Duration)),
+            _ => Self::parse_strict(text, ty),
+        }
+    }
+
+    fn parse_strict(text: &str, ty: ValueType) -> Option<Value> {
+        let text = text.trim();
+        match ty {
+            ValueType::Auto | ValueType::Text => None,
+            ValueType::Boolean => match text.to_ascii_lowercase().as_str() {
+                "true" => Some(Value::Boolean(true)),
+                "false" => Some(Value::Boolean(false)),
+                _ => None,
+            },
+            ValueType::Integer => {
+                if integer_regex().is_match(text) {
+                    text.parse().ok().map(Value::Integer)
+                } else {
+                    None
+                }
+            }
+            ValueType::Number => {
+                // Numbers out of range parse as infinity, which is no valid JSON nor cell value
+                if number_regex().is_match(text) {
+                    text.parse::<f64>()
+                        .ok()
+                        .filter(|n| n.is_finite())
+                        .map(Value::Number)
+                } else {
+                    None
+                }
+            }
+            ValueType::Date => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .map(Value::Date),
+            ValueType::DateTime => DATETIME_FORMATS
+                .iter()
+                .find_map(|f| NaiveDateTime::parse_from_str(text, f).ok())
+                .map(Value::DateTime),
+            ValueType::Duration => parse_iso8601_duration(text).map(Value::Duration),
+        }
+    }
+
+    /// Returns the value, with the type of text inferred by [`Value::infer`].
+    pub fn inferred(&self) -> Value {
+        match self {
+            Value::Text(text) => Self::infer(text),
+            value => value.clone(),
+        }
+    }
+
+    /// Returns the value as a number, if it is numeric.
+    ///
+    /// Durations are returned in seconds.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Number(n) => Some(*n),
+            Value::Duration(d) => Some(d.num_milliseconds() as f64 / 1000.0),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the value is an empty text.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Value::Text(s) if s.is_empty())
+    }
+}
+
+const DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+fn integer_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^[+-]?(0|[1-9][0-9]*)$").unwrap())
+}
+
+fn number_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^[+-]?((0|[1-9][0-9]*)(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?$").unwrap()
+    })
+}
+
+/// The number of significant digits an Excel number holds exactly.
+const MAX_SIGNIFICANT_DIGITS: usize = 15;
+
+/// Returns true if `value`, read from `text`, cannot be written as an Excel number without
+/// changing its digits.
+fn loses_precision(text: &str, value: &Value) -> bool {
+    match value {
+        Value::Integer(i) => i.unsigned_abs() > 1 << 53,
+        Value::Number(_) => {
+            let mantissa = text.trim().split(['e', 'E']).next().unwrap_or_default();
+            let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+            let significant = digits.trim_start_matches('0').trim_end_matches('0');
+            significant.len() > MAX_SIGNIFICANT_DIGITS
+        }
+        _ => false,
+    }
+}
+
+fn iso8601_duration_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"^(?P<sign>-)?P(?:(?P<d>[0-9]+)D)?(?:T(?:(?P<h>[0-9]+)H)?(?:(?P<m>[0-9]+)M)?(?:(?P<s>[0-9]+(?:\.[0-9]+)?)S)?)?$",
+        )
+        .unwrap()
+    })
+}
+
+/// Parses an ISO 8601 duration such as `PT8H0M0S` or `P1DT2H`.
+fn parse_iso8601_duration(text: &str) -> Option<TimeDelta> {
+    let captures = iso8601_duration_regex().captures(text)?;
+    if ["d", "h", "m", "s"]
+        .iter()
+        .all(|name| captures.name(name).is_none())
+    {
+        return None;
+    }
+    let field = |name: &str| -> f64 {
+        captures
+            .name(name)
+            .and_then(|m| m.as_str().parse().ok())
+            .unwrap_or(0.0)
+    };
+    let seconds = field("d") * 86400.0 + field("h") * 3600.0 + field("m") * 60.0 + field("s");
+    let delta = TimeDelta::milliseconds((seconds * 1000.0).round() as i64);
+    Some(if captures.name("sign").is_some() {
+        -delta
+    } else {
+        delta
+    })
+}
+
+/// Parses a clock-style duration such as `8:30` or `-12:00:15`.
+fn parse_clock_duration(text: &str) -> Option<TimeDelta> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let parts: Vec<&str> = text.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    let mut seconds: i64 = 0;
+    for part in &parts {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        seconds = seconds * 60 + part.parse::<i64>().ok()?;
+    }
+    if parts.len() == 2 {
+        seconds *= 60;
+    }
+    let delta = TimeDelta::seconds(seconds);
+    Some(if negative { -delta } else { delta })
+}
+
+/// Formats a duration as ISO 8601 (e.g. `PT8H30M0S`), the format read back by `Value::infer`.
+fn format_iso8601_duration(delta: &TimeDelta, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let sign = if *delta < TimeDelta::zero() { "-" } else { "" };
+    let millis = delta.num_milliseconds().abs();
+    let (hours, rest) = (millis / 3_600_000, millis % 3_600_000);
+    let (minutes, rest) = (rest / 60_000, rest % 60_000);
+    if rest % 1000 == 0 {
+        write!(f, "{}PT{}H{}M{}S", sign, hours, minutes, rest / 1000)
+    } else {
+        write!(
+            f,
+            "{}PT{}H{}M{}S",
+            sign,
+            hours,
+            minutes,
+            rest as f64 / 1000.0
+        )
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Text(s) => write!(f, "{}", s),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            Value::DateTime(dt) => write!(f, "{}", dt.format("%Y-%m-%dT%H:%M:%S%.f")),
+            Value::Duration(d) => format_iso8601_duration(d, f),
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+impl PartialEq<str> for Value {
+    fn eq(&self, other: &str) -> bool {
+        matches!(self, Value::Text(s) if s == other)
+    }
+}
+
+impl PartialEq<&str> for Value {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<String> for Value {
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+/// JSON representation of a `Value`.
+///
+/// Text, numbers and booleans map to the corresponding JSON scalars; dates,
+/// date-times and durations are written as `{"date": "2025-01-31"}`,
+/// `{"datetime": "2025-01-31T09:00:00"}` and `{"duration": "PT8H0M0S"}`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ValueRepr {
+    Text(String),
+    Integer(i64),
+    Number(f64),
+    Boolean(bool),
+    Tagged(TaggedValueRepr),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TaggedValueRepr {
+    Date(String),
+    DateTime(String),
+    Duration(String),
+}
+
+impl From<Value> for ValueRepr {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Text(s) => ValueRepr::Text(s),
+            Value::Integer(i) => ValueRepr::Integer(i),
+            Value::Number(n) => ValueRepr::Number(n),
+            Value::Boolean(b) => ValueRepr::Boolean(b),
+            Value::Date(_) => ValueRepr::Tagged(TaggedValueRepr::Date(value.to_string())),
+            Value::DateTime(_) => ValueRepr::Tagged(TaggedValueRepr::DateTime(value.to_string())),
+            Value::Duration(_) => ValueRepr::Tagged(TaggedValueRepr::Duration(value.to_string())),
+        }
+    }
+}
+
+impl TryFrom<ValueRepr> for Value {
+    type Error = String;
+
+    fn try_from(repr: ValueRepr) -> Result<Self, Self::Error> {
+        let (text, ty) = match repr {
+            ValueRepr::Text(s) => return Ok(Value::Text(s)),
+            ValueRepr::Integer(i) => return Ok(Value::Integer(i)),
+            ValueRepr::Number(n) => return Ok(Value::Number(n)),
+            ValueRepr::Boolean(b) => return Ok(Value::Boolean(b)),
+            ValueRepr::Tagged(TaggedValueRepr::Date(s)) => (s, ValueType::Date),
+            ValueRepr::Tagged(TaggedValueRepr::DateTime(s)) => (s, ValueType::DateTime),
+            ValueRepr::Tagged(TaggedValueRepr::Duration(s)) => (s, ValueType::Duration),
+        };
+        Value::parse_strict(&text, ty).ok_or_else(|| format!("invalid {:?} value: {}", ty, text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_infer() {
+        assert_eq!(Value::infer("true"), Value::Boolean(true));
+        assert_eq!(Value::infer("FALSE"), Value::Boolean(false));
+        assert_eq!(Value::infer("42"), Value::Integer(42));
+        assert_eq!(Value::infer("-7"), Value::Integer(-7));
+        assert_eq!(Value::infer("1.5"), Value::Number(1.5));
+        assert_eq!(Value::infer("1e3"), Value::Number(1000.0));
+        assert_eq!(
+            Value::infer("2025-01-31"),
+            Value::Date(NaiveDate::from_ymd_opt(2025, 1, 31).unwrap())
+        );
+        assert_eq!(
+            Value::infer("2025-01-31T08:30:00"),
+            Value::DateTime(
+                NaiveDate::from_ymd_opt(2025, 1, 31)
+                    .unwrap()
+                    .and_hms_opt(8, 30, 0)
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            Value::infer("PT8H30M0S"),
+            Value::Duration(TimeDelta::minutes(510))
+        );
+        assert_eq!(Value::infer("P1D"), Value::Duration(TimeDelta::days(1)));
+
+        // Kept as text
+        assert_eq!(Value::infer("007"), "007");
+        assert_eq!(Value::infer("inf"), "inf");
+        assert_eq!(Value::infer("NaN"), "NaN");
+        assert_eq!(Value::infer("1e400"), "1e400");
+        assert_eq!(Value::infer("-1e400"), "-1e400");
+        assert_eq!(Value::infer("1234567890123456789"), "1234567890123456789");
+        assert_eq!(Value::infer("12345678901234567890"), "12345678901234567890");
+        assert_eq!(Value::infer("0.1234567890123456"), "0.1234567890123456");
+        assert_eq!(
+            Value::infer("9007199254740992"),
+            Value::Integer(9007199254740992)
+        );
+        assert_eq!(Value::infer("1e20"), Value::Number(1e20));
+        assert_eq!(Value::infer("0.000123"), Value::Number(0.000123));
+        assert_eq!(Value::infer("P"), "P");
+        assert_eq!(Value::infer("PT"), "PT");
+        assert_eq!(Value::infer("8:30"), "8:30");
+        assert_eq!(Value::infer("2025-13-01"), "2025-13-01");
+        assert_eq!(Value::infer(""), "");
+    }
+
+    #[test]
+    fn test_value_parse_as() {
+        assert_eq!(
+            Value::parse_as("42", ValueType::Number),
+            Some(Value::Number(42.0))
+        );
+        assert_eq!(Value::parse_as("42", ValueType::Text), Some("42".into()));
+        assert_eq!(Value::parse_as("", ValueType::Date), Some("".into()));
+        assert_eq!(Value::parse_as("abc", ValueType::Integer), None);
+        assert_eq!(Value::parse_as("1e400", ValueType::Number), None);
+        assert_eq!(
+            Value::parse_as("8:30", ValueType::Duration),
+            Some(Value::Duration(TimeDelta::minutes(510)))
+        );
+        assert_eq!(
+            Value::parse_as("-1:00:30", ValueType::Duration),
+            Some(Value::Duration(-TimeDelta::seconds(3630)))
+        );
+    }
+
+    #[test]
+    fn test_value_display_round_trip() {
+        for text in [
+            "abc",
+            "42",
+            "1.5",
+            "true",
+            "2025-01-31",
+            "2025-01-31T08:30:00",
+            "PT8H30M0S",
+            "-PT0H0M1.5S",
+        ] {
+            assert_eq!(Value::infer(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_value_json() {
+        let values = vec![
+            Value::Text("a".to_string()),
+            Value::Integer(1),
+            Value::Number(1.5),
+            Value::Boolean(true),
+            Value::infer("2025-01-31"),
+            Value::infer("2025-01-31T08:30:00"),
+            Value::infer("PT8H0M0S"),
+        ];
+        let json = serde_json::to_string(&values).unwrap();
+        assert_eq!(
+            json,
+            r#"["a",1,1.5,true,{"date":"2025-01-31"},{"datetime":"2025-01-31T08:30:00"},{"duration":"PT8H0M0S"}]"#
+        );
+        let read: Vec<Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, values);
+
+        assert!(serde_json::from_str::<Value>(r#"{"date":"yesterday"}"#).is_err());
+    }
+
+    #[test]
+    fn test_value_as_f64() {
+        assert_eq!(Value::Integer(2).as_f64(), Some(2.0));
+        assert_eq!(Value::Number(2.5).as_f64(), Some(2.5));
+        assert_eq!(Value::infer("PT1M").as_f64(), Some(60.0));
+        assert_eq!(Value::Text("2".to_string()).as_f64(), None);
+    }
+}